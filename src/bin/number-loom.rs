@@ -1,14 +1,19 @@
-use std::path::PathBuf;
+use std::{io::IsTerminal, path::PathBuf};
 
 use clap::Parser;
 use colored::Colorize;
 use number_loom::import;
-use number_loom::puzzle::Document;
+use number_loom::line_solve::{SeparationRule, SolveMode};
 use number_loom::puzzle::NonogramFormat;
 use number_loom::puzzle::PuzzleDynOps;
-use number_loom::puzzle::Solution;
 use number_loom::{export, grid_solve, gui};
 
+/// Pixels per cell in `--clue-image` output.
+const CLUE_IMAGE_SCALE: u32 = 20;
+
+/// Pixels per cell in `--disambiguate` image output.
+const DISAMBIG_IMAGE_SCALE: u32 = 20;
+
 #[derive(clap::Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
@@ -23,10 +28,37 @@ struct Args {
     #[arg(short, long, value_enum)]
     input_format: Option<NonogramFormat>,
 
+    /// Read the input image at 2x2 pixels per cell, so that cells split along a diagonal become
+    /// Trianogram caps
+    #[arg(long, conflicts_with = "input_format")]
+    image_triangles: bool,
+
+    /// Read the input as a chargrid, and fail on any character that isn't one of these, instead
+    /// of making up a color for it. With no value, allows just the characters with a built-in
+    /// meaning (like '.', '#', 'r', or '◢')
+    #[arg(
+        long,
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "",
+        conflicts_with_all = ["input_format", "image_triangles"]
+    )]
+    strict_chargrid: Option<String>,
+
     /// Format to emit as output
     #[arg(short, long, value_enum)]
     output_format: Option<NonogramFormat>,
 
+    /// Additional extensions to export alongside the output path (e.g. "png,html" writes
+    /// `name.png` and `name.html`), each in the format its extension implies
+    #[arg(long, value_delimiter = ',', requires = "output_path")]
+    also_emit: Vec<String>,
+
+    /// Leave the picture out of formats that can carry it along with the clues (webpbn's
+    /// `<solution>` block)
+    #[arg(long, requires = "output_path")]
+    clues_only: bool,
+
     /// Explain the solve process line-by-line.
     #[arg(short, long, action = clap::ArgAction::SetTrue)]
     trace_solve: bool,
@@ -35,8 +67,66 @@ struct Args {
     #[arg(long, default_value_t)]
     gui: bool,
 
+    /// Suggest single-cell changes that would make the puzzle less ambiguous. With an output
+    /// path, also writes the picture with the suggestions tinted in
     #[arg(long, default_value_t)]
     disambiguate: bool,
+
+    /// How close to the best suggestion another must come for `--disambiguate` to show it:
+    /// 1.0 shows only the best, and 0.0 shows every change that helps at all
+    #[arg(long, default_value_t = grid_solve::DEFAULT_DISAMBIG_THRESHOLD, requires = "disambiguate")]
+    disambig_threshold: f32,
+
+    /// Instead of line-solving, search for up to this many distinct solutions and print them
+    /// (exactly one means the puzzle is unique)
+    #[arg(
+        long,
+        conflicts_with_all = ["output_path", "disambiguate"],
+        value_parser = clap::value_parser!(u64).range(1..),
+    )]
+    solutions: Option<u64>,
+
+    /// Which adjacent blocks need background between them, when solving
+    #[arg(long, value_enum, default_value = "same-color")]
+    separation: SeparationRule,
+
+    /// Show a spinner while solving (the default when stderr is a terminal)
+    #[arg(long, overrides_with = "no_progress")]
+    progress: bool,
+
+    /// Never show a spinner while solving
+    #[arg(long, overrides_with = "progress")]
+    no_progress: bool,
+
+    /// Only print errors and results: no warnings, and no spinner
+    #[arg(short, long, default_value_t)]
+    quiet: bool,
+
+    /// The most sophisticated line-solving technique to use ("skim" alone is much weaker, and
+    /// "enumerate" replaces "scrub" with a slower, simpler technique, to double-check it)
+    #[arg(long, value_enum, default_value = "scrub")]
+    max_effort: SolveMode,
+
+    /// Start solving from this partly-filled-in chargrid (drawn with the puzzle's color
+    /// characters, with '?' for undecided cells), and show what it leads to
+    #[arg(long, conflicts_with = "output_path")]
+    from_partial: Option<PathBuf>,
+
+    /// Treat the input as a directory or zip file of puzzles, and write them all, empty, to this
+    /// path as a printable HTML sheet
+    #[arg(long, conflicts_with_all = ["output_path", "gui", "disambiguate", "solutions", "index"])]
+    sheet: Option<PathBuf>,
+
+    /// Treat the input as a directory or zip file of puzzles: solve each one, and write an
+    /// index of them, sorted by difficulty, to this path (HTML if it ends in ".html", JSON
+    /// otherwise)
+    #[arg(long, conflicts_with_all = ["output_path", "gui", "disambiguate", "solutions"])]
+    index: Option<PathBuf>,
+
+    /// Draw the puzzle's clues around an empty grid and save that as a PNG, without opening
+    /// the GUI
+    #[arg(long, conflicts_with = "output_path")]
+    clue_image: Option<PathBuf>,
 }
 
 fn main() -> std::io::Result<()> {
@@ -45,23 +135,109 @@ fn main() -> std::io::Result<()> {
     let input_path = match args.input_path {
         Some(ip) => ip,
         None => {
-            gui::edit_image(Document::from_solution(
-                Solution::blank_bw(20, 20),
-                "blank.xml".to_string(),
-            ));
+            gui::edit_image(gui::blank_document());
             return Ok(());
         }
     };
 
-    let mut document = import::load_path(&input_path, args.input_format);
-    for problem in document.quality_check() {
-        eprintln!("Warning: {}", problem);
+    if let Some(sheet_path) = &args.sheet {
+        let documents = match import::load_library(&input_path) {
+            Ok(documents) => documents,
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
+            }
+        };
+        if let Err(e) = std::fs::write(sheet_path, export::as_html_sheet(&documents)) {
+            eprintln!("Error writing {}: {:?}", sheet_path.display(), e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    if let Some(index_path) = &args.index {
+        let mut documents = match import::load_library(&input_path) {
+            Ok(documents) => documents,
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(1);
+            }
+        };
+        let options = grid_solve::SolveOptions {
+            separation: args.separation,
+            max_effort: args.max_effort,
+            ..Default::default()
+        };
+        let entries = export::library_index(&mut documents, &options);
+        let contents = if index_path.extension().is_some_and(|ext| ext == "html") {
+            export::as_index_html(&entries)
+        } else {
+            export::as_index_json(&entries)
+        };
+        if let Err(e) = std::fs::write(index_path, contents) {
+            eprintln!("Error writing {}: {:?}", index_path.display(), e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let loaded = if args.image_triangles {
+        import::load_image_path_as_triano(&input_path)
+    } else if let Some(chars) = &args.strict_chargrid {
+        let allowed = if chars.is_empty() {
+            import::known_char_grid_chars()
+        } else {
+            chars.chars().collect()
+        };
+        import::load_path_as_strict_char_grid(&input_path, &allowed)
+    } else {
+        import::load_path(&input_path, args.input_format)
+    };
+    let mut document = match loaded {
+        Ok(document) => document,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            std::process::exit(1);
+        }
+    };
+    if !args.quiet {
+        for problem in document.quality_check(args.separation) {
+            eprintln!("Warning: {}", problem);
+        }
     }
 
     if args.gui {
         // TODO: inside the GUI, check the solution is complete!
         gui::edit_image(document);
         return Ok(());
+    } else if let Some(path) = &args.clue_image {
+        let written = export::render_puzzle_png(document.puzzle(), None, CLUE_IMAGE_SCALE)
+            .and_then(|bytes| Ok(std::fs::write(path, bytes)?));
+        if let Err(e) = written {
+            eprintln!("Error writing {}: {:?}", path.display(), e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    } else if let Some(max) = args.solutions {
+        let max = max as usize;
+        let solutions = grid_solve::enumerate_solutions(document.puzzle(), max, args.separation);
+        for (i, solution) in solutions.iter().enumerate() {
+            println!("Solution {}:", i + 1);
+            for y in 0..solution.y_size() {
+                for x in 0..solution.x_size() {
+                    print!("{}", solution.palette[&solution.grid[x][y]].ch);
+                }
+                println!();
+            }
+            println!();
+        }
+        match solutions.len() {
+            n if n >= max => println!("Stopped after {n} solutions; there may be more."),
+            0 => println!("No solutions: the clues contradict each other."),
+            1 => println!("The solution is unique."),
+            n => println!("Found all {n} solutions."),
+        }
+        return Ok(());
     } else if args.disambiguate {
         let solution = document.take_solution().expect("impossible puzzle");
 
@@ -82,25 +258,24 @@ fn main() -> std::io::Result<()> {
             }
         }
 
-        let display_threshold = 1.0 - (1.0 - best_result) * 0.75;
+        let display_threshold =
+            grid_solve::disambig_display_threshold(&disambig, args.disambig_threshold);
 
-        let display_threshold = if best_result == 0.0 {
+        if best_result == 0.0 {
             println!("Able to completely disambiguate with a one-cell change!");
-            0.0
         } else {
             println!(
                 "Best improvement brings ambiguities to {:0}%; showing everything {:0}% or better",
                 best_result * 100.0,
                 display_threshold * 100.0
             );
-
-            display_threshold
-        };
+        }
 
         for y in 0..solution.y_size() {
             for x in 0..solution.x_size() {
                 let ci = &solution.palette[&solution.grid[x][y]];
-                if disambig[x][y].1 <= display_threshold {
+                let score = disambig[x][y].1;
+                if score <= display_threshold && score < 1.0 {
                     let new_ch = &solution.palette[&disambig[x][y].0].ch;
                     let new_ch = if *new_ch == ' ' { '☒' } else { *new_ch };
 
@@ -113,37 +288,145 @@ fn main() -> std::io::Result<()> {
             println!("");
         }
 
+        if let Some(path) = &args.output_path {
+            let written = export::as_disambig_image_bytes(
+                &solution,
+                &disambig,
+                display_threshold,
+                path,
+                DISAMBIG_IMAGE_SCALE,
+            )
+            .and_then(|bytes| Ok(std::fs::write(path, bytes)?));
+            if let Err(e) = written {
+                eprintln!("Error writing {}: {:?}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+
         return Ok(());
     }
 
     match args.output_path {
         Some(path) => {
-            export::save(&mut document, &path, args.output_format).unwrap();
+            let mut any_failed = false;
+            if let Err(e) = export::save(&mut document, &path, args.output_format, args.clues_only)
+            {
+                eprintln!("Error writing {}: {:?}", path.display(), e);
+                any_failed = true;
+            }
+
+            // Name the extra outputs after the input if the primary one is going to stdout.
+            let base = if path == PathBuf::from("-") {
+                &input_path
+            } else {
+                &path
+            };
+            for ext in &args.also_emit {
+                let extra_path = base.with_extension(ext.trim().trim_start_matches('.'));
+                // Never clobber the puzzle we're reading from.
+                if let (Ok(extra), Ok(input)) =
+                    (extra_path.canonicalize(), input_path.canonicalize())
+                    && extra == input
+                {
+                    eprintln!(
+                        "Not writing {}: it's the input file (give an output path to rename it)",
+                        extra_path.display()
+                    );
+                    any_failed = true;
+                    continue;
+                }
+                if let Err(e) = export::save(&mut document, &extra_path, None, args.clues_only) {
+                    eprintln!("Error writing {}: {:?}", extra_path.display(), e);
+                    any_failed = true;
+                }
+            }
+
+            if any_failed {
+                std::process::exit(1);
+            }
         }
 
         None => {
             let options = grid_solve::SolveOptions {
                 trace_solve: args.trace_solve,
-                display_cli_progress: true,
+                display_cli_progress: !args.quiet
+                    && !args.no_progress
+                    && (args.progress || std::io::stderr().is_terminal()),
+                separation: args.separation,
+                max_effort: args.max_effort,
                 ..Default::default()
             };
 
-            match document.puzzle().solve(&options) {
-                Ok(grid_solve::Report {
-                    solve_counts,
-                    cells_left,
-                    solution: _solution,
-                    solved_mask: _solved_mask,
-                }) => {
-                    if cells_left == 0 {
-                        eprintln!("Solved after {solve_counts}.");
+            let puzzle = document.puzzle();
+            let mut partial = match &args.from_partial {
+                Some(path) => {
+                    let text = std::fs::read_to_string(path)?;
+                    match import::char_grid_to_partial(&text, puzzle.palette(), '?') {
+                        Ok(partial) if partial.dim() == (puzzle.rows(), puzzle.cols()) => {
+                            Some(partial)
+                        }
+                        Ok(partial) => {
+                            let (height, width) = partial.dim();
+                            eprintln!(
+                                "Error: {} is {width}x{height}, but the puzzle is {}x{}",
+                                path.display(),
+                                puzzle.cols(),
+                                puzzle.rows()
+                            );
+                            std::process::exit(1);
+                        }
+                        Err(e) => {
+                            eprintln!("Error reading {}: {:?}", path.display(), e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                None => None,
+            };
+
+            let start = std::time::Instant::now();
+            let result = match &mut partial {
+                Some(partial) => puzzle.partial_solve(partial, &options),
+                None => puzzle.solve(&options),
+            };
+            let elapsed_ms = start.elapsed().as_millis();
+            match result {
+                Ok(report) => {
+                    let grid_solve::Report {
+                        solve_counts,
+                        cells_left,
+                        ..
+                    } = &report;
+                    if let Some(partial) = &partial {
+                        for row in partial.rows() {
+                            let line: String = row
+                                .iter()
+                                .map(|cell| match cell.known_or() {
+                                    Some(color) => puzzle.palette()[&color].ch,
+                                    None => '?',
+                                })
+                                .collect();
+                            println!("{line}");
+                        }
+                    }
+                    if *cells_left == 0 {
+                        eprintln!(
+                            "Solved after {solve_counts} in {elapsed_ms} ms. \
+                            Difficulty: {:.1} ({}).",
+                            report.difficulty(),
+                            report.difficulty_band().name()
+                        );
                     } else {
                         eprintln!(
-                            "Unable to solve. Performed {solve_counts}; {cells_left} cells left."
+                            "Unable to solve. Performed {solve_counts} in {elapsed_ms} ms; \
+                            {cells_left} cells left."
                         );
                     }
                 }
                 Err(e) => {
+                    if let Some(contradiction) = grid_solve::Contradiction::find(&e) {
+                        eprintln!("Unsolvable: {contradiction}.");
+                    }
                     eprintln!("Error: {:?}", e);
                     std::process::exit(1);
                 }
@@ -0,0 +1,144 @@
+//! Where everything goes in the strips of clues beside the grid, separately from how it's drawn.
+//! The GUI paints a layout with egui, and `export::render_puzzle_png` paints the same layout into
+//! an image, so clue layouts can be produced without a window (for docs, listings, and tests).
+
+use crate::puzzle::{Clue, ColorInfo, Puzzle};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    /// Row clues, to the left of the grid.
+    Horizontal,
+    /// Column clues, above the grid.
+    Vertical,
+}
+
+/// Space between the clues and the grid.
+pub const PUZZ_PADDING: f32 = 10.0;
+
+/// One clue box, or one cap (which is drawn as a triangle).
+pub struct ClueBox<'a> {
+    pub lane: usize,
+    /// Which of the lane's clues this box shows part of.
+    pub clue: usize,
+    /// The top-left corner, relative to the top-left of the strip.
+    pub min: (f32, f32),
+    pub side: f32,
+    pub color_info: &'a ColorInfo,
+    /// `None` for a cap.
+    pub count: Option<u16>,
+}
+
+pub struct ClueStrip<'a> {
+    pub size: (f32, f32),
+    pub boxes: Vec<ClueBox<'a>>,
+    /// How far from the start of the strip each lane's clues begin; the forced-line star goes
+    /// just before this.
+    pub lane_starts: Vec<f32>,
+}
+
+/// Lays out the row (`Horizontal`) or column (`Vertical`) clues, `scale` units per cell. The clues
+/// are pushed up against the grid side of the strip.
+pub fn layout_clues<C: Clue>(
+    puzzle: &Puzzle<C>,
+    scale: f32,
+    orientation: Orientation,
+    room_for_star: bool,
+) -> ClueStrip<'_> {
+    let between_clues = scale * 0.5;
+    let box_side = scale * 0.9;
+    let box_margin = (scale - box_side) / 2.0;
+
+    let lanes = match orientation {
+        Orientation::Horizontal => &puzzle.rows,
+        Orientation::Vertical => &puzzle.cols,
+    };
+
+    let mut max_size: f32 = 0.0;
+    for line_clues in lanes {
+        let mut this_size = 0.0;
+        for clue in line_clues {
+            this_size += box_side * (clue.express(puzzle).len() as f32) + between_clues;
+        }
+        max_size = max_size.max(this_size);
+    }
+    max_size += PUZZ_PADDING;
+    if room_for_star {
+        max_size += scale;
+    }
+
+    let mut boxes = vec![];
+    let mut lane_starts = vec![];
+    for (lane, line_clues) in lanes.iter().enumerate() {
+        let mut current_pos = max_size - PUZZ_PADDING;
+        for (clue_idx, clue) in line_clues.iter().enumerate().rev() {
+            for (color_info, count) in clue.express(puzzle).into_iter().rev() {
+                current_pos -= box_side;
+                let across = lane as f32 * scale + box_margin;
+                boxes.push(ClueBox {
+                    lane,
+                    clue: clue_idx,
+                    min: match orientation {
+                        Orientation::Horizontal => (current_pos, across),
+                        Orientation::Vertical => (across, current_pos),
+                    },
+                    side: box_side,
+                    color_info,
+                    count,
+                });
+            }
+            current_pos -= between_clues;
+        }
+        lane_starts.push(current_pos);
+    }
+
+    let across = scale * lanes.len() as f32;
+    ClueStrip {
+        size: match orientation {
+            Orientation::Horizontal => (max_size, across),
+            Orientation::Vertical => (across, max_size),
+        },
+        boxes,
+        lane_starts,
+    }
+}
+
+/// Black or white, whichever shows up better on `rgb`.
+pub fn text_rgb_on((r, g, b): (u8, u8, u8)) -> (u8, u8, u8) {
+    if r as u16 + g as u16 + b as u16 > 384 {
+        (0, 0, 0)
+    } else {
+        (255, 255, 255)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::DynPuzzle;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 0.001
+    }
+
+    #[test]
+    fn clue_boxes_hug_the_grid() {
+        let solution = crate::import::char_grid_to_solution("#.##\n....\n");
+        let DynPuzzle::Nono(puzzle) = solution.to_puzzle() else {
+            panic!("expected a nonogram");
+        };
+        let strip = layout_clues(&puzzle, 10.0, Orientation::Horizontal, false);
+
+        // The widest row has two clues: two 9-wide boxes, each followed by a 5-wide gap.
+        assert!(close(strip.size.0, 28.0 + PUZZ_PADDING));
+        assert!(close(strip.size.1, 20.0));
+        assert_eq!(strip.boxes.len(), 2);
+        assert!(strip.boxes.iter().all(|b| b.lane == 0));
+        // Clues are laid out from the grid outward.
+        assert_eq!(strip.boxes[0].count, Some(2));
+        assert!(close(strip.boxes[0].min.0, 19.0));
+        assert_eq!(strip.boxes[1].count, Some(1));
+        assert!(close(strip.boxes[1].min.0, 5.0));
+        assert!(close(strip.lane_starts[0], 0.0));
+        assert!(close(strip.lane_starts[1], 28.0));
+    }
+}
@@ -1,18 +1,30 @@
-use std::path::{Path, PathBuf};
+use std::{
+    cmp::{Ordering, max, min},
+    path::{Path, PathBuf},
+};
 
 use axohtml::{html, text};
 use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+use itertools::Itertools;
 
 use crate::{
-    formats::woven::to_woven,
-    puzzle::{self, Clue, Document, NonogramFormat, Puzzle, Solution},
+    formats::woven::{to_json, to_woven},
+    grid_solve::{DifficultyBand, SolveOptions},
+    puzzle::{
+        self, BACKGROUND, Clue, Color, Corner, Document, DynPuzzle, NonogramFormat, Puzzle,
+        PuzzleDynOps, Solution,
+    },
 };
 
+/// With `clues_only`, formats that can carry the picture along with the clues (webpbn) leave it
+/// out.
 pub fn to_bytes(
     document: &mut Document,
     file_name: Option<String>,
     format: Option<NonogramFormat>,
+    clues_only: bool,
 ) -> anyhow::Result<Vec<u8>> {
+    use crate::formats::nin::as_nin;
     use crate::formats::olsak::{as_olsak_nono, as_olsak_triano};
     use crate::formats::webpbn::as_webpbn;
     let format = format.unwrap_or_else(|| {
@@ -26,15 +38,30 @@ pub fn to_bytes(
 
     let bytes = if format == NonogramFormat::Image {
         let file_name = file_name.expect("need file name to pick image format");
-        as_image_bytes(document.solution()?, file_name)?
+        let solution = document.solution()?;
+        // Caps need more than one pixel per cell; 2x2 is the smallest that keeps the diagonal.
+        let scale = match solution.clue_style {
+            puzzle::ClueStyle::Nono => 1,
+            puzzle::ClueStyle::Triano => 2,
+        };
+        as_image_bytes(solution, file_name, scale)?
     } else {
         match format {
             NonogramFormat::Olsak => document.puzzle().specialize(as_olsak_nono, as_olsak_triano),
-            NonogramFormat::Webpbn => as_webpbn(document),
+            NonogramFormat::Webpbn => as_webpbn(document, !clues_only),
             NonogramFormat::Html => document.puzzle().specialize(as_html, as_html),
+            NonogramFormat::ClueList => document.puzzle().specialize(as_clue_text, as_clue_text),
+            NonogramFormat::Nin => document.puzzle().specialize(as_nin, |_| {
+                anyhow::bail!("the .nin format doesn't support Trianograms")
+            })?,
             NonogramFormat::Image => panic!(),
             NonogramFormat::Woven => to_woven(document)?,
-            NonogramFormat::CharGrid => as_char_grid(document.solution()?),
+            NonogramFormat::Json => to_json(document)?,
+            NonogramFormat::CharGrid => {
+                let mut solution = document.solution()?.clone();
+                puzzle::assign_palette_chars(&mut solution);
+                as_char_grid(&solution)
+            }
         }
         .into_bytes()
     };
@@ -46,8 +73,14 @@ pub fn save(
     document: &mut Document,
     path: &PathBuf,
     format: Option<NonogramFormat>,
+    clues_only: bool,
 ) -> anyhow::Result<()> {
-    let bytes = to_bytes(document, Some(path.to_str().unwrap().to_string()), format)?;
+    let bytes = to_bytes(
+        document,
+        Some(path.to_str().unwrap().to_string()),
+        format,
+        clues_only,
+    )?;
 
     if path == &PathBuf::from("-") {
         use std::io::Write;
@@ -59,14 +92,7 @@ pub fn save(
     Ok(())
 }
 
-pub fn as_html<C: Clue>(puzzle: &Puzzle<C>) -> String {
-    let html: axohtml::dom::DOMTree<String> = html!(
-        <html>
-            <head>
-            <title></title>
-            <style>
-            {text!(
-"
+const HTML_STYLE: &str = "
 table, td, th {
     border-collapse: collapse;
 }
@@ -100,57 +126,330 @@ table td:last-child {
   font-family: courier;
   padding-right: 6px;
 }
+.sheet-item {
+  display: inline-block;
+  vertical-align: top;
+  margin: 0 2em 2em 0;
+  break-inside: avoid;
+  page-break-inside: avoid;
+}
+.caption {
+  font-family: sans-serif;
+  margin-bottom: 0.5em;
+}
+";
 
-
-    ")}
-            </style>
-            </head>
-            <body>
-                <table>
-                    <thead>
-                        <tr>
-                        <th></th>
-                        { puzzle.cols.iter().map(|col| html!(<th class="col">{
-                            col.iter().map(|clue| html!(<div style=(clue.html_color(puzzle))>{text!("{} ", clue.html_text(puzzle))} </div>))
-                        }</th>))}
-                        </tr>
-                    </thead>
-                    <tbody>
-                    {
-                        puzzle.rows.iter().map(|row| html!(<tr><th class="row">{
-                            row.iter().map(|clue| html!(<span style=(clue.html_color(puzzle))>{text!("{} ", clue.html_text(puzzle))} </span>))
-                        }</th>
-                        {
-                            puzzle.cols.iter().map(|_| html!(<td></td>))
-                        }
-                        </tr>))
-                    }
-                    </tbody>
-                </table>
-            </body>
-        </html>
+fn html_table<C: Clue>(puzzle: &Puzzle<C>) -> String {
+    let table: axohtml::dom::DOMTree<String> = html!(
+        <table>
+            <thead>
+                <tr>
+                <th></th>
+                { puzzle.cols.iter().map(|col| html!(<th class="col">{
+                    col.iter().map(|clue| html!(<div style=(clue.html_color(puzzle))>{text!("{} ", clue.html_text(puzzle))} </div>))
+                }</th>))}
+                </tr>
+            </thead>
+            <tbody>
+            {
+                puzzle.rows.iter().map(|row| html!(<tr><th class="row">{
+                    row.iter().map(|clue| html!(<span style=(clue.html_color(puzzle))>{text!("{} ", clue.html_text(puzzle))} </span>))
+                }</th>
+                {
+                    puzzle.cols.iter().map(|_| html!(<td></td>))
+                }
+                </tr>))
+            }
+            </tbody>
+        </table>
     );
 
-    html.to_string()
+    table.to_string()
+}
+
+const INDEX_STYLE: &str = "
+table {
+    border-collapse: collapse;
+}
+td, th {
+    border: 1px solid black;
+    padding: 2px 8px;
+    text-align: left;
+}
+";
+
+/// Escapes `s` for use as HTML (or XML) text or as a quoted attribute value.
+pub(crate) fn html_escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&#39;"),
+            _ => res.push(c),
+        }
+    }
+    res
+}
+
+fn html_page(title: &str, style: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html><head><title>{}</title><style>{style}</style></head><body>{body}</body></html>",
+        html_escape(title)
+    )
+}
+
+pub fn as_html<C: Clue>(puzzle: &Puzzle<C>) -> String {
+    html_page("", HTML_STYLE, &html_table(puzzle))
+}
+
+/// Lists the clues line-by-line, like "Row 1: 3 2 1". Counts are followed by color names, unless
+/// there's only one foreground color; caps are written with their characters (e.g. ◢).
+pub fn as_clue_text<C: Clue>(puzzle: &Puzzle<C>) -> String {
+    let foreground_colors = puzzle
+        .palette
+        .values()
+        .filter(|ci| ci.color != BACKGROUND && ci.corner.is_none())
+        .count();
+    let show_names = foreground_colors > 1;
+
+    let mut res = String::new();
+    for (label, lines) in [("Row", &puzzle.rows), ("Column", &puzzle.cols)] {
+        if !res.is_empty() {
+            res.push('\n');
+        }
+        for (idx, line) in lines.iter().enumerate() {
+            let clues: Vec<String> = line
+                .iter()
+                .map(|clue| {
+                    clue.express(puzzle)
+                        .into_iter()
+                        .map(|(ci, count)| match count {
+                            Some(count) if show_names => format!("{count} {}", ci.name),
+                            Some(count) => count.to_string(),
+                            None => ci.ch.to_string(),
+                        })
+                        .collect::<String>()
+                })
+                .collect();
+            let clues = if clues.is_empty() {
+                "0".to_string()
+            } else {
+                clues.join(if show_names { ", " } else { " " })
+            };
+            res.push_str(&format!("{label} {}: {clues}\n", idx + 1));
+        }
+    }
+    res
+}
+
+/// Lays out several empty puzzles, each captioned with its title and size, for printing.
+pub fn as_html_sheet(docs: &[Document]) -> String {
+    let mut body = String::new();
+    for doc in docs {
+        let mut doc = doc.clone();
+        let title = doc.get_or_make_up_title().unwrap_or_default();
+        let (x_size, y_size) = doc.dimensions();
+        let table = doc.puzzle().specialize(html_table, html_table);
+
+        body.push_str(&format!(
+            r#"<div class="sheet-item"><div class="caption">{} ({x_size}x{y_size})</div>{table}</div>"#,
+            html_escape(&title)
+        ));
+    }
+    html_page("Puzzles", HTML_STYLE, &body)
+}
+
+/// One puzzle's entry in a library index.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+pub struct IndexEntry {
+    pub file: String,
+    pub title: String,
+    pub width: usize,
+    pub height: usize,
+    /// Foreground colors, not counting caps.
+    pub colors: usize,
+    /// `Report::difficulty`, if the line solver can finish the puzzle.
+    pub difficulty: Option<f32>,
+    pub band: &'static str,
+    /// Whether the clues have exactly one solution. Puzzles that the line solver can't finish
+    /// are searched for a second one, but only for so long; `None` means the search gave up.
+    pub unique: Option<bool>,
 }
 
-pub fn as_image_bytes<P>(solution: &Solution, path_or_filename: P) -> anyhow::Result<Vec<u8>>
+/// Solves each puzzle and lists them from easiest to hardest, with the ones the line solver can't
+/// finish at the end.
+pub fn library_index(docs: &mut [Document], options: &SolveOptions) -> Vec<IndexEntry> {
+    let mut entries = vec![];
+    for doc in docs.iter_mut() {
+        let file = doc.file().to_string();
+        let title = doc.get_or_make_up_title().unwrap_or_default();
+        let (width, height) = doc.dimensions();
+        let puzzle = doc.puzzle();
+        let colors = puzzle
+            .palette()
+            .values()
+            .filter(|ci| ci.color != BACKGROUND && ci.corner.is_none())
+            .count();
+
+        let report = puzzle.solve(options).ok();
+        let finished = report.as_ref().filter(|report| report.cells_left == 0);
+        entries.push(IndexEntry {
+            file,
+            title,
+            width,
+            height,
+            colors,
+            difficulty: finished.map(|report| report.difficulty()),
+            band: report
+                .as_ref()
+                .map_or(DifficultyBand::Unsolved, |report| report.difficulty_band())
+                .name(),
+            // Line-solving only makes sound deductions, so if it finishes, nothing else fits.
+            unique: if finished.is_some() {
+                Some(true)
+            } else {
+                crate::grid_solve::is_unique(
+                    puzzle,
+                    crate::grid_solve::UNIQUENESS_GUESS_LIMIT,
+                    options.separation,
+                )
+            },
+        });
+    }
+
+    entries.sort_by(|a, b| {
+        let by_difficulty = match (a.difficulty, b.difficulty) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        };
+        by_difficulty.then_with(|| a.file.cmp(&b.file))
+    });
+    entries
+}
+
+pub fn as_index_json(entries: &[IndexEntry]) -> String {
+    serde_json::to_string_pretty(entries).unwrap()
+}
+
+/// A table of the entries, for browsing.
+pub fn as_index_html(entries: &[IndexEntry]) -> String {
+    let mut rows = String::new();
+    for entry in entries {
+        let difficulty = entry
+            .difficulty
+            .map_or("-".to_string(), |d| format!("{d:.1}"));
+        let unique = match entry.unique {
+            Some(true) => "yes",
+            Some(false) => "no",
+            None => "?",
+        };
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}x{}</td><td>{}</td><td>{difficulty}</td><td>{}</td><td>{unique}</td></tr>\n",
+            html_escape(&entry.file),
+            html_escape(&entry.title),
+            entry.width,
+            entry.height,
+            entry.colors,
+            entry.band,
+        ));
+    }
+    html_page(
+        "Puzzle index",
+        INDEX_STYLE,
+        &format!(
+            "<table>\n<tr><th>File</th><th>Title</th><th>Size</th><th>Colors</th><th>Difficulty</th><th>Band</th><th>Unique</th></tr>\n{rows}</table>"
+        ),
+    )
+}
+
+/// Each cell becomes a `scale`x`scale` block of pixels. Caps are split diagonally with the
+/// background, so they only look right if `scale` > 1.
+pub fn as_image_bytes<P>(
+    solution: &Solution,
+    path_or_filename: P,
+    scale: u32,
+) -> anyhow::Result<Vec<u8>>
 where
     P: AsRef<Path>,
 {
+    encode_image(draw_solution(solution, scale), path_or_filename)
+}
+
+/// Like `as_image_bytes`, but each cell whose `disambig` score is at most `threshold` gets the
+/// suggested color blended into its middle, more strongly the better the suggestion is (the
+/// same overlay the editor draws).
+pub fn as_disambig_image_bytes<P>(
+    solution: &Solution,
+    disambig: &[Vec<(Color, f32)>],
+    threshold: f32,
+    path_or_filename: P,
+    scale: u32,
+) -> anyhow::Result<Vec<u8>>
+where
+    P: AsRef<Path>,
+{
+    let mut image = draw_solution(solution, scale);
+
+    for (x, col) in disambig.iter().enumerate() {
+        for (y, (color, score)) in col.iter().enumerate() {
+            if *score > threshold {
+                continue;
+            }
+            let (r, g, b) = solution.palette[color].rgb;
+            let alpha = 1.0 - score.max(0.0);
+            let (px, py) = (x as u32 * scale + scale / 4, y as u32 * scale + scale / 4);
+            let side = max(1, scale / 2);
+            for ix in px..min(px + side, image.width()) {
+                for iy in py..min(py + side, image.height()) {
+                    let Rgb([old_r, old_g, old_b]) = *image.get_pixel(ix, iy);
+                    let blend = |new: u8, old: u8| {
+                        (new as f32 * alpha + old as f32 * (1.0 - alpha)).round() as u8
+                    };
+                    image.put_pixel(
+                        ix,
+                        iy,
+                        Rgb([blend(r, old_r), blend(g, old_g), blend(b, old_b)]),
+                    );
+                }
+            }
+        }
+    }
+
+    encode_image(image, path_or_filename)
+}
+
+fn draw_solution(solution: &Solution, scale: u32) -> RgbImage {
     let mut image = RgbImage::new(
-        solution.grid.len() as u32,
-        solution.grid.first().unwrap().len() as u32,
+        solution.x_size() as u32 * scale,
+        solution.y_size() as u32 * scale,
     );
 
+    let bg_rgb = solution.palette[&BACKGROUND].rgb;
     for (x, col) in solution.grid.iter().enumerate() {
         for (y, color) in col.iter().enumerate() {
             let color_info = &solution.palette[color];
-            let (r, g, b) = color_info.rgb;
-            image.put_pixel(x as u32, y as u32, Rgb::<u8>([r, g, b]));
+            let (px, py) = (x as u32 * scale, y as u32 * scale);
+            match color_info.corner {
+                Some(corner) if scale > 1 => {
+                    fill_rect(&mut image, px, py, scale, scale, bg_rgb);
+                    fill_corner(&mut image, px, py, scale, corner, color_info.rgb);
+                }
+                _ => fill_rect(&mut image, px, py, scale, scale, color_info.rgb),
+            }
         }
     }
+    image
+}
 
+fn encode_image<P>(image: RgbImage, path_or_filename: P) -> anyhow::Result<Vec<u8>>
+where
+    P: AsRef<Path>,
+{
     let image_format = ImageFormat::from_path(path_or_filename)?;
 
     let dyn_image: DynamicImage = image::DynamicImage::ImageRgb8(image);
@@ -165,11 +464,267 @@ where
         .into_inner())
 }
 
+/// 3x5 bitmaps of the digits; each row is three bits, leftmost pixel in the high bit.
+const DIGIT_GLYPHS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b011, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b010, 0b010, 0b010],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+fn fill_rect(image: &mut RgbImage, x: u32, y: u32, w: u32, h: u32, (r, g, b): (u8, u8, u8)) {
+    for px in x..min(x + w, image.width()) {
+        for py in y..min(y + h, image.height()) {
+            image.put_pixel(px, py, Rgb([r, g, b]));
+        }
+    }
+}
+
+/// Fills the half of the `side`-sized square at (`x`, `y`) that `corner` points to.
+fn fill_corner(
+    image: &mut RgbImage,
+    x: u32,
+    y: u32,
+    side: u32,
+    corner: Corner,
+    (r, g, b): (u8, u8, u8),
+) {
+    for dx in 0..side {
+        for dy in 0..side {
+            let from_left = if corner.left { dx } else { side - 1 - dx };
+            let from_top = if corner.upper { dy } else { side - 1 - dy };
+            if from_left + from_top < side && x + dx < image.width() && y + dy < image.height() {
+                image.put_pixel(x + dx, y + dy, Rgb([r, g, b]));
+            }
+        }
+    }
+}
+
+fn draw_number_in_box(image: &mut RgbImage, x: u32, y: u32, side: u32, n: u16, rgb: (u8, u8, u8)) {
+    fill_rect(image, x, y, side, side, rgb);
+    let text_rgb = crate::clue_layout::text_rgb_on(rgb);
+
+    let digits: Vec<usize> = n
+        .to_string()
+        .chars()
+        .map(|c| c.to_digit(10).unwrap() as usize)
+        .collect();
+    // Each digit is three glyph-pixels wide, with one between digits:
+    let text_w = 4 * digits.len() as u32 - 1;
+    let px = max(1, min(side / (text_w + 2), side / 7));
+    let left = x + side.saturating_sub(text_w * px) / 2;
+    let top = y + side.saturating_sub(5 * px) / 2;
+
+    for (i, digit) in digits.into_iter().enumerate() {
+        for (row, bits) in DIGIT_GLYPHS[digit].iter().enumerate() {
+            for col in 0..3 {
+                if bits & (0b100 >> col) != 0 {
+                    fill_rect(
+                        image,
+                        left + (4 * i as u32 + col) * px,
+                        top + row as u32 * px,
+                        px,
+                        px,
+                        text_rgb,
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn render_puzzle_image<C: Clue>(
+    puzzle: &Puzzle<C>,
+    solution: Option<&Solution>,
+    scale: u32,
+) -> RgbImage {
+    use crate::clue_layout::{Orientation, layout_clues};
+
+    // The same geometry the editor draws with:
+    let row_clues = layout_clues(puzzle, scale as f32, Orientation::Horizontal, false);
+    let col_clues = layout_clues(puzzle, scale as f32, Orientation::Vertical, false);
+    let (grid_x, grid_y) = (
+        row_clues.size.0.ceil() as u32,
+        col_clues.size.1.ceil() as u32,
+    );
+    let (x_size, y_size) = (puzzle.cols.len() as u32, puzzle.rows.len() as u32);
+
+    let mut image = RgbImage::from_pixel(
+        grid_x + x_size * scale + 2,
+        grid_y + y_size * scale + 2,
+        Rgb([255, 255, 255]),
+    );
+
+    if let Some(solution) = solution {
+        let bg_rgb = solution.palette[&BACKGROUND].rgb;
+        for (x, col) in solution.grid.iter().enumerate() {
+            for (y, color) in col.iter().enumerate() {
+                let color_info = &solution.palette[color];
+                let (cell_x, cell_y) = (grid_x + x as u32 * scale, grid_y + y as u32 * scale);
+                match color_info.corner {
+                    Some(corner) => {
+                        fill_rect(&mut image, cell_x, cell_y, scale, scale, bg_rgb);
+                        fill_corner(&mut image, cell_x, cell_y, scale, corner, color_info.rgb);
+                    }
+                    None => fill_rect(&mut image, cell_x, cell_y, scale, scale, color_info.rgb),
+                }
+            }
+        }
+    }
+
+    // Grid lines, with every fifth one heavier:
+    for x in 0..=x_size {
+        let weight = if x % 5 == 0 || x == x_size { 2 } else { 1 };
+        fill_rect(
+            &mut image,
+            grid_x + x * scale,
+            grid_y,
+            weight,
+            y_size * scale,
+            (0, 0, 0),
+        );
+    }
+    for y in 0..=y_size {
+        let weight = if y % 5 == 0 || y == y_size { 2 } else { 1 };
+        fill_rect(
+            &mut image,
+            grid_x,
+            grid_y + y * scale,
+            x_size * scale + 2,
+            weight,
+            (0, 0, 0),
+        );
+    }
+
+    for (strip, (left, top)) in [(&row_clues, (0, grid_y)), (&col_clues, (grid_x, 0))] {
+        for clue_box in &strip.boxes {
+            let x = left + clue_box.min.0.round() as u32;
+            let y = top + clue_box.min.1.round() as u32;
+            let side = max(1, clue_box.side.round() as u32);
+            let color_info = clue_box.color_info;
+            match clue_box.count {
+                Some(count) => draw_number_in_box(&mut image, x, y, side, count, color_info.rgb),
+                None => fill_corner(
+                    &mut image,
+                    x,
+                    y,
+                    side,
+                    color_info.corner.expect("must be a corner"),
+                    color_info.rgb,
+                ),
+            }
+        }
+    }
+
+    image
+}
+
+/// Renders the clues and the grid (filled in with `solution`, if provided) as a PNG. `scale` is
+/// the side length of a cell, in pixels.
+pub fn render_puzzle_png(
+    puzzle: &DynPuzzle,
+    solution: Option<&Solution>,
+    scale: u32,
+) -> anyhow::Result<Vec<u8>> {
+    anyhow::ensure!(scale > 0, "the scale must be at least one pixel per cell");
+    let image = puzzle.specialize(
+        |p| render_puzzle_image(p, solution, scale),
+        |p| render_puzzle_image(p, solution, scale),
+    );
+
+    let mut writer = std::io::Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(image).write_to(&mut writer, ImageFormat::Png)?;
+    Ok(writer.into_inner())
+}
+
+/// Animates the line solver filling in `puzzle`, one frame per step that determined anything.
+/// Unknown cells are gray, and inferred background cells get a gray dot to distinguish them from
+/// cells that were never drawn yet. `scale` is the side length of a cell, in pixels.
+pub fn render_solve_gif(
+    puzzle: &DynPuzzle,
+    scale: u32,
+    frames_per_second: u32,
+) -> anyhow::Result<Vec<u8>> {
+    use image::{
+        Delay, Frame,
+        codecs::gif::{GifEncoder, Repeat},
+    };
+
+    let report = puzzle.plain_solve()?;
+    let solution = &report.solution;
+    let steps: Vec<usize> = report
+        .solve_order
+        .iter()
+        .flatten()
+        .copied()
+        .filter(|step| *step != usize::MAX)
+        .sorted()
+        .dedup()
+        .collect();
+
+    let scale = max(scale, 3);
+    let unknown_rgb = (160, 160, 160);
+    let dot_rgb = (190, 190, 190);
+    let bg_rgb = solution.palette[&BACKGROUND].rgb;
+    let delay = Delay::from_numer_denom_ms(1000, max(frames_per_second, 1));
+
+    let mut writer = std::io::Cursor::new(Vec::new());
+    {
+        let mut encoder = GifEncoder::new(&mut writer);
+        encoder.set_repeat(Repeat::Infinite)?;
+
+        // Start from a blank grid, so the first deductions are visible too:
+        for frame_step in std::iter::once(None).chain(steps.into_iter().map(Some)) {
+            let mut image = RgbImage::new(
+                solution.x_size() as u32 * scale,
+                solution.y_size() as u32 * scale,
+            );
+            for (x, col) in solution.grid.iter().enumerate() {
+                for (y, color) in col.iter().enumerate() {
+                    let (px, py) = (x as u32 * scale, y as u32 * scale);
+                    let known = frame_step.is_some_and(|s| report.solve_order[x][y] <= s);
+                    if !known {
+                        fill_rect(&mut image, px, py, scale, scale, unknown_rgb);
+                        continue;
+                    }
+                    let color_info = &solution.palette[color];
+                    if *color == BACKGROUND {
+                        fill_rect(&mut image, px, py, scale, scale, bg_rgb);
+                        fill_rect(
+                            &mut image,
+                            px + scale / 3,
+                            py + scale / 3,
+                            scale / 3,
+                            scale / 3,
+                            dot_rgb,
+                        );
+                    } else if let Some(corner) = color_info.corner {
+                        fill_rect(&mut image, px, py, scale, scale, bg_rgb);
+                        fill_corner(&mut image, px, py, scale, corner, color_info.rgb);
+                    } else {
+                        fill_rect(&mut image, px, py, scale, scale, color_info.rgb);
+                    }
+                }
+            }
+            let rgba = DynamicImage::ImageRgb8(image).into_rgba8();
+            encoder.encode_frame(Frame::from_parts(rgba, 0, 0, delay))?;
+        }
+    }
+
+    Ok(writer.into_inner())
+}
+
 pub fn as_char_grid(solution: &Solution) -> String {
     let mut result = String::new();
 
-    for y in 0..solution.grid[0].len() {
-        for x in 0..solution.grid.len() {
+    for y in 0..solution.y_size() {
+        for x in 0..solution.x_size() {
             let color = solution.grid[x][y];
             let color_info = &solution.palette[&color];
             result.push(color_info.ch);
@@ -187,7 +742,7 @@ mod tests {
 
     use crate::{
         import::olsak_to_puzzle,
-        puzzle::{Color, ColorInfo, Corner, Puzzle, Triano},
+        puzzle::{Color, ColorInfo, Corner, Nono, Puzzle, Triano},
     };
 
     fn match_march<'a, T>(
@@ -268,6 +823,218 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn render_puzzle_png_dimensions() {
+        let puzzle = crate::puzzle::DynPuzzle::Nono(Puzzle {
+            palette: HashMap::from_iter([
+                (Color(0), ColorInfo::default_bg()),
+                (Color(1), ColorInfo::default_fg(Color(1))),
+            ]),
+            rows: vec![
+                vec![Nono {
+                    color: Color(1),
+                    count: 12,
+                }],
+                vec![],
+            ],
+            cols: vec![
+                vec![Nono {
+                    color: Color(1),
+                    count: 1
+                }];
+                3
+            ],
+        });
+
+        let png = super::render_puzzle_png(&puzzle, None, 10).unwrap();
+        let image = image::load_from_memory(&png).unwrap();
+
+        // Clue strips are one 9px box, 5px of spacing, and `PUZZ_PADDING`:
+        assert_eq!(image.width(), 24 + 3 * 10 + 2);
+        assert_eq!(image.height(), 24 + 2 * 10 + 2);
+
+        assert!(super::render_puzzle_png(&puzzle, None, 0).is_err());
+    }
+
+    #[test]
+    fn clue_text() {
+        let mut palette = HashMap::from_iter([
+            (Color(0), ColorInfo::default_bg()),
+            (Color(1), ColorInfo::default_fg(Color(1))),
+        ]);
+        let nono = |color, count| Nono { color, count };
+        let mut puzzle = Puzzle {
+            palette: palette.clone(),
+            rows: vec![vec![nono(Color(1), 3), nono(Color(1), 1)], vec![]],
+            cols: vec![vec![nono(Color(1), 1)]],
+        };
+
+        assert_eq!(
+            super::as_clue_text(&puzzle),
+            "Row 1: 3 1\nRow 2: 0\n\nColumn 1: 1\n"
+        );
+
+        palette.insert(
+            Color(2),
+            ColorInfo {
+                ch: 'r',
+                name: "red".to_string(),
+                rgb: (255, 0, 0),
+                color: Color(2),
+                corner: None,
+            },
+        );
+        puzzle.palette = palette;
+        puzzle.rows[0][1].color = Color(2);
+        assert!(super::as_clue_text(&puzzle).starts_with("Row 1: 3 black, 1 red\n"));
+    }
+
+    #[test]
+    fn solve_gif_has_a_frame_per_step() {
+        use image::AnimationDecoder;
+
+        let palette = HashMap::from_iter([
+            (Color(0), ColorInfo::default_bg()),
+            (Color(1), ColorInfo::default_fg(Color(1))),
+        ]);
+        let clue = |count| {
+            vec![Nono {
+                color: Color(1),
+                count,
+            }]
+        };
+        let puzzle = crate::puzzle::DynPuzzle::Nono(Puzzle {
+            palette,
+            rows: vec![clue(2), clue(2)],
+            cols: vec![clue(2), clue(2)],
+        });
+
+        let gif = super::render_solve_gif(&puzzle, 4, 10).unwrap();
+        let frames = image::codecs::gif::GifDecoder::new(std::io::Cursor::new(gif))
+            .unwrap()
+            .into_frames()
+            .collect_frames()
+            .unwrap();
+
+        // A blank frame, then at least one per line that made progress:
+        assert!(frames.len() >= 3);
+        assert_eq!(frames[0].buffer().width(), 8);
+        assert_ne!(
+            frames[0].buffer().get_pixel(0, 0),
+            frames.last().unwrap().buffer().get_pixel(0, 0)
+        );
+    }
+
+    #[test]
+    fn image_export_splits_caps() {
+        let solution = crate::puzzle::Solution {
+            clue_style: crate::puzzle::ClueStyle::Triano,
+            palette: crate::import::triano_palette(),
+            // ◢ then ◤
+            grid: vec![vec![Color(6)], vec![Color(3)]],
+        };
+
+        let png = super::as_image_bytes(&solution, "out.png", 4).unwrap();
+        let image = image::load_from_memory(&png).unwrap().to_rgb8();
+
+        assert_eq!(image.dimensions(), (8, 4));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255]);
+        assert_eq!(image.get_pixel(3, 3).0, [0, 0, 0]);
+        assert_eq!(image.get_pixel(4, 0).0, [0, 0, 0]);
+        assert_eq!(image.get_pixel(7, 3).0, [255, 255, 255]);
+    }
+
+    #[test]
+    fn disambig_image_tints_flagged_cells() {
+        let solution = crate::puzzle::Solution::blank_bw(2, 1);
+        let disambig = vec![vec![(Color(1), 0.0)], vec![(Color(1), 0.5)]];
+
+        let png = super::as_disambig_image_bytes(&solution, &disambig, 0.25, "out.png", 4).unwrap();
+        let image = image::load_from_memory(&png).unwrap().to_rgb8();
+
+        assert_eq!(image.dimensions(), (8, 4));
+        // Only the middle of the flagged cell changes:
+        assert_eq!(image.get_pixel(0, 0).0, [255, 255, 255]);
+        assert_eq!(image.get_pixel(1, 1).0, [0, 0, 0]);
+        // The other cell is above the threshold:
+        assert_eq!(image.get_pixel(5, 1).0, [255, 255, 255]);
+    }
+
+    #[test]
+    fn chargrid_after_adding_colors() {
+        use crate::puzzle::{BACKGROUND, Document, NonogramFormat, Solution};
+
+        let mut solution = Solution::blank_bw(5, 1);
+        // Characters that used to come out of the palette editor, or just collide:
+        for (idx, ch) in [(2, 'B'), (3, '#'), (4, '\n')] {
+            solution.palette.insert(
+                Color(idx),
+                ColorInfo {
+                    ch,
+                    name: format!("color {idx}"),
+                    rgb: (idx * 50, 0, 0),
+                    color: Color(idx),
+                    corner: None,
+                },
+            );
+        }
+        solution.grid = (0..5).map(|i| vec![Color(i)]).collect();
+
+        let mut doc = Document::from_solution(solution, "added.txt".to_string());
+        let text = super::to_bytes(&mut doc, None, Some(NonogramFormat::CharGrid), false).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        assert_eq!(text.lines().count(), 1);
+
+        let reread = crate::import::char_grid_to_solution(&text);
+        assert_eq!(reread.palette.len(), 5);
+        assert_eq!(reread.grid[0][0], BACKGROUND);
+        assert_eq!(reread.palette[&reread.grid[1][0]].rgb, (0, 0, 0));
+        let distinct: std::collections::HashSet<Color> =
+            reread.grid.iter().flatten().copied().collect();
+        assert_eq!(distinct.len(), 5);
+    }
+
+    #[test]
+    fn webpbn_clues_only() {
+        use crate::puzzle::{Document, NonogramFormat};
+
+        let solution = crate::import::char_grid_to_solution("#.#\n.#.\n");
+        let mut doc = Document::from_solution(solution, "x.xml".to_string());
+        let webpbn = |doc: &mut Document, clues_only| {
+            String::from_utf8(
+                super::to_bytes(doc, None, Some(NonogramFormat::Webpbn), clues_only).unwrap(),
+            )
+            .unwrap()
+        };
+
+        assert!(webpbn(&mut doc, false).contains("<solution"));
+        let clues_only = webpbn(&mut doc, true);
+        assert!(!clues_only.contains("<solution"));
+        assert!(clues_only.contains("<clues"));
+    }
+
+    #[test]
+    fn tan_background_survives_export() {
+        use crate::puzzle::{BACKGROUND, Document, PuzzleDynOps, Solution};
+
+        let mut solution = Solution::blank_bw(3, 2);
+        solution.grid[1][0] = Color(1);
+        solution.grid[2][1] = Color(1);
+        let tan = solution.palette.get_mut(&BACKGROUND).unwrap();
+        tan.rgb = (210, 180, 140);
+        tan.name = "tan".to_string();
+
+        let mut doc = Document::from_solution(solution, "tan.xml".to_string());
+        let webpbn = crate::formats::webpbn::as_webpbn(&doc, true);
+        assert!(webpbn.contains(r#"defaultcolor="tan""#));
+        let mut reread = crate::formats::webpbn::webpbn_to_document(&webpbn).unwrap();
+        assert_eq!(reread.puzzle().palette()[&BACKGROUND].rgb, (210, 180, 140));
+
+        let olsak = crate::formats::olsak::as_olsak_nono(doc.puzzle().assume_nono());
+        let reread = olsak_to_puzzle(&olsak).unwrap();
+        assert_eq!(reread.palette()[&BACKGROUND].rgb, (210, 180, 140));
+    }
+
     #[test]
     fn round_trip_olsak_triano() {
         let p = Puzzle::<Triano> {
@@ -321,4 +1088,131 @@ mod tests {
 
         puzzles_eq(&p, &roundtripped.assume_triano()).unwrap();
     }
+
+    #[test]
+    fn round_trip_olsak_colored_caps() {
+        let color = |idx, ch, rgb, corner| {
+            (
+                Color(idx),
+                ColorInfo {
+                    ch,
+                    name: format!("color {idx}"),
+                    rgb,
+                    color: Color(idx),
+                    corner,
+                },
+            )
+        };
+        let p = Puzzle::<Triano> {
+            palette: HashMap::from_iter([
+                (Color(0), ColorInfo::default_bg()),
+                color(1, 'r', (255, 0, 0), None),
+                color(
+                    2,
+                    '◢',
+                    (255, 0, 0),
+                    Some(Corner {
+                        upper: false,
+                        left: false,
+                    }),
+                ),
+                color(
+                    3,
+                    '◤',
+                    (0, 0, 255),
+                    Some(Corner {
+                        upper: true,
+                        left: true,
+                    }),
+                ),
+            ]),
+            cols: vec![vec![Triano {
+                front_cap: Some(Color(2)),
+                body_len: 1,
+                body_color: Color(1),
+                back_cap: Some(Color(3)),
+            }]],
+            rows: vec![
+                vec![Triano {
+                    front_cap: Some(Color(2)),
+                    body_len: 1,
+                    body_color: Color(1),
+                    back_cap: None,
+                }],
+                vec![Triano {
+                    front_cap: None,
+                    body_len: 1,
+                    body_color: Color(1),
+                    back_cap: None,
+                }],
+                vec![Triano {
+                    front_cap: None,
+                    body_len: 1,
+                    body_color: Color(1),
+                    back_cap: Some(Color(3)),
+                }],
+            ],
+        };
+
+        let serialized = crate::formats::olsak::as_olsak_triano(&p);
+        assert!(serialized.contains("white/#FF0000"));
+        assert!(serialized.contains("#0000FF/white"));
+
+        let roundtripped = olsak_to_puzzle(&serialized).unwrap();
+        puzzles_eq(&p, &roundtripped.assume_triano()).unwrap();
+    }
+
+    #[test]
+    fn library_index_sorts_by_difficulty() {
+        use crate::import::char_grid_to_solution;
+
+        let doc = |grid: &str, file: &str| {
+            crate::puzzle::Document::from_solution(char_grid_to_solution(grid), file.to_string())
+        };
+        let mut docs = vec![
+            // Either diagonal fits these clues:
+            doc("#.\n.#\n", "ambiguous.txt"),
+            doc("##\n..\n", "easy.txt"),
+            doc("#r\n..\n", "two_colors.txt"),
+        ];
+
+        let entries = super::library_index(&mut docs, &SolveOptions::default());
+        let files: Vec<&str> = entries.iter().map(|e| e.file.as_str()).collect();
+        assert_eq!(files.last(), Some(&"ambiguous.txt"));
+
+        let ambiguous = entries.last().unwrap();
+        assert_eq!(ambiguous.difficulty, None);
+        assert_eq!(ambiguous.band, "unsolved");
+        assert_eq!(ambiguous.unique, Some(false));
+
+        let two_colors = entries.iter().find(|e| e.file == "two_colors.txt").unwrap();
+        assert_eq!((two_colors.width, two_colors.height), (2, 2));
+        assert_eq!(two_colors.colors, 2);
+        assert_eq!(two_colors.unique, Some(true));
+        assert!(two_colors.difficulty.is_some());
+
+        assert!(super::as_index_html(&entries).contains("<td>ambiguous.txt</td>"));
+        assert!(super::as_index_json(&entries).contains("\"unique\": false"));
+    }
+
+    #[test]
+    fn html_sheet_captions_each_puzzle() {
+        use crate::import::char_grid_to_solution;
+
+        let mut doc = crate::puzzle::Document::from_solution(
+            char_grid_to_solution("##\n.#\n"),
+            "corner.txt".to_string(),
+        );
+        doc.title = "Fish & <Chips>".to_string();
+        let other = crate::puzzle::Document::from_solution(
+            char_grid_to_solution("#..\n"),
+            "dash.txt".to_string(),
+        );
+
+        let sheet = super::as_html_sheet(&[doc, other]);
+        assert_eq!(sheet.matches(r#"class="sheet-item""#).count(), 2);
+        assert!(sheet.contains("Fish &amp; &lt;Chips&gt; (2x2)"));
+        assert!(sheet.contains("(3x1)"));
+        assert!(sheet.contains("page-break-inside: avoid"));
+    }
 }
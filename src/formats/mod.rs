@@ -1,3 +1,4 @@
+pub mod nin;
 pub mod olsak;
 pub mod webpbn;
 pub mod woven;
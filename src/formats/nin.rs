@@ -0,0 +1,131 @@
+//! The `.nin` format: the width and height on the first line, then one line of clues per row,
+//! then one per column. Black-and-white only. Lines starting with '#' are comments.
+
+use std::collections::HashMap;
+
+use anyhow::{Context, bail};
+
+use crate::puzzle::{BACKGROUND, Color, ColorInfo, Nono, Puzzle};
+
+pub fn nin_to_puzzle(nin: &str) -> anyhow::Result<Puzzle<Nono>> {
+    let black = Color(1);
+    let mut lines = nin
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.starts_with('#'));
+
+    let (width, height) = loop {
+        let Some((line_num, line)) = lines.next() else {
+            bail!("expected the width and height on the first line");
+        };
+        if line.is_empty() {
+            continue;
+        }
+        let dims: Vec<&str> = line.split_whitespace().collect();
+        let &[width, height] = dims.as_slice() else {
+            bail!("line {line_num}: expected the width and height, not {line:?}");
+        };
+        break (
+            width
+                .parse::<usize>()
+                .with_context(|| format!("line {line_num}: bad width"))?,
+            height
+                .parse::<usize>()
+                .with_context(|| format!("line {line_num}: bad height"))?,
+        );
+    };
+
+    let mut lanes = vec![];
+    for (line_num, line) in lines.by_ref().take(height + width) {
+        let mut clues = vec![];
+        for num in line.split(|c: char| c.is_whitespace() || c == ',') {
+            if num.is_empty() {
+                continue;
+            }
+            let count: u16 = num
+                .parse()
+                .with_context(|| format!("line {line_num}: expected a number, not {num:?}"))?;
+            if count > 0 {
+                clues.push(Nono {
+                    color: black,
+                    count,
+                });
+            }
+        }
+        lanes.push(clues);
+    }
+    if lanes.len() < height + width {
+        bail!(
+            "expected {height} rows and {width} columns, but only found {} lines of clues",
+            lanes.len()
+        );
+    }
+    if let Some((line_num, _)) = lines.find(|(_, line)| !line.is_empty()) {
+        bail!("line {line_num}: unexpected text after the last column");
+    }
+
+    let cols = lanes.split_off(height);
+    Ok(Puzzle {
+        palette: HashMap::from([
+            (BACKGROUND, ColorInfo::default_bg()),
+            (black, ColorInfo::default_fg(black)),
+        ]),
+        rows: lanes,
+        cols,
+    })
+}
+
+pub fn as_nin(puzzle: &Puzzle<Nono>) -> anyhow::Result<String> {
+    if puzzle.palette.len() > 2 {
+        bail!("the .nin format only supports black-and-white puzzles");
+    }
+
+    let mut res = format!("{} {}\n", puzzle.cols.len(), puzzle.rows.len());
+    for lane in puzzle.rows.iter().chain(puzzle.cols.iter()) {
+        if lane.is_empty() {
+            res.push('0');
+        } else {
+            let counts: Vec<String> = lane.iter().map(|clue| clue.count.to_string()).collect();
+            res.push_str(&counts.join(" "));
+        }
+        res.push('\n');
+    }
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let nin = "3 2\n1 1\n0\n1\n0\n1\n";
+        let puzzle = nin_to_puzzle(nin).unwrap();
+        assert_eq!(puzzle.rows.len(), 2);
+        assert_eq!(puzzle.cols.len(), 3);
+        assert_eq!(puzzle.rows[0].len(), 2);
+        assert!(puzzle.rows[1].is_empty());
+        assert_eq!(as_nin(&puzzle).unwrap(), nin);
+
+        let with_comments = "# A tiny one\n3 2\n1 1\n\n1\n0\n1\n";
+        assert_eq!(as_nin(&nin_to_puzzle(with_comments).unwrap()).unwrap(), nin);
+    }
+
+    #[test]
+    fn round_trip_from_solution() {
+        let solution = crate::import::char_grid_to_solution("#.##\n.##.\n####\n");
+        let puzzle = crate::import::solution_to_puzzle(&solution);
+        let reread = nin_to_puzzle(&as_nin(&puzzle).unwrap()).unwrap();
+        assert_eq!(reread.rows, puzzle.rows);
+        assert_eq!(reread.cols, puzzle.cols);
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(nin_to_puzzle("").is_err());
+        assert!(nin_to_puzzle("2 2\n1\n1\n1\n").is_err());
+        assert!(nin_to_puzzle("1 1\nx\n1\n").is_err());
+        assert!(nin_to_puzzle("1 1\n1\n1\n1\n").is_err());
+    }
+}
@@ -3,7 +3,7 @@ use std::{
     iter::FromIterator,
 };
 
-use crate::puzzle::{self, Nono, Puzzle, Triano};
+use crate::puzzle::{self, BACKGROUND, Color, ColorInfo, Nono, Puzzle, Triano};
 
 fn olsak_ch(c: char, orig_to_sanitized: &mut HashMap<char, char>) -> char {
     let existing = HashSet::<char>::from_iter(orig_to_sanitized.values().cloned());
@@ -21,6 +21,22 @@ fn olsak_ch(c: char, orig_to_sanitized: &mut HashMap<char, char>) -> char {
     })
 }
 
+fn background_line(palette: &HashMap<Color, ColorInfo>) -> String {
+    let bg = &palette[&BACKGROUND];
+    let (r, g, b) = bg.rgb;
+    format!("   0:   #{r:02X}{g:02X}{b:02X}   {}\n", bg.name)
+}
+
+/// One half of a triangle's color spec. Black and white are written by name, since that's all
+/// older readers understand.
+fn triangle_half((r, g, b): (u8, u8, u8)) -> String {
+    match (r, g, b) {
+        (0, 0, 0) => "black".to_string(),
+        (255, 255, 255) => "white".to_string(),
+        _ => format!("#{r:02X}{g:02X}{b:02X}"),
+    }
+}
+
 pub fn as_olsak_nono(puzzle: &Puzzle<Nono>) -> String {
     let mut orig_to_sanitized: HashMap<char, char> = HashMap::new();
 
@@ -29,10 +45,11 @@ pub fn as_olsak_nono(puzzle: &Puzzle<Nono>) -> String {
     let mut res = String::new();
     res.push_str("#d\n");
 
-    // Nonny doesn't like it if white isn't the first color in the palette.
-    res.push_str("   0:   #FFFFFF   white\n");
+    // The background has to come first (and Nonny doesn't like it if that isn't white, but it's
+    // better than misrepresenting the puzzle).
+    res.push_str(&background_line(&puzzle.palette));
     for color in palette.values_mut() {
-        if color.rgb != (255, 255, 255) {
+        if color.color != BACKGROUND {
             let (r, g, b) = color.rgb;
             color.ch = olsak_ch(color.ch, &mut orig_to_sanitized);
             let ch = color.ch;
@@ -81,10 +98,11 @@ pub fn as_olsak_triano(puzzle: &Puzzle<Triano>) -> String {
         })
         .collect::<HashMap<_, _>>();
 
-    // Nonny doesn't like it if white isn't the first color in the palette.
-    res.push_str("   0:   #FFFFFF   white\n");
+    res.push_str(&background_line(&puzzle.palette));
+    // The empty half of a triangle is the background.
+    let bg_half = triangle_half(puzzle.palette[&BACKGROUND].rgb);
     for color in palette.values() {
-        if color.rgb != (255, 255, 255) {
+        if color.color != BACKGROUND {
             let (r, g, b) = color.rgb;
             let ch = color.ch;
             let (spec, comment) = match color.corner {
@@ -92,9 +110,17 @@ pub fn as_olsak_triano(puzzle: &Puzzle<Triano>) -> String {
                 Some(Corner { upper, left }) => (
                     &format!(
                         "{}{}{}",
-                        if left { "black" } else { "white" },
+                        if left {
+                            triangle_half(color.rgb)
+                        } else {
+                            bg_half.clone()
+                        },
                         if left == upper { "/" } else { "\\" },
-                        if left { "white" } else { "black" },
+                        if left {
+                            bg_half.clone()
+                        } else {
+                            triangle_half(color.rgb)
+                        },
                     ),
                     format!(
                         "{}{}",
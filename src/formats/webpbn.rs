@@ -1,7 +1,10 @@
-use anyhow::bail;
-use std::collections::HashMap;
+use anyhow::{anyhow, bail};
+use std::collections::{HashMap, HashSet};
 
-use crate::puzzle::{BACKGROUND, Clue, Color, ColorInfo, Document, Nono, Puzzle};
+use crate::line_solve::SeparationRule;
+use crate::puzzle::{
+    BACKGROUND, Clue, ClueStyle, Color, ColorInfo, Document, Nono, Puzzle, Solution, UNSOLVED,
+};
 
 fn get_children<'a, 'input>(
     node: roxmltree::Node<'a, 'input>,
@@ -44,10 +47,63 @@ fn get_single_child<'a, 'input>(
     Ok(res.pop().unwrap())
 }
 
-pub fn webpbn_to_document(webpbn: &str) -> Document {
-    let doc = roxmltree::Document::parse(webpbn).unwrap();
+/// Some files use multi-glyph labels in `char` (or omit it, or reuse one). The name is preserved
+/// separately, so we just need some single character that's unique within the palette.
+fn unique_ch(label: &str, name: &str, used: &HashSet<char>) -> anyhow::Result<char> {
+    let mut label_chars = label.chars();
+    if let (Some(ch), None) = (label_chars.next(), label_chars.next()) {
+        if !used.contains(&ch) {
+            return Ok(ch);
+        }
+    }
+
+    label
+        .chars()
+        .chain(name.chars())
+        .filter(|ch| ch.is_alphanumeric())
+        .chain('a'..='z')
+        .chain('A'..='Z')
+        .chain('0'..='9')
+        .find(|ch| !used.contains(ch))
+        .ok_or(anyhow!("too many colors to give {name} a character"))
+}
+
+/// Reads the rows of an `<image>`, like `|X.X|`, into a grid indexed `[x][y]`.
+fn parse_image(image: &str, char_colors: &HashMap<char, Color>) -> anyhow::Result<Vec<Vec<Color>>> {
+    let mut rows = vec![];
+    for line in image.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let Some(inner) = line.strip_prefix('|').and_then(|l| l.strip_suffix('|')) else {
+            bail!("expected an image row between '|'s, not {line:?}");
+        };
+        let row = inner
+            .chars()
+            .map(|ch| {
+                char_colors
+                    .get(&ch)
+                    .copied()
+                    .ok_or(anyhow!("image uses {ch:?}, which isn't in the palette"))
+            })
+            .collect::<anyhow::Result<Vec<Color>>>()?;
+        rows.push(row);
+    }
+
+    let x_size = rows.first().map_or(0, |row| row.len());
+    if x_size == 0 || rows.iter().any(|row| row.len() != x_size) {
+        bail!("image rows must all be the same, nonzero, length");
+    }
+
+    Ok((0..x_size)
+        .map(|x| rows.iter().map(|row| row[x]).collect())
+        .collect())
+}
+
+/// Files with clues are read as puzzles, along with their goal `<image>`, if any (which has to
+/// match the clues). Files with only a goal (and no `<clues>`) are read as pictures, so the clues
+/// can be generated from them.
+pub fn webpbn_to_document(webpbn: &str) -> anyhow::Result<Document> {
+    let doc = roxmltree::Document::parse(webpbn)?;
     let puzzleset = doc.root_element();
-    let puzzle_node = get_single_child(puzzleset, "puzzle").unwrap();
+    let puzzle_node = get_single_child(puzzleset, "puzzle")?;
 
     let mut title = None;
     let mut description = None;
@@ -58,10 +114,13 @@ pub fn webpbn_to_document(webpbn: &str) -> Document {
 
     let default_color = puzzle_node
         .attribute("defaultcolor")
-        .expect("Expected a 'defaultcolor");
+        .ok_or(anyhow!("expected a 'defaultcolor' attribute"))?;
     let mut next_color_index = 1;
 
     let mut named_colors = HashMap::<String, Color>::new();
+    // How `<image>`s refer to colors.
+    let mut char_colors = HashMap::<char, Color>::new();
+    let mut goal = None;
 
     let mut puzzle = Puzzle {
         palette: HashMap::<Color, ColorInfo>::new(),
@@ -88,7 +147,9 @@ pub fn webpbn_to_document(webpbn: &str) -> Document {
         } else if tag_name == "copyright" {
             license = puzzle_part.text().map(|s| s.trim().to_string());
         } else if tag_name == "color" {
-            let color_name = puzzle_part.attribute("name").unwrap();
+            let color_name = puzzle_part
+                .attribute("name")
+                .ok_or(anyhow!("expected a 'name' attribute on a color"))?;
             let color = if color_name == default_color {
                 BACKGROUND
             } else {
@@ -104,28 +165,38 @@ pub fn webpbn_to_document(webpbn: &str) -> Document {
             )
             .unwrap();
 
-            let color_text = puzzle_part.text().expect("Expected hex color in text");
+            let color_text = puzzle_part
+                .text()
+                .ok_or(anyhow!("expected a hex color for {color_name}"))?;
             let (_, component_strs) = hex_color
-                .captures(&color_text)
-                .expect("Expected a string of 6 hex digits")
+                .captures(color_text.trim())
+                .ok_or(anyhow!(
+                    "expected 6 hex digits for {color_name}, not {color_text:?}"
+                ))?
                 .extract();
 
-            let [r, g, b] = component_strs.map(|s| u8::from_str_radix(s, 16).unwrap());
+            let [r, g, b] = component_strs.map(|s| u8::from_str_radix(s, 16));
+            let (r, g, b) = (r?, g?, b?);
 
+            let used_chars: HashSet<char> = puzzle.palette.values().map(|ci| ci.ch).collect();
             let color_info = ColorInfo {
-                // TODO: error if there's more than one char!
-                ch: puzzle_part
-                    .attribute("char")
-                    .unwrap()
-                    .chars()
-                    .next()
-                    .unwrap(),
+                ch: unique_ch(
+                    puzzle_part.attribute("char").unwrap_or(""),
+                    color_name,
+                    &used_chars,
+                )?,
                 name: color_name.to_string(),
                 rgb: (r, g, b),
                 color: color,
                 corner: None, // webpbn isn't intended to represent Triano clues
             };
 
+            let mut file_chars = puzzle_part.attribute("char").unwrap_or("").chars();
+            if let (Some(ch), None) = (file_chars.next(), file_chars.next()) {
+                char_colors.insert(ch, color);
+            }
+            char_colors.entry(color_info.ch).or_insert(color);
+
             puzzle.palette.insert(color, color_info);
             named_colors.insert(color_name.to_string(), color);
         } else if tag_name == "clues" {
@@ -134,20 +205,27 @@ pub fn webpbn_to_document(webpbn: &str) -> Document {
             } else if puzzle_part.attribute("type") == Some("columns") {
                 false
             } else {
-                panic!("Expected rows or columns.")
+                bail!("expected clues of type 'rows' or 'columns'")
             };
 
             let mut clue_lanes = vec![];
 
-            for lane in get_children(puzzle_part, "line").unwrap() {
+            for lane in get_children(puzzle_part, "line")? {
                 let mut clues = vec![];
-                for block in get_children(lane, "count").unwrap() {
+                for block in get_children(lane, "count")? {
+                    let line_num = doc.text_pos_at(block.range().start).row;
+                    let color_name = block
+                        .attribute("color")
+                        .ok_or(anyhow!("line {line_num}: expected a 'color' attribute"))?;
+                    let color = *named_colors.get(color_name).ok_or(anyhow!(
+                        "line {line_num}: clue uses the color {color_name:?}, which isn't in the palette"
+                    ))?;
+                    let count_text = block.text().unwrap_or("").trim();
                     clues.push(Nono {
-                        color: named_colors[block
-                            .attribute("color")
-                            .expect("Expected 'color' attribute")],
-                        count: u16::from_str_radix(&block.text().unwrap(), 10)
-                            .expect("Expected a number."),
+                        color,
+                        count: count_text.parse().map_err(|_| {
+                            anyhow!("line {line_num}: expected a number, not {count_text:?}")
+                        })?,
                     });
                 }
                 clue_lanes.push(clues);
@@ -158,22 +236,54 @@ pub fn webpbn_to_document(webpbn: &str) -> Document {
             } else {
                 puzzle.cols = clue_lanes;
             }
+        } else if tag_name == "solution" {
+            // "saved" solutions are someone's work in progress, not the intended picture.
+            if !matches!(puzzle_part.attribute("type"), None | Some("goal")) {
+                continue;
+            }
+            goal = Some(get_single_child(puzzle_part, "image")?.text().unwrap_or(""));
         }
     }
 
-    Document::new(
-        Some(Nono::to_dyn(puzzle)),
-        None,
+    let solution = goal
+        .map(|image| -> anyhow::Result<Solution> {
+            Ok(Solution {
+                clue_style: ClueStyle::Nono,
+                grid: parse_image(image, &char_colors)?,
+                palette: puzzle.palette.clone(),
+            })
+        })
+        .transpose()?;
+
+    let puzzle = if solution.is_some() && puzzle.rows.is_empty() && puzzle.cols.is_empty() {
+        None
+    } else {
+        if !puzzle.rows.is_empty() && !puzzle.cols.is_empty() {
+            // The file doesn't say which separation rule it's for, so only reject clues that
+            // can't fit under any of them.
+            puzzle.check_lines_fit(SeparationRule::Never)?;
+        }
+        Some(Nono::to_dyn(puzzle))
+    };
+
+    let mut doc = Document::new(
+        puzzle,
+        solution,
         "".to_string(),
         title,
         description,
         author.or(authorid),
         id,
         license,
-    )
+    );
+    doc.drop_inconsistent_solution();
+    Ok(doc)
 }
 
-pub fn as_webpbn(document: &Document) -> String {
+/// With `include_solution`, a document that has a (completely solved) picture gets a
+/// `<solution type="goal">` block, so other tools can check their answers against ours.
+pub fn as_webpbn(document: &Document, include_solution: bool) -> String {
+    use crate::export::html_escape as escape;
     use indoc::indoc;
 
     let mut document_with_puzzle = document.clone();
@@ -184,32 +294,43 @@ pub fn as_webpbn(document: &Document) -> String {
     res.push_str(indoc! {r#"
         <?xml version="1.0"?>
         <puzzleset>
-        <puzzle type="grid" defaultcolor="white">
-        <source>number-loom</source>
         "#});
+    res.push_str(&format!(
+        r#"<puzzle type="grid" defaultcolor="{}">"#,
+        escape(&puzzle.palette[&BACKGROUND].name)
+    ));
+    res.push('\n');
+    res.push_str("<source>number-loom</source>\n");
     if !document.title.is_empty() {
-        res.push_str(&format!("<title>{}</title>\n", &document.title));
+        res.push_str(&format!("<title>{}</title>\n", escape(&document.title)));
     }
     if !document.description.is_empty() {
         res.push_str(&format!(
             "<description>{}</description>\n",
-            &document.description
+            escape(&document.description)
         ));
     }
     if !document.author.is_empty() {
-        res.push_str(&format!("<author>{}</author>\n", &document.author));
+        res.push_str(&format!("<author>{}</author>\n", escape(&document.author)));
     }
     if !document.id.is_empty() {
-        res.push_str(&format!("<id>{}</id>\n", &document.id));
+        res.push_str(&format!("<id>{}</id>\n", escape(&document.id)));
     }
     if !document.license.is_empty() {
-        res.push_str(&format!("<copyright>{}</copyright>\n", &document.license));
+        res.push_str(&format!(
+            "<copyright>{}</copyright>\n",
+            escape(&document.license)
+        ));
     }
     for color in puzzle.palette.values() {
         let (r, g, b) = color.rgb;
         res.push_str(&format!(
             r#"<color name="{}" char="{}">{:02X}{:02X}{:02X}</color>"#,
-            color.name, color.ch, r, g, b
+            escape(&color.name),
+            escape(&color.ch.to_string()),
+            r,
+            g,
+            b
         ));
         res.push('\n');
     }
@@ -220,7 +341,8 @@ pub fn as_webpbn(document: &Document) -> String {
         for clue in column {
             res.push_str(&format!(
                 r#"<count color="{}">{}</count>"#,
-                puzzle.palette[&clue.color].name, clue.count
+                escape(&puzzle.palette[&clue.color].name),
+                clue.count
             ));
         }
         res.push_str("</line>\n");
@@ -234,7 +356,8 @@ pub fn as_webpbn(document: &Document) -> String {
         for clue in row {
             res.push_str(&format!(
                 r#"<count color="{}">{}</count>"#,
-                puzzle.palette[&clue.color].name, clue.count
+                escape(&puzzle.palette[&clue.color].name),
+                clue.count
             ));
         }
         res.push_str("</line>\n");
@@ -242,8 +365,168 @@ pub fn as_webpbn(document: &Document) -> String {
     res.push_str(r#"</clues>"#);
     res.push('\n');
 
+    if let Some(solution) = document
+        .try_solution()
+        .filter(|_| include_solution)
+        .filter(|s| s.grid.iter().flatten().all(|c| *c != UNSOLVED))
+    {
+        res.push_str(r#"<solution type="goal"><image>"#);
+        res.push('\n');
+        for y in 0..solution.y_size() {
+            res.push('|');
+            for x in 0..solution.x_size() {
+                res.push_str(&escape(
+                    &solution.palette[&solution.grid[x][y]].ch.to_string(),
+                ));
+            }
+            res.push_str("|\n");
+        }
+        res.push_str(r#"</image></solution>"#);
+        res.push('\n');
+    }
+
     res.push_str(r#"</puzzle></puzzleset>"#);
     res.push('\n');
 
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_color_is_an_error() {
+        let webpbn = r#"<?xml version="1.0"?>
+<puzzleset>
+<puzzle type="grid" defaultcolor="white">
+<color name="white" char=".">FFFFFF</color>
+<color name="black" char="X">000000</color>
+<clues type="columns">
+<line><count color="black">1</count></line>
+</clues>
+<clues type="rows">
+<line><count color="blakc">1</count></line>
+</clues>
+</puzzle>
+</puzzleset>
+"#;
+        let err = webpbn_to_document(webpbn).unwrap_err().to_string();
+        assert!(err.contains("blakc"), "{err}");
+        assert!(err.contains("line 10"), "{err}");
+
+        assert!(webpbn_to_document(&webpbn.replace("blakc", "black")).is_ok());
+    }
+
+    #[test]
+    fn bad_counts_are_errors() {
+        let webpbn = r#"<?xml version="1.0"?>
+<puzzleset>
+<puzzle type="grid" defaultcolor="white">
+<color name="white" char=".">FFFFFF</color>
+<color name="black" char="X">000000</color>
+<clues type="columns">
+<line><count color="black">1</count></line>
+<line><count color="black">1</count></line>
+</clues>
+<clues type="rows">
+<line><count color="black">COUNT</count></line>
+</clues>
+</puzzle>
+</puzzleset>
+"#;
+        let err = webpbn_to_document(&webpbn.replace("COUNT", "two"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected a number"), "{err}");
+
+        let err = webpbn_to_document(&webpbn.replace("COUNT", "3"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("row 1 need at least 3 cells"), "{err}");
+
+        assert!(webpbn_to_document(&webpbn.replace("COUNT", "2")).is_ok());
+    }
+
+    #[test]
+    fn out_of_chars() {
+        let used: HashSet<char> = ('a'..='z').chain('A'..='Z').chain('0'..='9').collect();
+        assert!(unique_ch("xy", "zz", &used).is_err());
+        assert_eq!(unique_ch("é", "zz", &used).unwrap(), 'é');
+    }
+
+    #[test]
+    fn image_only() {
+        let webpbn = r#"<?xml version="1.0"?>
+<puzzleset>
+<puzzle type="grid" defaultcolor="white">
+<title>Just a picture</title>
+<color name="white" char=".">FFFFFF</color>
+<color name="black" char="X">000000</color>
+<color name="red" char="r">FF0000</color>
+<solution type="goal">
+<image>
+|X.r|
+|.XX|
+</image>
+</solution>
+</puzzle>
+</puzzleset>
+"#;
+        let mut doc = webpbn_to_document(webpbn).unwrap();
+        assert_eq!(doc.title, "Just a picture");
+
+        let solution = doc.try_solution().unwrap();
+        assert_eq!((solution.x_size(), solution.y_size()), (3, 2));
+        assert_eq!(solution.grid[0][1], BACKGROUND);
+        assert_eq!(solution.palette[&solution.grid[2][0]].name, "red");
+
+        let puzzle = doc.puzzle().assume_nono();
+        assert_eq!(puzzle.rows.len(), 2);
+        assert_eq!(puzzle.cols[1].len(), 1);
+        assert_eq!(puzzle.cols[1][0].count, 1);
+    }
+
+    #[test]
+    fn special_characters_are_escaped() {
+        let mut solution = crate::import::char_grid_to_solution("#.\n.#\n");
+        solution.palette.get_mut(&BACKGROUND).unwrap().name = r#""off" white & <cream>"#.into();
+        solution.palette.get_mut(&Color(1)).unwrap().ch = '&';
+        let mut doc = Document::from_solution(solution, "x.xml".to_string());
+        doc.title = "Fish & <chips>".to_string();
+
+        let reread = webpbn_to_document(&as_webpbn(&doc, true)).unwrap();
+        assert_eq!(reread.title, doc.title);
+        let palette = &reread.try_solution().unwrap().palette;
+        assert_eq!(palette[&BACKGROUND].name, r#""off" white & <cream>"#);
+        assert_eq!(palette[&Color(1)].ch, '&');
+    }
+
+    #[test]
+    fn solution_block() {
+        let solution = crate::import::char_grid_to_solution("#.#\n.#.\n");
+        let doc = Document::from_solution(solution, "x.xml".to_string());
+
+        let with_solution = as_webpbn(&doc, true);
+        assert!(with_solution.contains("<solution type=\"goal\"><image>\n|#.#|\n|.#.|\n</image>"));
+        let read_back = webpbn_to_document(&with_solution).unwrap();
+        assert_eq!(
+            read_back.try_solution().unwrap().grid,
+            doc.try_solution().unwrap().grid
+        );
+        assert!(read_back.try_puzzle().is_some());
+
+        let mismatched = with_solution.replace("|.#.|", "|..#|");
+        let mut mismatched = webpbn_to_document(&mismatched).unwrap();
+        assert!(mismatched.try_puzzle().is_some());
+        assert!(mismatched.try_solution().is_none());
+        assert!(
+            mismatched
+                .quality_check(SeparationRule::default())
+                .iter()
+                .any(|problem| problem.contains("doesn't match the clues"))
+        );
+
+        assert!(!as_webpbn(&doc, false).contains("<solution"));
+    }
+}
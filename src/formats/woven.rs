@@ -1,4 +1,10 @@
-use crate::puzzle::{ClueStyle, Color, ColorInfo, Document, DynPuzzle, Nono, Solution, Triano};
+use crate::grid_solve::SolveOptions;
+use crate::line_solve::Cell;
+use crate::puzzle::{
+    BACKGROUND, Clue, ClueStyle, Color, ColorInfo, Document, DynPuzzle, Nono, PartialSolution,
+    Puzzle, PuzzleDynOps, Solution, Triano,
+};
+use anyhow::{Context, bail};
 use base64::{Engine as _, engine::general_purpose};
 use serde::{Deserialize, Serialize};
 use std::io::prelude::*;
@@ -61,6 +67,10 @@ impl From<&mut Document> for SerializableDocument {
     }
 }
 
+/// Written right after "WOVEN", so that the encoding can change later without breaking old share
+/// strings. Strings from before this existed start with just "WOVEN-", and are read as version 1.
+const WOVEN_VERSION: char = '1';
+
 pub fn to_woven(doc: &mut Document) -> anyhow::Result<String> {
     let s_doc: SerializableDocument = doc.into();
     let buf = std::io::BufWriter::new(Vec::new());
@@ -70,7 +80,7 @@ pub fn to_woven(doc: &mut Document) -> anyhow::Result<String> {
     encoder.write_all(&bytes)?;
     let compressed = encoder.into_inner().into_inner().unwrap();
     let encoded = format!(
-        "WOVEN-{}-",
+        "WOVEN{WOVEN_VERSION}-{}-",
         general_purpose::STANDARD_NO_PAD.encode(compressed)
     );
 
@@ -84,10 +94,29 @@ pub fn to_woven(doc: &mut Document) -> anyhow::Result<String> {
     Ok(result)
 }
 
+pub fn to_json(doc: &mut Document) -> anyhow::Result<String> {
+    let s_doc: SerializableDocument = doc.into();
+    Ok(serde_json::to_string_pretty(&s_doc)?)
+}
+
+pub fn from_json(s: &str) -> anyhow::Result<Document> {
+    let s_doc: SerializableDocument = serde_json::from_str(s)?;
+    Ok(s_doc.into())
+}
+
 pub fn from_woven(s: &str) -> anyhow::Result<Document> {
     let s = s
-        .strip_prefix("WOVEN-")
-        .ok_or_else(|| anyhow::anyhow!("Missing 'WOVEN-' prefix"))?
+        .strip_prefix("WOVEN")
+        .ok_or_else(|| anyhow::anyhow!("Missing 'WOVEN-' prefix"))?;
+    let s = match s.split_once('-') {
+        Some(("", rest)) => rest,
+        Some((version, rest)) if version == WOVEN_VERSION.to_string() => rest,
+        Some((version, _)) => anyhow::bail!(
+            "Unknown share string version {version:?} (maybe it's from a newer Number Loom?)"
+        ),
+        None => anyhow::bail!("Missing '-' after 'WOVEN'"),
+    };
+    let s = s
         .strip_suffix("-")
         .ok_or_else(|| anyhow::anyhow!("Must end in a '-'"))?;
     let s: String = s.chars().filter(|c| !c.is_whitespace()).collect();
@@ -101,6 +130,95 @@ pub fn from_woven(s: &str) -> anyhow::Result<Document> {
     Ok(s_doc.into())
 }
 
+/// Each cell's remaining candidate colors, indexed `[y][x]` (like `PartialSolution`).
+pub type SerializablePartial = Vec<Vec<Vec<Color>>>;
+
+/// What `solve_partial_bytes` sends back.
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct SerializableReport {
+    pub partial: SerializablePartial,
+    pub cells_left: usize,
+    /// In the order of `SolveMode::all()`.
+    pub solve_counts: Vec<usize>,
+    pub interrupted: bool,
+}
+
+fn check_clue_colors<C: Clue>(puzzle: &Puzzle<C>) -> anyhow::Result<()> {
+    if !puzzle.palette.contains_key(&BACKGROUND) {
+        bail!("the palette has no background color");
+    }
+    if let Some(color) = puzzle.palette.keys().find(|c| c.0 >= 32) {
+        bail!("palette color {} is out of range", color.0);
+    }
+    for clue in puzzle.rows.iter().chain(puzzle.cols.iter()).flatten() {
+        for idx in 0..clue.len() {
+            let color = clue.color_at(idx);
+            if !puzzle.palette.contains_key(&color) {
+                bail!("a clue uses color {}, which isn't in the palette", color.0);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn solve_partial(puzzle_json: &[u8], partial_json: &[u8]) -> anyhow::Result<SerializableReport> {
+    let s_puzzle: SerializablePuzzle =
+        serde_json::from_slice(puzzle_json).context("reading the puzzle")?;
+    let s_partial: SerializablePartial =
+        serde_json::from_slice(partial_json).context("reading the partial solution")?;
+    let puzzle = DynPuzzle::from(&s_puzzle);
+    puzzle.specialize(check_clue_colors::<Nono>, check_clue_colors::<Triano>)?;
+
+    let (rows, cols) = (puzzle.rows(), puzzle.cols());
+    if s_partial.len() != rows || s_partial.iter().any(|row| row.len() != cols) {
+        bail!("the partial solution should be {cols}x{rows}, to match the clues");
+    }
+    let mut partial = PartialSolution::from_elem((rows, cols), Cell::new_impossible());
+    for (y, row) in s_partial.iter().enumerate() {
+        for (x, candidates) in row.iter().enumerate() {
+            if let Some(color) = candidates
+                .iter()
+                .find(|c| !puzzle.palette().contains_key(c))
+            {
+                bail!(
+                    "cell ({x}, {y}) could be color {}, which isn't in the palette",
+                    color.0
+                );
+            }
+            partial[[y, x]] = Cell::from_colors(candidates);
+        }
+    }
+
+    let report = puzzle.partial_solve(&mut partial, &SolveOptions::default())?;
+
+    Ok(SerializableReport {
+        partial: partial
+            .rows()
+            .into_iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| cell.can_be_iter().collect())
+                    .collect()
+            })
+            .collect(),
+        cells_left: report.cells_left,
+        solve_counts: report.solve_counts.iter().map(|(_, n)| *n).collect(),
+        interrupted: report.interrupted,
+    })
+}
+
+/// Solves as far as possible from `partial_json` (a `SerializablePartial`), given
+/// `puzzle_json` (a `SerializablePuzzle`). Returns a JSON-encoded `Result<SerializableReport,
+/// String>`: bad input or a contradiction comes back as an `Err`, instead of panicking, so that a
+/// web worker running this can't take the page down with it.
+pub fn solve_partial_bytes(puzzle_json: &[u8], partial_json: &[u8]) -> Vec<u8> {
+    let result = solve_partial(puzzle_json, partial_json).map_err(|e| format!("{e:#}"));
+    serde_json::to_vec(&result).unwrap_or_else(|e| {
+        // Not expected to happen, but still better than panicking.
+        format!(r#"{{"Err":{:?}}}"#, e.to_string()).into_bytes()
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -277,6 +395,145 @@ mod tests {
         assert_eq!(doc.license, new_doc.license);
         assert_eq!(doc.puzzle(), new_doc.puzzle());
     }
+
+    #[test]
+    fn test_share_string_versions() {
+        let mut doc = Document::from_solution(
+            crate::puzzle::Solution::blank_bw(2, 3),
+            "versions.woven".to_string(),
+        );
+        let share_string = to_woven(&mut doc).unwrap();
+        assert!(share_string.starts_with("WOVEN1-"));
+
+        let unversioned = share_string.replacen("WOVEN1-", "WOVEN-", 1);
+        assert_eq!(
+            from_woven(&unversioned).unwrap().solution().unwrap(),
+            doc.solution().unwrap()
+        );
+
+        let from_the_future = share_string.replacen("WOVEN1-", "WOVEN9-", 1);
+        let err = from_woven(&from_the_future).unwrap_err();
+        assert!(err.to_string().contains("version"));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut palette = HashMap::new();
+        palette.insert(
+            Color(0),
+            crate::puzzle::ColorInfo {
+                ch: ' ',
+                name: "white".to_string(),
+                rgb: (255, 255, 255),
+                color: Color(0),
+                corner: None,
+            },
+        );
+        palette.insert(
+            Color(1),
+            crate::puzzle::ColorInfo {
+                ch: '#',
+                name: "black".to_string(),
+                rgb: (0, 0, 0),
+                color: Color(1),
+                corner: None,
+            },
+        );
+
+        let solution = crate::puzzle::Solution {
+            clue_style: crate::puzzle::ClueStyle::Nono,
+            palette,
+            grid: vec![vec![Color(1), Color(0)], vec![Color(0), Color(1)]],
+        };
+
+        let mut doc = Document::new(
+            None,
+            Some(solution),
+            "test.json".to_string(),
+            Some("Test Title".to_string()),
+            Some("Test Description".to_string()),
+            Some("Test Author".to_string()),
+            Some("Test ID".to_string()),
+            None,
+        );
+
+        let json = to_json(&mut doc).unwrap();
+        assert!(json.contains("\"title\": \"Test Title\""));
+
+        let mut new_doc = from_json(&json).unwrap();
+
+        assert_eq!(doc.file, new_doc.file);
+        assert_eq!(doc.title, new_doc.title);
+        assert_eq!(doc.description, new_doc.description);
+        assert_eq!(doc.author, new_doc.author);
+        assert_eq!(doc.id, new_doc.id);
+        assert_eq!(doc.license, new_doc.license);
+        assert_eq!(doc.solution().unwrap(), new_doc.solution().unwrap());
+    }
+
+    #[test]
+    fn test_json_is_normalized() {
+        use crate::puzzle::{BACKGROUND, ColorInfo, Solution};
+
+        let solution = Solution {
+            clue_style: crate::puzzle::ClueStyle::Nono,
+            palette: HashMap::from([
+                (BACKGROUND, ColorInfo::default_bg()),
+                (Color(9), ColorInfo::default_fg(Color(9))),
+                (Color(4), ColorInfo::default_fg(Color(4))),
+            ]),
+            grid: vec![vec![Color(9), Color(4)]],
+        };
+        let mut doc = Document::from_solution(solution, "sparse.json".to_string());
+
+        let json = to_json(&mut doc).unwrap();
+        assert_eq!(json, to_json(&mut doc.clone()).unwrap());
+
+        let mut new_doc = from_json(&json).unwrap();
+        assert_eq!(
+            new_doc.solution().unwrap().grid,
+            vec![vec![Color(2), Color(1)]]
+        );
+    }
+
+    #[test]
+    fn solve_partial_json_boundary() {
+        let solution = crate::import::char_grid_to_solution("#.\n##\n");
+        let puzzle_json =
+            serde_json::to_vec(&SerializablePuzzle::from(&solution.to_puzzle())).unwrap();
+        let anything = vec![Color(0), Color(1)];
+        let partial_json = serde_json::to_vec(&vec![vec![anything; 2]; 2]).unwrap();
+
+        let result: Result<SerializableReport, String> =
+            serde_json::from_slice(&solve_partial_bytes(&puzzle_json, &partial_json)).unwrap();
+        let report = result.unwrap();
+        assert_eq!(report.cells_left, 0);
+        assert_eq!(
+            report.partial,
+            vec![
+                vec![vec![Color(1)], vec![Color(0)]],
+                vec![vec![Color(1)], vec![Color(1)]],
+            ]
+        );
+
+        // The report survives its own trip across the boundary:
+        let bytes = serde_json::to_vec(&report).unwrap();
+        assert_eq!(
+            serde_json::from_slice::<SerializableReport>(&bytes).unwrap(),
+            report
+        );
+
+        // Bad input is an error, not a panic:
+        for (puzzle, partial) in [
+            (&b"not json"[..], &partial_json[..]),
+            (&puzzle_json[..], &b"[[[0]]]"[..]),
+            (&puzzle_json[..], &b"[[[0, 7], [0]], [[0], [0]]]"[..]),
+        ] {
+            let result: Result<SerializableReport, String> =
+                serde_json::from_slice(&solve_partial_bytes(puzzle, partial)).unwrap();
+            assert!(result.is_err());
+        }
+    }
 }
 
 impl From<SerializableDocument> for Document {
@@ -338,10 +595,15 @@ impl From<&DynPuzzle> for SerializablePuzzle {
 
 impl From<&Solution> for SerializableSolution {
     fn from(solution: &Solution) -> Self {
+        // Keep the output stable, so that saved files diff nicely.
+        let mut solution = solution.clone();
+        solution.normalize_palette();
+        let mut palette: Vec<ColorInfo> = solution.palette.into_values().collect();
+        palette.sort_by_key(|ci| ci.color);
         SerializableSolution {
             clue_style: solution.clue_style,
-            palette: solution.palette.values().cloned().collect(),
-            grid: solution.grid.clone(),
+            palette,
+            grid: solution.grid,
         }
     }
 }
@@ -1,16 +1,18 @@
 use std::{fmt::Debug, sync::mpsc, vec};
 
-use anyhow::Context;
+use anyhow::{Context, bail};
 use colored::Colorize;
 use ndarray::{ArrayView1, ArrayViewMut1};
 
 use crate::{
     gui,
     line_solve::{
-        Cell, ModeMap, ScrubReport, SolveMode, exhaust_line, scrub_heuristic, skim_heuristic,
-        skim_line,
+        Cell, ModeMap, ScrubReport, SeparationRule, SolveMode, clue_spans, exhaust_line,
+        exhaust_line_by_enumeration, scrub_heuristic, skim_heuristic, skim_line,
+    },
+    puzzle::{
+        BACKGROUND, Clue, Color, ColorInfo, DynPuzzle, PartialSolution, Puzzle, Solution, UNSOLVED,
     },
-    puzzle::{BACKGROUND, Clue, Color, ColorInfo, PartialSolution, Puzzle, Solution, UNSOLVED},
 };
 
 pub struct SolveOptions {
@@ -18,6 +20,15 @@ pub struct SolveOptions {
     pub display_cli_progress: bool,
     pub only_solve_color: Option<Color>,
     pub max_effort: SolveMode,
+    pub separation: SeparationRule,
+    /// Lines with more unknown cells than this are never scrubbed (which is slow on long, dense
+    /// lines). This bounds the solve time, but the solver may get stuck where it wouldn't have.
+    pub scrub_cell_limit: Option<usize>,
+    /// Called with the number of cells left whenever a line makes progress. (It's an `Fn`, since
+    /// `solve_grid` only borrows the options; use a channel or a `Cell` to get data out.)
+    pub progress: Option<Box<dyn Fn(usize) + Send>>,
+    /// Checked every step; a message here stops the solve early (with `Report::interrupted` set).
+    pub terminate: Option<mpsc::Receiver<()>>,
 }
 
 impl Default for SolveOptions {
@@ -27,6 +38,10 @@ impl Default for SolveOptions {
             display_cli_progress: false,
             only_solve_color: None,
             max_effort: SolveMode::Scrub,
+            separation: SeparationRule::SameColor,
+            scrub_cell_limit: None,
+            progress: None,
+            terminate: None,
         }
     }
 }
@@ -38,6 +53,142 @@ pub struct Report {
     pub cells_left: usize,
     pub solution: Solution,
     pub solved_mask: Vec<Vec<bool>>,
+    /// Each unsolved cell, as `(x, y, candidates)`.
+    pub ambiguous: Vec<(usize, usize, Cell)>,
+    /// For each cell (indexed `[x][y]`), the line-solving step at which it became known. Cells
+    /// known from the start are 0; cells that were never determined are `usize::MAX`.
+    pub solve_order: Vec<Vec<usize>>,
+    /// The solve was stopped by `SolveOptions::terminate` before it ran out of things to try.
+    pub interrupted: bool,
+    /// How much work each row took.
+    pub row_stats: Vec<LineStats>,
+    /// How much work each column took.
+    pub col_stats: Vec<LineStats>,
+}
+
+/// How the solver got on with one row or column.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LineStats {
+    /// How many times the line was skimmed or scrubbed.
+    pub visits: usize,
+    /// The technique that last taught us something about the line (if any did).
+    pub cracked_by: Option<SolveMode>,
+}
+
+/// How many skims a scrub (or an enumeration) is worth, for the purposes of `Report::difficulty`.
+const SCRUB_WEIGHT: f32 = 5.0;
+
+/// With `SolveMode::Enumerate`, lines with more arrangements than this are scrubbed instead.
+pub const MAX_ENUMERATED_ARRANGEMENTS: usize = 100_000;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum DifficultyBand {
+    Easy,
+    Medium,
+    Hard,
+    Expert,
+    /// The solver got stuck (or was interrupted).
+    Unsolved,
+}
+
+impl DifficultyBand {
+    pub fn name(self) -> &'static str {
+        match self {
+            DifficultyBand::Easy => "easy",
+            DifficultyBand::Medium => "medium",
+            DifficultyBand::Hard => "hard",
+            DifficultyBand::Expert => "expert",
+            DifficultyBand::Unsolved => "unsolved",
+        }
+    }
+}
+
+impl Report {
+    /// Roughly, how many times each line had to be examined, with scrubs weighted more heavily
+    /// than skims. Dividing by the number of lines makes puzzles of different sizes comparable.
+    pub fn difficulty(&self) -> f32 {
+        let lines = (self.solution.x_size() + self.solution.y_size()).max(1);
+        (self.solve_counts[SolveMode::Skim] as f32
+            + SCRUB_WEIGHT
+                * (self.solve_counts[SolveMode::Scrub] + self.solve_counts[SolveMode::Enumerate])
+                    as f32)
+            / lines as f32
+    }
+
+    pub fn difficulty_band(&self) -> DifficultyBand {
+        if self.cells_left > 0 || self.interrupted {
+            return DifficultyBand::Unsolved;
+        }
+        match self.difficulty() {
+            d if d < 4.0 => DifficultyBand::Easy,
+            d if d < 10.0 => DifficultyBand::Medium,
+            d if d < 20.0 => DifficultyBand::Hard,
+            _ => DifficultyBand::Expert,
+        }
+    }
+}
+
+/// Where a solve from scratch ended up, compared to the picture the puzzle was drawn from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Comparison {
+    /// Cells the solver couldn't pin down: the puzzle needs guessing, or has another solution.
+    pub undetermined: Vec<(usize, usize)>,
+    /// Cells the solver decided on, but differently from the picture. The clues don't describe
+    /// the picture (or there's a solver bug).
+    pub disagreements: Vec<(usize, usize)>,
+}
+
+impl Report {
+    /// Compares the solver's result against `intended`, cell by cell (as `(x, y)`).
+    pub fn compare_to(&self, intended: &Solution) -> Comparison {
+        let mut comparison = Comparison::default();
+        for (x, col) in self.solution.grid.iter().enumerate() {
+            for (y, color) in col.iter().enumerate() {
+                if !self.solved_mask[x][y] {
+                    comparison.undetermined.push((x, y));
+                } else if intended.grid.get(x).and_then(|col| col.get(y)) != Some(color) {
+                    comparison.disagreements.push((x, y));
+                }
+            }
+        }
+        comparison
+    }
+}
+
+/// Attached (as context) to errors caused by a line whose clues can't be satisfied. Use
+/// `Contradiction::find` to get it back out of an `anyhow::Error`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Contradiction {
+    pub row: bool,
+    pub index: usize,
+    /// The clues, as formatted by `Debug`.
+    pub clues: String,
+}
+
+impl Contradiction {
+    fn new<C: Clue>(row: bool, index: usize, clues: &[C]) -> Contradiction {
+        Contradiction {
+            row,
+            index,
+            clues: format!("{clues:?}"),
+        }
+    }
+
+    pub fn find(err: &anyhow::Error) -> Option<&Contradiction> {
+        err.downcast_ref::<Contradiction>()
+    }
+}
+
+impl std::fmt::Display for Contradiction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} (clues: {}) can't be satisfied",
+            if self.row { "row" } else { "column" },
+            self.index + 1,
+            self.clues
+        )
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -61,6 +212,7 @@ pub struct LaneState<'a, C: Clue> {
     clues: &'a [C], // just convenience, since `row` and `index` suffice to find it again
     row: bool,
     index: ndarray::Ix,
+    rule: SeparationRule,
     per_mode: ModeMap<PerModeLaneState>,
 }
 
@@ -81,11 +233,22 @@ impl<'a, C: Clue> LaneState<'a, C> {
         format!("{}{}", if self.row { "R" } else { "C" }, self.index + 1)
     }
 
-    fn new(clues: &'a [C], row: bool, idx: usize, grid: &PartialSolution) -> LaneState<'a, C> {
+    fn contradiction(&self) -> Contradiction {
+        Contradiction::new(self.row, self.index, self.clues)
+    }
+
+    fn new(
+        clues: &'a [C],
+        row: bool,
+        idx: usize,
+        rule: SeparationRule,
+        grid: &PartialSolution,
+    ) -> LaneState<'a, C> {
         let mut res = LaneState {
             clues,
             row,
             index: idx,
+            rule,
             per_mode: ModeMap::new_uniform(PerModeLaneState::new()),
         };
         res.rescore(grid, false);
@@ -106,7 +269,9 @@ impl<'a, C: Clue> LaneState<'a, C> {
                 s.processed_score = s.score;
             }
             s.score = match mode {
-                SolveMode::Scrub => scrub_heuristic(self.clues, lane),
+                SolveMode::Scrub | SolveMode::Enumerate => {
+                    scrub_heuristic(self.clues, lane, self.rule)
+                }
                 SolveMode::Skim => skim_heuristic(self.clues, lane),
             };
         }
@@ -171,6 +336,21 @@ fn grid_to_solved_mask<C: Clue>(grid: &PartialSolution) -> Vec<Vec<bool>> {
         .collect()
 }
 
+fn grid_to_ambiguous(grid: &PartialSolution) -> Vec<(usize, usize, Cell)> {
+    grid.indexed_iter()
+        .filter(|(_, cell)| !cell.is_known())
+        .map(|((y, x), cell)| (x, y, *cell))
+        .collect()
+}
+
+fn order_to_vecs(solve_order: &ndarray::Array2<usize>) -> Vec<Vec<usize>> {
+    solve_order
+        .columns()
+        .into_iter()
+        .map(|col| col.to_vec())
+        .collect()
+}
+
 fn grid_to_solution<C: Clue>(grid: &PartialSolution, puzzle: &Puzzle<C>) -> Solution {
     let mut palette = puzzle.palette.clone();
     if grid.iter().any(|cell| !cell.is_known()) {
@@ -241,8 +421,12 @@ fn display_step<'a, C: Clue>(
     // Hackish way of getting the original score...
     let lane_arr: ndarray::Array1<Cell> = orig_lane.into();
     let (orig_score, new_score) = match mode {
-        SolveMode::Scrub => (
-            scrub_heuristic(clue_lane.clues, lane_arr.rows().into_iter().next().unwrap()),
+        SolveMode::Scrub | SolveMode::Enumerate => (
+            scrub_heuristic(
+                clue_lane.clues,
+                lane_arr.rows().into_iter().next().unwrap(),
+                clue_lane.rule,
+            ),
             clue_lane.per_mode[mode].score,
         ),
         SolveMode::Skim => (
@@ -253,19 +437,22 @@ fn display_step<'a, C: Clue>(
     println!("   {}->{}", orig_score, new_score);
 }
 
-pub type LineCache<C> = std::collections::HashMap<(Vec<C>, Vec<u32>), (ScrubReport, Vec<Cell>)>;
+pub type LineCache<C> =
+    std::collections::HashMap<(SeparationRule, Vec<C>, Vec<u32>), (ScrubReport, Vec<Cell>)>;
 
 fn op_or_cache<'a, C: Clue, F>(
     f: F,
     solve_lane: &LaneState<'a, C>,
     lane: &mut ArrayViewMut1<Cell>,
+    rule: SeparationRule,
     cache: &mut Option<LineCache<C>>,
 ) -> anyhow::Result<ScrubReport>
 where
-    F: Fn(&[C], &mut ArrayViewMut1<Cell>) -> anyhow::Result<ScrubReport>,
+    F: Fn(&[C], &mut ArrayViewMut1<Cell>, SeparationRule) -> anyhow::Result<ScrubReport>,
 {
     if let Some(cache) = cache {
         let entry = cache.entry((
+            rule,
             solve_lane.clues.to_vec(),
             lane.iter().map(|cell| cell.raw()).collect::<Vec<_>>(),
         ));
@@ -280,7 +467,7 @@ where
                 return Ok(report.clone());
             }
             std::collections::hash_map::Entry::Vacant(v) => {
-                let report = f(solve_lane.clues, lane)?;
+                let report = f(solve_lane.clues, lane, rule)?;
                 let mut cells_to_cache = vec![];
 
                 for idx in &report.affected_cells {
@@ -292,7 +479,7 @@ where
             }
         }
     } else {
-        f(solve_lane.clues, lane)
+        f(solve_lane.clues, lane, rule)
     }
 }
 
@@ -309,12 +496,13 @@ pub fn solve<C: Clue>(
 pub fn settle_solution<C: Clue>(
     puzzle: &Puzzle<C>,
     grid: &mut PartialSolution,
+    rule: SeparationRule,
 ) -> anyhow::Result<()> {
     for (idx, clue_row) in puzzle.rows.iter().enumerate() {
-        crate::line_solve::settle_line(clue_row, &mut grid.row_mut(idx))?;
+        crate::line_solve::settle_line(clue_row, &mut grid.row_mut(idx), rule)?;
     }
     for (idx, clue_col) in puzzle.cols.iter().enumerate() {
-        crate::line_solve::settle_line(clue_col, &mut grid.column_mut(idx))?;
+        crate::line_solve::settle_line(clue_col, &mut grid.column_mut(idx), rule)?;
     }
     Ok(())
 }
@@ -328,30 +516,94 @@ pub fn solve_grid<C: Clue>(
     let mut solve_lanes = vec![];
 
     for (idx, clue_row) in puzzle.rows.iter().enumerate() {
-        solve_lanes.push(LaneState::new(clue_row, true, idx, &grid));
+        solve_lanes.push(LaneState::new(
+            clue_row,
+            true,
+            idx,
+            options.separation,
+            &grid,
+        ));
     }
 
     for (idx, clue_col) in puzzle.cols.iter().enumerate() {
-        solve_lanes.push(LaneState::new(clue_col, false, idx, &grid));
+        solve_lanes.push(LaneState::new(
+            clue_col,
+            false,
+            idx,
+            options.separation,
+            &grid,
+        ));
     }
 
-    let progress = indicatif::ProgressBar::new_spinner();
-    if options.trace_solve || !options.display_cli_progress {
-        progress.finish_and_clear();
-    }
+    // A hidden bar never touches the terminal (creating and clearing a real one can leave
+    // escape codes behind).
+    let progress = if options.trace_solve || !options.display_cli_progress {
+        indicatif::ProgressBar::hidden()
+    } else {
+        indicatif::ProgressBar::new_spinner()
+    };
 
     let mut cells_left = grid.iter().filter(|c| !c.is_known()).count();
     let mut solve_counts = ModeMap::new_uniform(0);
+    let mut row_stats = vec![LineStats::default(); puzzle.rows.len()];
+    let mut col_stats = vec![LineStats::default(); puzzle.cols.len()];
 
-    let initial_allowed_failures = ModeMap {
-        skim: 10,
-        scrub: 0, /*ignored */
-    };
+    if grid.is_empty() {
+        // There are no cells to solve, so all that can go wrong is a clue with nowhere to go.
+        if puzzle
+            .rows
+            .iter()
+            .chain(puzzle.cols.iter())
+            .any(|clues| !clues.is_empty())
+        {
+            bail!("clues for an empty grid");
+        }
+        progress.finish_and_clear();
+        return Ok(Report {
+            solve_counts,
+            cells_left,
+            solution: grid_to_solution::<C>(&grid, puzzle),
+            solved_mask: grid_to_solved_mask::<C>(&grid),
+            ambiguous: vec![],
+            solve_order: order_to_vecs(&grid.map(|_| 0)),
+            interrupted: false,
+            row_stats,
+            col_stats,
+        });
+    }
+
+    let mut step = 0;
+    let mut solve_order = grid.map(|cell| if cell.is_known() { 0 } else { usize::MAX });
+
+    let initial_allowed_failures = ModeMap::from_fn(|mode| match mode {
+        SolveMode::Skim => 10,
+        // Enumeration stands in for scrubbing, rather than following it, so it's an independent
+        // check on it.
+        SolveMode::Scrub if options.max_effort == SolveMode::Enumerate => 0,
+        SolveMode::Scrub => 10,
+        SolveMode::Enumerate => 0, /*ignored */
+    });
 
     let mut allowed_failures = initial_allowed_failures;
 
     loop {
         progress.tick();
+        if let Some(terminate) = &options.terminate {
+            if terminate.try_recv().is_ok() {
+                progress.finish_and_clear();
+                return Ok(Report {
+                    solve_counts,
+                    cells_left,
+                    solution: grid_to_solution::<C>(&grid, puzzle),
+                    solved_mask: grid_to_solved_mask::<C>(&grid),
+                    ambiguous: grid_to_ambiguous(&grid),
+                    solve_order: order_to_vecs(&solve_order),
+                    interrupted: true,
+                    row_stats,
+                    col_stats,
+                });
+            }
+        }
         let mut current_mode = options.max_effort;
         for mode in SolveMode::all() {
             if allowed_failures[*mode] > 0 {
@@ -371,6 +623,11 @@ pub fn solve_grid<C: Clue>(
                             cells_left,
                             solution: grid_to_solution::<C>(&grid, puzzle),
                             solved_mask: grid_to_solved_mask::<C>(&grid),
+                            ambiguous: grid_to_ambiguous(&grid),
+                            solve_order: order_to_vecs(&solve_order),
+                            interrupted: false,
+                            row_stats,
+                            col_stats,
                         });
                     } else {
                         allowed_failures[current_mode] = 0; // try the next mode
@@ -389,24 +646,64 @@ pub fn solve_grid<C: Clue>(
 
             let orig_version_of_line: Vec<Cell> = best_grid_lane.iter().cloned().collect();
 
-            solve_counts[current_mode] += 1;
+            let too_expensive = current_mode == SolveMode::Scrub
+                && options.scrub_cell_limit.is_some_and(|limit| {
+                    best_grid_lane.iter().filter(|c| !c.is_known()).count() > limit
+                });
+            if !too_expensive {
+                // Skipped lines don't count toward the difficulty.
+                solve_counts[current_mode] += 1;
+            }
+            step += 1;
             let mut report = match current_mode {
+                SolveMode::Scrub if too_expensive => {
+                    // Carry on as though scrubbing found nothing.
+                    ScrubReport {
+                        affected_cells: vec![],
+                    }
+                }
                 SolveMode::Scrub => op_or_cache(
                     exhaust_line,
                     best_clue_lane,
                     &mut best_grid_lane,
+                    options.separation,
                     line_cache,
                 )
                 .context(format!(
                     "scrubbing {:?} with {:?}",
                     best_clue_lane, orig_version_of_line
-                ))?,
-                SolveMode::Skim => {
-                    skim_line(best_clue_lane.clues, &mut best_grid_lane).context(format!(
-                        "skimming {:?} with {:?}",
-                        best_clue_lane, orig_version_of_line
-                    ))?
-                }
+                ))
+                .with_context(|| best_clue_lane.contradiction())?,
+                // Not cached, since the cache may hold what scrubbing found.
+                SolveMode::Enumerate => exhaust_line_by_enumeration(
+                    best_clue_lane.clues,
+                    &mut best_grid_lane,
+                    options.separation,
+                    MAX_ENUMERATED_ARRANGEMENTS,
+                )
+                .transpose()
+                .unwrap_or_else(|| {
+                    exhaust_line(
+                        best_clue_lane.clues,
+                        &mut best_grid_lane,
+                        options.separation,
+                    )
+                })
+                .context(format!(
+                    "enumerating {:?} with {:?}",
+                    best_clue_lane, orig_version_of_line
+                ))
+                .with_context(|| best_clue_lane.contradiction())?,
+                SolveMode::Skim => skim_line(
+                    best_clue_lane.clues,
+                    &mut best_grid_lane,
+                    options.separation,
+                )
+                .context(format!(
+                    "skimming {:?} with {:?}",
+                    best_clue_lane, orig_version_of_line
+                ))
+                .with_context(|| best_clue_lane.contradiction())?,
             };
             best_clue_lane.per_mode[current_mode].processed = true;
 
@@ -419,12 +716,42 @@ pub fn solve_grid<C: Clue>(
                 );
             }
 
+            let stats = if best_clue_lane.row {
+                &mut row_stats[best_clue_lane.index]
+            } else {
+                &mut col_stats[best_clue_lane.index]
+            };
+            stats.visits += 1;
+            if !report.affected_cells.is_empty() {
+                stats.cracked_by = Some(current_mode);
+            }
+
             let known_before = orig_version_of_line.iter().filter(|c| c.is_known()).count();
             let known_after = best_grid_lane.iter().filter(|c| c.is_known()).count();
 
+            for (i, (orig, now)) in orig_version_of_line
+                .iter()
+                .zip(best_grid_lane.iter())
+                .enumerate()
+            {
+                if !orig.is_known() && now.is_known() {
+                    let (x, y) = if best_clue_lane.row {
+                        (i, best_clue_lane.index)
+                    } else {
+                        (best_clue_lane.index, i)
+                    };
+                    solve_order[[y, x]] = step;
+                }
+            }
+
             best_clue_lane.rescore(grid, /*was_processed=*/ true);
 
             cells_left -= known_after - known_before;
+            if known_after != known_before {
+                if let Some(progress) = &options.progress {
+                    progress(cells_left);
+                }
+            }
 
             if options.trace_solve {
                 display_step(
@@ -446,6 +773,11 @@ pub fn solve_grid<C: Clue>(
                 cells_left,
                 solution: grid_to_solution::<C>(&grid, puzzle),
                 solved_mask: grid_to_solved_mask::<C>(&grid),
+                ambiguous: grid_to_ambiguous(&grid),
+                solve_order: order_to_vecs(&solve_order),
+                interrupted: false,
+                row_stats,
+                col_stats,
             });
         }
 
@@ -475,24 +807,35 @@ pub fn solve_grid<C: Clue>(
     }
 }
 
-fn analyze_line<C: Clue>(clues: &[C], lane: ArrayView1<Cell>) -> LineStatus {
+/// With `only_color`, the only progress that counts is finding cells of that color (like
+/// `SolveOptions::only_solve_color`).
+fn analyze_line<C: Clue>(
+    clues: &[C],
+    lane: ArrayView1<Cell>,
+    only_color: Option<Color>,
+    rule: SeparationRule,
+) -> LineStatus {
     let any_newly_known = |original_lane: ArrayView1<Cell>, new_lane: ArrayView1<Cell>| -> bool {
         original_lane
             .iter()
             .zip(new_lane.iter())
-            .any(|(orig, new)| !orig.is_known() && new.is_known())
+            .any(|(orig, new)| {
+                !orig.is_known()
+                    && new.is_known()
+                    && only_color.is_none_or(|color| new.is_known_to_be(color))
+            })
     };
 
     // Try skimming
     let mut skim_lane = lane.to_owned();
-    skim_line(clues, &mut skim_lane.view_mut())?;
+    skim_line(clues, &mut skim_lane.view_mut(), rule)?;
     if any_newly_known(lane, skim_lane.view()) {
         return Ok(Some(SolveMode::Skim));
     }
 
     // Try scrubbing
     let mut scrub_lane = lane.to_owned();
-    exhaust_line(clues, &mut scrub_lane.view_mut())?;
+    exhaust_line(clues, &mut scrub_lane.view_mut(), rule)?;
     if any_newly_known(lane, scrub_lane.view()) {
         return Ok(Some(SolveMode::Scrub));
     }
@@ -503,97 +846,635 @@ fn analyze_line<C: Clue>(clues: &[C], lane: ArrayView1<Cell>) -> LineStatus {
 pub fn analyze_lines<C: Clue>(
     puzzle: &Puzzle<C>,
     grid: &PartialSolution,
+    only_color: Option<Color>,
+    rule: SeparationRule,
 ) -> (Vec<LineStatus>, Vec<LineStatus>) {
     let mut row_techniques = vec![];
     for (idx, clues) in puzzle.rows.iter().enumerate() {
-        row_techniques.push(analyze_line(clues, grid.row(idx)));
+        row_techniques.push(
+            analyze_line(clues, grid.row(idx), only_color, rule)
+                .with_context(|| Contradiction::new(true, idx, clues)),
+        );
     }
 
     let mut col_techniques = vec![];
     for (idx, clues) in puzzle.cols.iter().enumerate() {
-        col_techniques.push(analyze_line(clues, grid.column(idx)));
+        col_techniques.push(
+            analyze_line(clues, grid.column(idx), only_color, rule)
+                .with_context(|| Contradiction::new(false, idx, clues)),
+        );
     }
 
     (row_techniques, col_techniques)
 }
 
+/// Finds one cell that can be deduced from `grid`, preferring the easiest technique that works.
+/// Returns `(x, y, color)`.
+pub fn hint<C: Clue>(
+    puzzle: &Puzzle<C>,
+    grid: &PartialSolution,
+    rule: SeparationRule,
+) -> Option<(usize, usize, Color)> {
+    let (row_techniques, col_techniques) = analyze_lines(puzzle, grid, None, rule);
+
+    for mode in SolveMode::all() {
+        for (row, techniques, lines) in [
+            (true, &row_techniques, &puzzle.rows),
+            (false, &col_techniques, &puzzle.cols),
+        ] {
+            for (idx, technique) in techniques.iter().enumerate() {
+                if !matches!(technique, Ok(Some(m)) if m == mode) {
+                    continue;
+                }
+                let orig_lane = if row { grid.row(idx) } else { grid.column(idx) };
+                let mut lane = orig_lane.to_owned();
+                let result = match mode {
+                    SolveMode::Skim => skim_line(&lines[idx], &mut lane.view_mut(), rule),
+                    SolveMode::Scrub | SolveMode::Enumerate => {
+                        exhaust_line(&lines[idx], &mut lane.view_mut(), rule)
+                    }
+                };
+                if result.is_err() {
+                    continue;
+                }
+                for (i, (orig, new)) in orig_lane.iter().zip(lane.iter()).enumerate() {
+                    if let (false, Some(color)) = (orig.is_known(), new.known_or()) {
+                        return Some(if row {
+                            (i, idx, color)
+                        } else {
+                            (idx, i, color)
+                        });
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+/// What each cell of `grid` could still be, judging each row and column on its own. An unknown
+/// cell with only one candidate left is one the player could fill in right now.
+pub fn candidates<C: Clue>(
+    puzzle: &Puzzle<C>,
+    grid: &PartialSolution,
+    rule: SeparationRule,
+) -> PartialSolution {
+    let mut start = grid.clone();
+    for cell in start.iter_mut() {
+        if !cell.is_known() {
+            *cell = Cell::new(puzzle);
+        }
+    }
+
+    let mut res = start.clone();
+    for (row, lines) in [(true, &puzzle.rows), (false, &puzzle.cols)] {
+        for (idx, clues) in lines.iter().enumerate() {
+            let orig_lane = if row {
+                start.row(idx)
+            } else {
+                start.column(idx)
+            };
+            let mut lane = orig_lane.to_owned();
+            // A contradictory line tells us nothing about its cells:
+            if exhaust_line(clues, &mut lane.view_mut(), rule).is_err() {
+                continue;
+            }
+            let mut res_lane = if row {
+                res.row_mut(idx)
+            } else {
+                res.column_mut(idx)
+            };
+            for (cell, learned) in res_lane.iter_mut().zip(lane.iter()) {
+                let _ = cell.learn_intersect(*learned);
+            }
+        }
+    }
+    res
+}
+
+/// Which cells the `clue_idx`th clue of a row (or column) could cover, as an inclusive range,
+/// judging that line on its own. `None` if the line already contradicts its clues.
+pub fn clue_span<C: Clue>(
+    puzzle: &Puzzle<C>,
+    grid: &PartialSolution,
+    row: bool,
+    lane: usize,
+    clue_idx: usize,
+    rule: SeparationRule,
+) -> Option<(usize, usize)> {
+    let (clues, cells) = if row {
+        (&puzzle.rows[lane], grid.row(lane))
+    } else {
+        (&puzzle.cols[lane], grid.column(lane))
+    };
+    clue_spans(clues, cells, rule).ok()?.get(clue_idx).copied()
+}
+
+/// Which rows and columns skimming fully determines, starting from a blank grid. These are the
+/// places to start a puzzle.
+pub fn forced_lines<C: Clue>(puzzle: &Puzzle<C>, rule: SeparationRule) -> (Vec<bool>, Vec<bool>) {
+    let forced = |clues: &Vec<C>, len: usize| {
+        let mut lane = ndarray::Array1::from_elem(len, Cell::new(puzzle));
+        skim_line(clues, &mut lane.view_mut(), rule).is_ok()
+            && lane.iter().all(|cell| cell.is_known())
+    };
+
+    (
+        puzzle
+            .rows
+            .iter()
+            .map(|clues| forced(clues, puzzle.cols.len()))
+            .collect(),
+        puzzle
+            .cols
+            .iter()
+            .map(|clues| forced(clues, puzzle.rows.len()))
+            .collect(),
+    )
+}
+
+/// Up to `max` distinct solutions to `puzzle`. Exactly one means the puzzle is unique (though
+/// maybe not line-solvable); none means it's contradictory. Whenever line-solving stalls, this
+/// branches on the undetermined cell with the fewest candidates.
+pub fn enumerate_solutions(puzzle: &DynPuzzle, max: usize, rule: SeparationRule) -> Vec<Solution> {
+    enumerate_solutions_capped(puzzle, max, usize::MAX, rule).expect("no guess limit")
+}
+
+/// How many guesses `enumerate_solutions_capped` gets when checking uniqueness in bulk (for the
+/// library index and the gallery).
+pub const UNIQUENESS_GUESS_LIMIT: usize = 100;
+
+/// Like `enumerate_solutions`, but gives up (returning `None`) rather than make more than
+/// `max_guesses` guesses, since some puzzles need exponentially many.
+pub fn enumerate_solutions_capped(
+    puzzle: &DynPuzzle,
+    max: usize,
+    max_guesses: usize,
+    rule: SeparationRule,
+) -> Option<Vec<Solution>> {
+    puzzle.specialize(
+        |p| enumerate_solutions_of(p, max, max_guesses, rule),
+        |p| enumerate_solutions_of(p, max, max_guesses, rule),
+    )
+}
+
+/// Whether `puzzle` has exactly one solution, or `None` if that takes more than `max_guesses`
+/// guesses to find out.
+pub fn is_unique(puzzle: &DynPuzzle, max_guesses: usize, rule: SeparationRule) -> Option<bool> {
+    enumerate_solutions_capped(puzzle, 2, max_guesses, rule).map(|solutions| solutions.len() == 1)
+}
+
+fn enumerate_solutions_of<C: Clue>(
+    puzzle: &Puzzle<C>,
+    max: usize,
+    max_guesses: usize,
+    rule: SeparationRule,
+) -> Option<Vec<Solution>> {
+    let options = SolveOptions {
+        separation: rule,
+        ..SolveOptions::default()
+    };
+    let mut res = vec![];
+    if max > 0 {
+        let grid =
+            PartialSolution::from_elem((puzzle.rows.len(), puzzle.cols.len()), Cell::new(puzzle));
+        let mut guesses_left = max_guesses;
+        let finished = enumerate_from(
+            puzzle,
+            &mut Some(LineCache::new()),
+            grid,
+            max,
+            &mut guesses_left,
+            &options,
+            &mut res,
+        );
+        if !finished {
+            return None;
+        }
+    }
+    Some(res)
+}
+
+/// Returns `false` if it ran out of guesses.
+fn enumerate_from<C: Clue>(
+    puzzle: &Puzzle<C>,
+    line_cache: &mut Option<LineCache<C>>,
+    mut grid: PartialSolution,
+    max: usize,
+    guesses_left: &mut usize,
+    options: &SolveOptions,
+    res: &mut Vec<Solution>,
+) -> bool {
+    let report = match solve_grid(puzzle, line_cache, options, &mut grid) {
+        Ok(report) => report,
+        Err(_) => return true, // A contradiction: this branch has no solutions.
+    };
+
+    if report.cells_left == 0 {
+        if grid_satisfies(puzzle, &grid, options.separation) {
+            res.push(report.solution);
+        }
+        return true;
+    }
+
+    let Some((x, y, cell)) = report
+        .ambiguous
+        .iter()
+        .min_by_key(|(_, _, cell)| cell.candidate_count())
+        .copied()
+    else {
+        return true;
+    };
+
+    for color in cell.can_be_iter() {
+        if res.len() >= max {
+            return true;
+        }
+        if *guesses_left == 0 {
+            return false;
+        }
+        *guesses_left -= 1;
+        let mut guess = grid.clone();
+        guess[[y, x]] = Cell::from_color(color);
+        if !enumerate_from(puzzle, line_cache, guess, max, guesses_left, options, res) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Whether every line of the finished `grid` fits its clues. The line solver stops as soon as every
+/// cell is known, so lines it didn't revisit might not.
+fn grid_satisfies<C: Clue>(
+    puzzle: &Puzzle<C>,
+    grid: &PartialSolution,
+    rule: SeparationRule,
+) -> bool {
+    let mut grid = grid.clone();
+    (0..puzzle.rows.len())
+        .all(|y| exhaust_line(&puzzle.rows[y], &mut grid.row_mut(y), rule).is_ok())
+        && (0..puzzle.cols.len())
+            .all(|x| exhaust_line(&puzzle.cols[x], &mut grid.column_mut(x), rule).is_ok())
+}
+
+/// The default `closeness` for `disambig_display_threshold`.
+pub const DEFAULT_DISAMBIG_THRESHOLD: f32 = 0.75;
+
+/// The worst score (from `disambig_candidates`) worth showing. A suggestion must be `closeness`
+/// of the way from no improvement to the best one: 1.0 shows only the best, and 0.0 shows
+/// everything that helps at all. If some change disambiguates completely, only those are shown.
+pub fn disambig_display_threshold(report: &[Vec<(Color, f32)>], closeness: f32) -> f32 {
+    let best = report
+        .iter()
+        .flatten()
+        .map(|(_, score)| *score)
+        .fold(f32::MAX, f32::min);
+
+    if best == 0.0 {
+        0.0
+    } else {
+        1.0 - (1.0 - best) * closeness
+    }
+}
+
+/// The single-cell changes worth trying first: only cells in a row or column that the solver
+/// couldn't finish, and only to the colors of their neighbors (or the background). Changes far
+/// from the ambiguity rarely help, and colors that appear nowhere nearby rarely fit the clues.
+fn focused_changes(s: &Solution, solved_mask: &[Vec<bool>]) -> Vec<(usize, usize, Vec<Color>)> {
+    let (x_size, y_size) = (s.x_size(), s.y_size());
+    let open_cols: Vec<bool> = (0..x_size)
+        .map(|x| (0..y_size).any(|y| !solved_mask[x][y]))
+        .collect();
+    let open_rows: Vec<bool> = (0..y_size)
+        .map(|y| (0..x_size).any(|x| !solved_mask[x][y]))
+        .collect();
+
+    let mut res = vec![];
+    for x in 0..x_size {
+        for y in 0..y_size {
+            if !open_cols[x] && !open_rows[y] {
+                continue;
+            }
+            let mut colors = vec![BACKGROUND];
+            for nx in x.saturating_sub(1)..(x + 2).min(x_size) {
+                for ny in y.saturating_sub(1)..(y + 2).min(y_size) {
+                    let c = s.grid[nx][ny];
+                    if !colors.contains(&c) {
+                        colors.push(c);
+                    }
+                }
+            }
+            colors.retain(|c| *c != s.grid[x][y]);
+            res.push((x, y, colors));
+        }
+    }
+    res
+}
+
+/// Every single-cell change.
+fn all_changes(s: &Solution) -> Vec<(usize, usize, Vec<Color>)> {
+    let mut res = vec![];
+    for x in 0..s.x_size() {
+        for y in 0..s.y_size() {
+            let colors = s
+                .palette
+                .keys()
+                .filter(|c| **c != s.grid[x][y])
+                .cloned()
+                .collect();
+            res.push((x, y, colors));
+        }
+    }
+    res
+}
+
+/// Every single-cell change that isn't in `focused`.
+fn remaining_changes(
+    s: &Solution,
+    focused: &[(usize, usize, Vec<Color>)],
+) -> Vec<(usize, usize, Vec<Color>)> {
+    let mut tried = vec![vec![vec![]; s.y_size()]; s.x_size()];
+    for (x, y, colors) in focused {
+        tried[*x][*y] = colors.clone();
+    }
+    let mut res = vec![];
+    for (x, y, mut colors) in all_changes(s) {
+        colors.retain(|c| !tried[x][y].contains(c));
+        if !colors.is_empty() {
+            res.push((x, y, colors));
+        }
+    }
+    res
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ChangesOutcome {
+    Finished,
+    /// Some change made the puzzle completely solvable, so nothing else can do better.
+    Decided,
+    Terminated,
+}
+
+/// Tries each of `changes`, recording the best color for each cell in `res` (unless `res`
+/// already has something better). A cell stops trying colors once one makes the puzzle solvable.
+/// `done` counts changes (for progress) and `probes` counts solves, across calls.
+#[allow(clippy::too_many_arguments)]
+async fn try_changes(
+    s: &Solution,
+    solve_cache: &mut crate::puzzle::DynSolveCache,
+    orig_cells_left: usize,
+    changes: Vec<(usize, usize, Vec<Color>)>,
+    res: &mut [Vec<(Color, f32)>],
+    (done, total): (&mut usize, usize),
+    probes: &mut usize,
+    progress: &mpsc::Sender<f32>,
+    // `&mut`, not `&`: a `&Receiver` held across an `.await` would make the future `!Send`.
+    terminate: &mut mpsc::Receiver<()>,
+) -> ChangesOutcome {
+    let mut outcome = ChangesOutcome::Finished;
+    for (x, y, colors) in changes {
+        let mut best_result = std::usize::MAX;
+        let mut best_color = BACKGROUND;
+
+        for new_col in colors {
+            let mut new_grid = s.grid.clone();
+            new_grid[x][y] = new_col;
+            let new_solution = Solution {
+                grid: new_grid,
+                ..s.clone()
+            };
+
+            *probes += 1;
+            let Report {
+                cells_left: new_cells_left,
+                ..
+            } = solve_cache.solve(&new_solution.to_puzzle()).expect("");
+
+            if new_cells_left < best_result {
+                best_result = new_cells_left;
+                best_color = new_col;
+            }
+            if new_cells_left == 0 {
+                outcome = ChangesOutcome::Decided;
+                break;
+            }
+        }
+
+        if *done % 5 == 0 {
+            let _ = progress.send(*done as f32 / total as f32);
+        }
+        *done += 1;
+
+        gui::yield_now().await;
+
+        let score = (best_result as f32) / (orig_cells_left as f32);
+        if best_result != std::usize::MAX && score < res[x][y].1 {
+            res[x][y] = (best_color, score);
+        }
+
+        if terminate.try_recv().is_ok() {
+            return ChangesOutcome::Terminated;
+        }
+    }
+    outcome
+}
+
 pub async fn disambig_candidates(
     s: &Solution,
     progress: mpsc::Sender<f32>,
     terminate: mpsc::Receiver<()>,
 ) -> Vec<Vec<(Color, f32)>> {
+    disambig_search(s, progress, terminate).await.0
+}
+
+/// Tries the `focused_changes` first. If one of them makes the puzzle solvable, that's as good
+/// as it gets, and the rest aren't tried; otherwise, so that the best change is found even if
+/// it's far from the ambiguity, every other change is tried too. Also returns how many solves it
+/// took.
+async fn disambig_search(
+    s: &Solution,
+    progress: mpsc::Sender<f32>,
+    mut terminate: mpsc::Receiver<()>,
+) -> (Vec<Vec<(Color, f32)>>, usize) {
     let mut solve_cache = crate::puzzle::DynSolveCache::new();
 
     let p = s.to_puzzle();
     // Probably redundant, but a small cost compared to the rest!
     let Report {
         cells_left: orig_cells_left,
+        solved_mask,
         ..
     } = solve_cache
         .solve(&p)
         .expect("started from a solution; shouldn't be possible!");
 
-    let mut res = vec![vec![(BACKGROUND, 0.0); s.grid.first().unwrap().len()]; s.grid.len()];
     if orig_cells_left == 0 {
         // TODO: probably send a result
-        progress.send(0.0).unwrap();
-        return res;
+        let _ = progress.send(0.0);
+        return (vec![vec![(BACKGROUND, 0.0); s.y_size()]; s.x_size()], 0);
     }
 
-    for x in 0..s.x_size() {
-        for y in 0..s.y_size() {
-            let mut best_result = std::usize::MAX;
-            let mut best_color = BACKGROUND;
+    let mut res = vec![vec![(BACKGROUND, 1.0); s.y_size()]; s.x_size()];
+
+    let focused = focused_changes(s, &solved_mask);
+    let remaining = remaining_changes(s, &focused);
+    let total = focused.len() + remaining.len();
+    let mut done = 0;
+    let mut probes = 0;
+
+    for changes in [focused, remaining] {
+        let outcome = try_changes(
+            s,
+            &mut solve_cache,
+            orig_cells_left,
+            changes,
+            &mut res,
+            (&mut done, total),
+            &mut probes,
+            &progress,
+            &mut terminate,
+        )
+        .await;
+        match outcome {
+            ChangesOutcome::Finished => {}
+            ChangesOutcome::Decided => break,
+            ChangesOutcome::Terminated => return (res, probes),
+        }
+    }
+    // Nobody may be listening (the CLI doesn't).
+    let _ = progress.send(1.0);
 
-            for new_col in s.palette.keys() {
-                if *new_col == s.grid[x][y] {
-                    continue;
-                }
-                let mut new_grid = s.grid.clone();
-                new_grid[x][y] = *new_col;
-                let new_solution = Solution {
-                    grid: new_grid,
-                    ..s.clone()
-                };
+    (res, probes)
+}
 
-                let Report {
-                    cells_left: new_cells_left,
-                    ..
-                } = solve_cache.solve(&new_solution.to_puzzle()).expect("");
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
 
-                if new_cells_left < best_result {
-                    best_result = new_cells_left;
-                    best_color = *new_col;
-                }
-            }
+    use crate::puzzle::{ColorInfo, DynPuzzle, Nono};
 
-            if y % 5 == 0 {
-                progress
-                    .send((x * s.y_size() + y) as f32 / (s.x_size() * s.y_size()) as f32)
-                    .unwrap();
-            }
+    use super::*;
 
-            gui::yield_now().await;
+    #[test]
+    fn test_empty_puzzle() {
+        let empty = crate::import::char_grid_to_solution("");
+        assert_eq!((empty.x_size(), empty.y_size()), (0, 0));
+        let DynPuzzle::Nono(puzzle) = empty.to_puzzle() else {
+            panic!("expected a nonogram");
+        };
+        let report = solve(&puzzle, &mut None, &SolveOptions::default()).unwrap();
+        assert_eq!(report.cells_left, 0);
+        assert_eq!(report.solution.x_size(), 0);
 
-            res[x][y] = (best_color, (best_result as f32) / (orig_cells_left as f32));
+        let mut palette = HashMap::new();
+        palette.insert(BACKGROUND, ColorInfo::default_bg());
+        palette.insert(Color(1), ColorInfo::default_fg(Color(1)));
+        let nowhere_to_go = Puzzle {
+            palette,
+            rows: vec![vec![Nono {
+                color: Color(1),
+                count: 1,
+            }]],
+            cols: vec![],
+        };
+        assert!(solve(&nowhere_to_go, &mut None, &SolveOptions::default()).is_err());
+    }
 
-            if terminate.try_recv().is_ok() {
-                return res;
-            }
-        }
+    #[test]
+    fn test_compare_to_intended() {
+        // Two diagonal pixels: the clues can't tell which diagonal.
+        let intended = crate::import::char_grid_to_solution("#.\n.#\n");
+        let DynPuzzle::Nono(puzzle) = intended.to_puzzle() else {
+            panic!("expected a nonogram");
+        };
+        let report = solve(&puzzle, &mut None, &SolveOptions::default()).unwrap();
+        let comparison = report.compare_to(&intended);
+        assert_eq!(comparison.undetermined.len(), 4);
+        assert!(comparison.disagreements.is_empty());
+
+        let forced = crate::import::char_grid_to_solution("##\n.#\n");
+        let DynPuzzle::Nono(puzzle) = forced.to_puzzle() else {
+            panic!("expected a nonogram");
+        };
+        let report = solve(&puzzle, &mut None, &SolveOptions::default()).unwrap();
+        assert_eq!(report.compare_to(&forced), Comparison::default());
+
+        let mut wrong = forced.clone();
+        wrong.grid[0][1] = Color(1);
+        assert_eq!(report.compare_to(&wrong).disagreements, vec![(0, 1)]);
     }
-    progress.send(1.0).unwrap();
 
-    return res;
-}
+    #[test]
+    fn test_focused_changes() {
+        let s = crate::import::char_grid_to_solution("#..\n...\n");
+        let mut solved_mask = vec![vec![true; 2]; 3];
+        solved_mask[0][0] = false;
 
-#[cfg(test)]
-mod tests {
-    use std::collections::HashMap;
+        let changes = focused_changes(&s, &solved_mask);
+        // Only the unfinished row and column, and never to a color from far away:
+        assert_eq!(
+            changes,
+            vec![
+                (0, 0, vec![BACKGROUND]),
+                (0, 1, vec![Color(1)]),
+                (1, 0, vec![Color(1)]),
+                (2, 0, vec![]),
+            ]
+        );
+    }
 
-    use crate::puzzle::{ColorInfo, Nono};
+    #[test]
+    fn test_remaining_changes() {
+        let s = crate::import::char_grid_to_solution("#..\n...\n");
+        let mut solved_mask = vec![vec![true; 2]; 3];
+        solved_mask[0][0] = false;
+
+        // Between them, every change gets tried exactly once:
+        let focused = focused_changes(&s, &solved_mask);
+        let remaining = remaining_changes(&s, &focused);
+        let mut flat: Vec<(usize, usize, Color)> = focused
+            .iter()
+            .chain(&remaining)
+            .flat_map(|(x, y, colors)| colors.iter().map(|c| (*x, *y, *c)))
+            .collect();
+        let mut expected: Vec<(usize, usize, Color)> = all_changes(&s)
+            .iter()
+            .flat_map(|(x, y, colors)| colors.iter().map(|c| (*x, *y, *c)))
+            .collect();
+        flat.sort();
+        expected.sort();
+        assert_eq!(flat, expected);
+    }
 
-    use super::*;
+    #[test]
+    fn disambiguating_nearby_skips_the_rest() {
+        // Either diagonal fits the clues in the corner; everything else is empty, and solved.
+        let s = crate::import::char_grid_to_solution("#.....\n.#....\n......\n......\n");
+        let all_probes: usize = all_changes(&s).iter().map(|(_, _, c)| c.len()).sum();
+
+        let (res, probes) = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap()
+            .block_on(disambig_search(&s, mpsc::channel().0, mpsc::channel().1));
+        assert!(probes < all_probes, "{probes} vs. {all_probes}");
+        // Clearing either black cell settles it:
+        assert_eq!(res[1][1], (BACKGROUND, 0.0));
+    }
+
+    #[test]
+    fn test_disambig_display_threshold() {
+        let report = vec![
+            vec![(Color(1), 0.6), (Color(1), 0.8)],
+            vec![(BACKGROUND, 1.0); 2],
+        ];
+        // The best brings it to 60%; halfway from there to no improvement is 80%.
+        assert!((disambig_display_threshold(&report, 0.5) - 0.8).abs() < 1e-6);
+        assert!((disambig_display_threshold(&report, 1.0) - 0.6).abs() < 1e-6);
+        assert!((disambig_display_threshold(&report, 0.0) - 1.0).abs() < 1e-6);
+
+        let complete = vec![vec![(Color(1), 0.0), (Color(1), 0.1)]];
+        assert_eq!(disambig_display_threshold(&complete, 0.0), 0.0);
+    }
 
     #[test]
     fn test_analyze_lines() {
@@ -617,7 +1498,7 @@ mod tests {
         grid[[0, 0]] = Cell::from_color(BACKGROUND);
         grid[[1, 1]] = Cell::from_color(BACKGROUND);
 
-        let (row_tech, col_tech) = analyze_lines(&puzzle, &grid);
+        let (row_tech, col_tech) = analyze_lines(&puzzle, &grid, None, SeparationRule::default());
 
         assert_eq!(
             row_tech.into_iter().map(|r| r.ok()).collect::<Vec<_>>(),
@@ -625,6 +1506,180 @@ mod tests {
         );
         assert!(col_tech[0].as_ref().is_ok());
         assert!(col_tech[1].is_err());
+
+        let contradiction = Contradiction::find(col_tech[1].as_ref().unwrap_err()).unwrap();
+        assert_eq!((contradiction.row, contradiction.index), (false, 1));
+        assert!(contradiction.to_string().starts_with("column 2"));
+
+        // Each row only learns where its foreground cell is.
+        let (row_tech, _) =
+            analyze_lines(&puzzle, &grid, Some(BACKGROUND), SeparationRule::default());
+        assert!(row_tech.iter().all(|r| matches!(r, Ok(None))));
+        let (row_tech, _) =
+            analyze_lines(&puzzle, &grid, Some(Color(1)), SeparationRule::default());
+        assert!(
+            row_tech
+                .iter()
+                .all(|r| matches!(r, Ok(Some(SolveMode::Skim))))
+        );
+
+        let err = solve(&puzzle, &mut None, &SolveOptions::default()).unwrap_err();
+        assert!(Contradiction::find(&err).is_some());
+    }
+
+    #[test]
+    fn test_forced_lines() {
+        let mut palette = HashMap::new();
+        palette.insert(BACKGROUND, ColorInfo::default_bg());
+        palette.insert(Color(1), ColorInfo::default_fg(Color(1)));
+
+        let clue = |counts: &[u16]| {
+            counts
+                .iter()
+                .map(|count| Nono {
+                    color: Color(1),
+                    count: *count,
+                })
+                .collect::<Vec<_>>()
+        };
+        let puzzle = Puzzle {
+            palette,
+            rows: vec![clue(&[3]), clue(&[1, 1]), clue(&[1]), clue(&[])],
+            cols: vec![clue(&[4]), clue(&[2]), clue(&[1, 2])],
+        };
+
+        let (rows, cols) = forced_lines(&puzzle, SeparationRule::default());
+        assert_eq!(rows, vec![true, true, false, true]);
+        assert_eq!(cols, vec![true, false, true]);
+
+        // If blocks can touch, "1 1" and "1 2" have room to move:
+        let (rows, cols) = forced_lines(&puzzle, SeparationRule::Never);
+        assert_eq!(rows, vec![true, false, false, true]);
+        assert_eq!(cols, vec![true, false, false]);
+    }
+
+    #[test]
+    fn test_hint() {
+        let mut palette = HashMap::new();
+        palette.insert(BACKGROUND, ColorInfo::default_bg());
+        palette.insert(Color(1), ColorInfo::default_fg(Color(1)));
+
+        let clue = |n| {
+            vec![Nono {
+                color: Color(1),
+                count: n,
+            }]
+        };
+        let puzzle = Puzzle {
+            palette,
+            rows: vec![clue(1), clue(2)],
+            cols: vec![clue(2), clue(1)],
+        };
+
+        let mut grid = PartialSolution::from_elem((2, 2), Cell::new(&puzzle));
+        grid[[1, 0]] = Cell::from_color(Color(1));
+        grid[[1, 1]] = Cell::from_color(Color(1));
+
+        // Only the first row and column have anything left to learn:
+        let (x, y, color) = hint(&puzzle, &grid, SeparationRule::default()).unwrap();
+        assert!(x == 0 || y == 0);
+        assert!(!grid[[y, x]].is_known());
+        assert_eq!(
+            color,
+            if (x, y) == (0, 0) {
+                Color(1)
+            } else {
+                BACKGROUND
+            }
+        );
+
+        grid[[0, 0]] = Cell::from_color(Color(1));
+        grid[[0, 1]] = Cell::from_color(BACKGROUND);
+        assert_eq!(hint(&puzzle, &grid, SeparationRule::default()), None);
+    }
+
+    #[test]
+    fn test_line_stats() {
+        let puzzle = crate::import::solution_to_puzzle(&crate::import::char_grid_to_solution(
+            "#.#.\n####\n.##.\n#..#\n",
+        ));
+        let report = solve(&puzzle, &mut None, &SolveOptions::default()).unwrap();
+        assert_eq!(report.row_stats.len(), 4);
+        assert_eq!(report.col_stats.len(), 4);
+
+        let visits: usize = report
+            .row_stats
+            .iter()
+            .chain(report.col_stats.iter())
+            .map(|stats| stats.visits)
+            .sum();
+        assert_eq!(
+            visits,
+            report
+                .solve_counts
+                .iter()
+                .map(|(_, count)| count)
+                .sum::<usize>()
+        );
+        // The full row is where any solver would start:
+        assert_eq!(report.row_stats[1].cracked_by, Some(SolveMode::Skim));
+
+        assert_eq!(
+            puzzle.per_line_difficulty().unwrap(),
+            (report.row_stats, report.col_stats)
+        );
+    }
+
+    #[test]
+    fn test_scrub_cell_limit() {
+        let mut doc =
+            crate::import::load_path(&"examples/png/carry_on_bag.png".into(), None).unwrap();
+        let puzzle = doc.puzzle().assume_nono();
+
+        let limited = SolveOptions {
+            scrub_cell_limit: Some(0),
+            ..SolveOptions::default()
+        };
+        let report = solve(puzzle, &mut None, &limited).unwrap();
+        assert!(report.cells_left > 0);
+        // Every line it would have scrubbed was skipped:
+        assert_eq!(report.solve_counts[SolveMode::Scrub], 0);
+        assert_eq!(
+            solve(puzzle, &mut None, &SolveOptions::default())
+                .unwrap()
+                .cells_left,
+            0
+        );
+    }
+
+    #[test]
+    fn test_candidates() {
+        let mut palette = HashMap::new();
+        palette.insert(BACKGROUND, ColorInfo::default_bg());
+        palette.insert(Color(1), ColorInfo::default_fg(Color(1)));
+
+        let clue = |n| {
+            vec![Nono {
+                color: Color(1),
+                count: n,
+            }]
+        };
+        let puzzle = Puzzle {
+            palette,
+            rows: vec![clue(1), clue(2)],
+            cols: vec![clue(2), clue(1)],
+        };
+
+        let mut grid = PartialSolution::from_elem((2, 2), Cell::new_anything());
+        grid[[1, 0]] = Cell::from_color(Color(1));
+
+        let candidates = candidates(&puzzle, &grid, SeparationRule::default());
+        // The bottom row and left column are full:
+        assert_eq!(candidates[[1, 1]].candidate_count(), 1);
+        assert_eq!(candidates[[0, 0]].candidate_count(), 1);
+        // ...but the last cell depends on the others:
+        assert_eq!(candidates[[0, 1]].candidate_count(), 2);
+        assert!(candidates[[1, 0]].is_known_to_be(Color(1)));
     }
 
     #[test]
@@ -652,6 +1707,34 @@ mod tests {
         assert!(grid[[1, 0]].can_be(Color(1)));
     }
 
+    #[test]
+    fn enumeration_agrees_with_scrubbing() {
+        let options = |max_effort| SolveOptions {
+            max_effort,
+            ..SolveOptions::default()
+        };
+        for grid in ["#.\n.#\n", "##.#\n#..#\n.##.\n"] {
+            let DynPuzzle::Nono(puzzle) = crate::import::char_grid_to_solution(grid).to_puzzle()
+            else {
+                panic!("expected a nonogram");
+            };
+            let scrubbed = solve(&puzzle, &mut None, &options(SolveMode::Scrub)).unwrap();
+            let enumerated = solve(&puzzle, &mut None, &options(SolveMode::Enumerate)).unwrap();
+            assert_eq!(scrubbed.cells_left, enumerated.cells_left);
+            assert_eq!(scrubbed.solution.grid, enumerated.solution.grid);
+        }
+
+        // The diagonal is stuck, so it gets as far as enumerating, and never scrubs:
+        let DynPuzzle::Nono(puzzle) = crate::import::char_grid_to_solution("#.\n.#\n").to_puzzle()
+        else {
+            panic!("expected a nonogram");
+        };
+        let report = solve(&puzzle, &mut None, &options(SolveMode::Enumerate)).unwrap();
+        assert!(report.solve_counts[SolveMode::Enumerate] > 0);
+        assert_eq!(report.solve_counts[SolveMode::Scrub], 0);
+        assert!(format!("{}", report.solve_counts).contains("enumerates:"));
+    }
+
     #[test]
     fn test_color_filtered_solve() {
         let puz = Puzzle {
@@ -719,9 +1802,275 @@ mod tests {
         grid[[0, 0]] = Cell::from_color(Color(1));
         grid[[1, 1]] = Cell::from_color(Color(1));
 
-        settle_solution(&puzzle, &mut grid).unwrap();
+        settle_solution(&puzzle, &mut grid, SeparationRule::default()).unwrap();
 
         assert!(grid[[0, 1]].is_known_to_be(BACKGROUND));
         assert!(grid[[1, 0]].is_known_to_be(BACKGROUND));
     }
+
+    #[test]
+    fn test_progress_callback() {
+        let mut palette = HashMap::new();
+        palette.insert(BACKGROUND, ColorInfo::default_bg());
+        palette.insert(Color(1), ColorInfo::default_fg(Color(1)));
+
+        let clue = |n| {
+            vec![Nono {
+                color: Color(1),
+                count: n,
+            }]
+        };
+        let puzzle = Puzzle {
+            palette,
+            rows: vec![clue(2), clue(1)],
+            cols: vec![clue(2), clue(1)],
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let options = SolveOptions {
+            progress: Some(Box::new(move |cells_left| sender.send(cells_left).unwrap())),
+            ..SolveOptions::default()
+        };
+        solve(&puzzle, &mut None, &options).unwrap();
+        drop(options);
+
+        let reported: Vec<usize> = receiver.iter().collect();
+        assert!(!reported.is_empty());
+        assert!(reported.windows(2).all(|w| w[0] > w[1]));
+        assert_eq!(reported.last(), Some(&0));
+    }
+
+    #[test]
+    fn test_terminate() {
+        let mut palette = HashMap::new();
+        palette.insert(BACKGROUND, ColorInfo::default_bg());
+        palette.insert(Color(1), ColorInfo::default_fg(Color(1)));
+
+        let clue = |n| {
+            vec![Nono {
+                color: Color(1),
+                count: n,
+            }]
+        };
+        let puzzle = Puzzle {
+            palette,
+            rows: vec![clue(2), clue(1)],
+            cols: vec![clue(2), clue(1)],
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        sender.send(()).unwrap();
+        let report = solve(
+            &puzzle,
+            &mut None,
+            &SolveOptions {
+                terminate: Some(receiver),
+                ..SolveOptions::default()
+            },
+        )
+        .unwrap();
+
+        assert!(report.interrupted);
+        assert_eq!(report.cells_left, 4);
+        assert!(
+            !solve(&puzzle, &mut None, &SolveOptions::default())
+                .unwrap()
+                .interrupted
+        );
+    }
+
+    #[test]
+    fn test_solve_order() {
+        let mut palette = HashMap::new();
+        palette.insert(BACKGROUND, ColorInfo::default_bg());
+        palette.insert(Color(1), ColorInfo::default_fg(Color(1)));
+
+        let clue = |n| {
+            vec![Nono {
+                color: Color(1),
+                count: n,
+            }]
+        };
+        let puzzle = Puzzle {
+            palette,
+            rows: vec![clue(3), clue(1), vec![]],
+            cols: vec![clue(2), clue(1), clue(1)],
+        };
+
+        let report = solve(&puzzle, &mut None, &SolveOptions::default()).unwrap();
+        assert_eq!(report.cells_left, 0);
+
+        let order = report.solve_order;
+        assert_eq!(order.len(), 3);
+        assert!(
+            order
+                .iter()
+                .flatten()
+                .all(|step| *step >= 1 && *step != usize::MAX)
+        );
+        // No single line determines the whole grid:
+        assert!(order.iter().flatten().max() > Some(&1));
+    }
+
+    #[test]
+    fn test_enumerate_solutions() {
+        let mut palette = HashMap::new();
+        palette.insert(BACKGROUND, ColorInfo::default_bg());
+        palette.insert(Color(1), ColorInfo::default_fg(Color(1)));
+
+        let one = vec![Nono {
+            color: Color(1),
+            count: 1,
+        }];
+        // A 2x2 diagonal, which could go either way:
+        let puzzle = DynPuzzle::Nono(Puzzle {
+            palette,
+            rows: vec![one.clone(), one.clone()],
+            cols: vec![one.clone(), one.clone()],
+        });
+
+        let solutions = enumerate_solutions(&puzzle, 10, SeparationRule::SameColor);
+        assert_eq!(solutions.len(), 2);
+        assert_ne!(solutions[0].grid, solutions[1].grid);
+        for solution in &solutions {
+            assert_eq!(
+                solution.to_puzzle().assume_nono().rows,
+                puzzle.assume_nono().rows
+            );
+            assert_eq!(
+                solution.to_puzzle().assume_nono().cols,
+                puzzle.assume_nono().cols
+            );
+        }
+
+        assert_eq!(
+            enumerate_solutions(&puzzle, 1, SeparationRule::SameColor).len(),
+            1
+        );
+        assert_eq!(
+            enumerate_solutions(&puzzle, 0, SeparationRule::SameColor).len(),
+            0
+        );
+        // Telling the diagonals apart takes a guess:
+        assert_eq!(
+            enumerate_solutions_capped(&puzzle, 2, 0, SeparationRule::SameColor),
+            None
+        );
+        assert_eq!(is_unique(&puzzle, 0, SeparationRule::SameColor), None);
+        assert_eq!(
+            is_unique(&puzzle, UNIQUENESS_GUESS_LIMIT, SeparationRule::SameColor),
+            Some(false)
+        );
+
+        // Filling the whole top row instead pins everything down:
+        let mut unique = puzzle.assume_nono().clone();
+        unique.rows = vec![
+            vec![Nono {
+                color: Color(1),
+                count: 2,
+            }],
+            vec![],
+        ];
+        assert_eq!(
+            enumerate_solutions(
+                &DynPuzzle::Nono(unique.clone()),
+                10,
+                SeparationRule::SameColor
+            )
+            .len(),
+            1
+        );
+
+        // ...and a 2 in each column contradicts that:
+        unique.cols = unique.rows.clone();
+        unique.cols[1] = unique.cols[0].clone();
+        assert_eq!(
+            enumerate_solutions(&DynPuzzle::Nono(unique), 10, SeparationRule::SameColor).len(),
+            0
+        );
+    }
+
+    #[test]
+    fn solve_respects_the_separation_rule() {
+        let mut palette = HashMap::new();
+        palette.insert(BACKGROUND, ColorInfo::default_bg());
+        palette.insert(Color(1), ColorInfo::default_fg(Color(1)));
+        palette.insert(Color(2), ColorInfo::default_fg(Color(2)));
+        let clue = |color, count| Nono {
+            color: Color(color),
+            count,
+        };
+        let solve_with = |puzzle: &Puzzle<Nono>, separation| {
+            let options = SolveOptions {
+                separation,
+                ..SolveOptions::default()
+            };
+            solve(puzzle, &mut None, &options).map(|report| report.cells_left)
+        };
+
+        // Touching black blocks:
+        let touching = Puzzle {
+            palette: palette.clone(),
+            rows: vec![vec![clue(1, 1), clue(1, 1)]],
+            cols: vec![vec![clue(1, 1)], vec![clue(1, 1)]],
+        };
+        assert!(solve_with(&touching, SeparationRule::SameColor).is_err());
+        assert!(solve_with(&touching, SeparationRule::Always).is_err());
+        assert_eq!(solve_with(&touching, SeparationRule::Never).unwrap(), 0);
+
+        // Touching blocks of different colors:
+        let two_colors = Puzzle {
+            palette,
+            rows: vec![vec![clue(1, 1), clue(2, 1)]],
+            cols: vec![vec![clue(1, 1)], vec![clue(2, 1)]],
+        };
+        assert_eq!(
+            solve_with(&two_colors, SeparationRule::SameColor).unwrap(),
+            0
+        );
+        assert!(solve_with(&two_colors, SeparationRule::Always).is_err());
+        assert_eq!(solve_with(&two_colors, SeparationRule::Never).unwrap(), 0);
+    }
+
+    #[test]
+    fn enumeration_respects_the_separation_rule() {
+        let mut palette = HashMap::new();
+        palette.insert(BACKGROUND, ColorInfo::default_bg());
+        palette.insert(Color(1), ColorInfo::default_fg(Color(1)));
+        palette.insert(Color(2), ColorInfo::default_fg(Color(2)));
+        let clue = |color, count| Nono {
+            color: Color(color),
+            count,
+        };
+
+        // Two touching black blocks only count as a solution if blocks needn't be separated:
+        let touching = DynPuzzle::Nono(Puzzle {
+            palette: palette.clone(),
+            rows: vec![vec![clue(1, 1), clue(1, 1)]],
+            cols: vec![vec![clue(1, 1)], vec![clue(1, 1)], vec![]],
+        });
+        assert_eq!(
+            enumerate_solutions(&touching, 10, SeparationRule::SameColor).len(),
+            0
+        );
+        assert_eq!(
+            enumerate_solutions(&touching, 10, SeparationRule::Never).len(),
+            1
+        );
+
+        // Different colors may touch, unless every block must be separated:
+        let two_colors = DynPuzzle::Nono(Puzzle {
+            palette,
+            rows: vec![vec![clue(1, 1), clue(2, 1)]],
+            cols: vec![vec![clue(1, 1)], vec![clue(2, 1)]],
+        });
+        assert_eq!(
+            enumerate_solutions(&two_colors, 10, SeparationRule::SameColor).len(),
+            1
+        );
+        assert_eq!(
+            enumerate_solutions(&two_colors, 10, SeparationRule::Always).len(),
+            0
+        );
+    }
 }
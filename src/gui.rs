@@ -1,6 +1,6 @@
 use std::{
     cmp::{max, min},
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     sync::mpsc,
 };
 
@@ -13,14 +13,21 @@ pub enum Tool {
 
 use crate::{
     export::to_bytes,
-    grid_solve::{self, disambig_candidates},
-    gui_solver::{RenderStyle, SolveGui},
+    grid_solve::{self, LineStats, disambig_candidates, disambig_display_threshold},
+    gui_gallery::{Solvability, SolvabilityCache, ThumbnailCache},
+    gui_solver::{
+        Orientation, RenderStyle, SolveGui, draw_coordinate_ruler, draw_dyn_clues,
+        draw_string_in_box, run_lengths_widget,
+    },
     import,
+    line_solve::SeparationRule,
     puzzle::{
-        BACKGROUND, ClueStyle, Color, ColorInfo, Corner, Document, PuzzleDynOps, Solution, UNSOLVED,
+        Anchor, BACKGROUND, ClueStyle, Color, ColorInfo, Corner, Document, DynPuzzle,
+        DynSolveCache, Solution, UNSOLVED,
     },
     user_settings::{UserSettings, consts},
 };
+use anyhow::Context as _;
 use egui::{Color32, Pos2, Rect, RichText, Shape, Style, TextStyle, Vec2, Visuals};
 use egui_material_icons::icons;
 
@@ -95,7 +102,7 @@ pub fn edit_image(document: Document) {
 }
 
 #[cfg(target_arch = "wasm32")]
-use wasm_bindgen_futures::spawn_local as spawn_async;
+pub use wasm_bindgen_futures::spawn_local as spawn_async;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub fn spawn_async<F>(future: F)
@@ -132,6 +139,49 @@ pub async fn yield_now() {
     wasm_bindgen_futures::JsFuture::from(p).await.unwrap();
 }
 
+#[cfg(not(target_arch = "wasm32"))]
+async fn clipboard_image() -> anyhow::Result<image::DynamicImage> {
+    let data = arboard::Clipboard::new()?.get_image()?;
+    let buffer = image::RgbaImage::from_raw(
+        data.width as u32,
+        data.height as u32,
+        data.bytes.into_owned(),
+    )
+    .ok_or_else(|| anyhow::anyhow!("malformed clipboard image"))?;
+    Ok(image::DynamicImage::ImageRgba8(buffer))
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn clipboard_image() -> anyhow::Result<image::DynamicImage> {
+    use wasm_bindgen::JsCast;
+    use wasm_bindgen_futures::JsFuture;
+    let js_err = |e: wasm_bindgen::JsValue| anyhow::anyhow!("{e:?}");
+
+    let clipboard = web_sys::window()
+        .ok_or_else(|| anyhow::anyhow!("no window"))?
+        .navigator()
+        .clipboard();
+    let items: js_sys::Array = JsFuture::from(clipboard.read())
+        .await
+        .map_err(js_err)?
+        .unchecked_into();
+    for item in items.iter() {
+        let item: web_sys::ClipboardItem = item.unchecked_into();
+        for mime_type in item.types().iter().filter_map(|t| t.as_string()) {
+            if mime_type.starts_with("image/") {
+                let blob: web_sys::Blob = JsFuture::from(item.get_type(&mime_type))
+                    .await
+                    .map_err(js_err)?
+                    .unchecked_into();
+                let buffer = JsFuture::from(blob.array_buffer()).await.map_err(js_err)?;
+                let bytes = js_sys::Uint8Array::new(&buffer).to_vec();
+                return Ok(image::load_from_memory(&bytes)?);
+            }
+        }
+    }
+    anyhow::bail!("no image on the clipboard")
+}
+
 type Version = u32;
 
 pub struct Staleable<T> {
@@ -179,25 +229,92 @@ pub struct CanvasGui {
     pub current_tool: Tool,
     pub line_tool_state: Option<(usize, usize)>,
     pub solved_mask: Staleable<(String, Vec<Vec<bool>>)>,
+    pub solve_order: Staleable<Vec<Vec<usize>>>,
+    pub show_solve_order: bool,
+    /// A cell to reveal (until the next edit), as `(x, y, color)`.
+    pub hint: Staleable<Option<(usize, usize, Color)>>,
+    /// How many colors each unknown cell could still be, as `(x, y, count)`.
+    pub candidate_counts: Staleable<Vec<(usize, usize, usize)>>,
+    /// Where the hovered clue could go, as `(is_row, lane, first_cell, last_cell)`.
+    pub clue_span: Option<(bool, usize, usize, usize)>,
+    /// The keyboard cursor, if the keyboard has been used.
+    pub selected_cell: Option<(usize, usize)>,
+    /// Cells to mark as mistakes (until the next edit).
+    pub flagged_cells: Staleable<Vec<(usize, usize)>>,
+    /// Heavier gridlines every this-many cells (0 for none).
+    pub ruling: usize,
+    /// Number the rows and columns along the edges of the grid.
+    pub show_coordinates: bool,
     pub disambiguator: Staleable<Disambiguator>,
+    /// How close to the best disambiguation a suggestion must be to get drawn; see
+    /// `disambig_display_threshold`.
+    pub disambig_threshold: f32,
     pub id: Staleable<String>,
+    /// A palette color whose rgb is being dragged around, and what it was before. The grid shows
+    /// the new rgb right away, but it only becomes an undoable action when the mouse is released.
+    pub palette_preview: Option<(Color, (u8, u8, u8))>,
 }
 
 pub struct NonogramGui {
     // The `pub`s are solely for tests/gui.rs
     pub editor_gui: CanvasGui,
     scale: f32,
-    opened_file_receiver: mpsc::Receiver<Document>,
-    library_receiver: mpsc::Receiver<Vec<Document>>,
+    opened_file_receiver: mpsc::Receiver<anyhow::Result<Document>>,
+    saved_file_receiver: mpsc::Receiver<anyhow::Result<()>>,
+    /// The puzzles in a zip file that was opened directly; they replace the library.
+    opened_zip_receiver: mpsc::Receiver<Vec<anyhow::Result<Document>>>,
+    /// Puzzles arrive one at a time; the sender hangs up when the fetch is done (or fails).
+    library_receiver: mpsc::Receiver<anyhow::Result<Document>>,
+    library_loading: bool,
+    /// Why the last fetch stopped early, if it did.
+    library_error: Option<String>,
     library_dialog: Option<Vec<Document>>,
+    /// Content hashes of the library puzzles so far, to drop duplicates as they arrive.
+    library_seen: HashSet<u64>,
+    /// How many puzzles in the library were dropped for duplicating another.
+    library_duplicates: usize,
+    /// Bumped whenever the library is (re)loaded.
+    library_version: Version,
+    thumbnails: Staleable<ThumbnailCache>,
+    /// How far the line solver gets with each library puzzle, worked out in the background.
+    solvability: SolvabilityCache,
+    solvability_receiver: mpsc::Receiver<(String, Solvability)>,
+    solvability_pending: bool,
+    /// The separation rule `solvability` was worked out under.
+    solvability_separation: SeparationRule,
+    /// The solver panel's separation rule, kept between visits to it.
+    separation: SeparationRule,
+    library_url: String,
     new_dialog: Option<NewPuzzleDialog>,
     auto_solve: bool,
+    solve_receiver: mpsc::Receiver<(Version, DynPuzzle, anyhow::Result<grid_solve::Report>)>,
+    /// Line results from earlier solves. An edit only changes the clues of one row and column, so
+    /// most lines can be looked up instead of scrubbed again. (The running solve has it.)
+    solve_cache: Option<DynSolveCache>,
+    /// Every solve hands its cache back here when it finishes, even if it was interrupted (which
+    /// replaces `solve_receiver`), so its work isn't lost.
+    solve_cache_sender: mpsc::Sender<DynSolveCache>,
+    solve_cache_receiver: mpsc::Receiver<DynSolveCache>,
+    /// The version being solved in the background, and a way to stop it.
+    solve_running: Option<(Version, mpsc::Sender<()>)>,
+    show_run_lengths: bool,
+    /// The clues from the last solve, with how hard each row and column was.
+    line_stats: Staleable<Option<(DynPuzzle, Vec<LineStats>, Vec<LineStats>)>>,
+    show_line_stats: bool,
+    hovered_cell: Option<(usize, usize)>,
     lines_to_affect_string: String,
+    /// When the canvas was last resized, in egui's clock, and its version right afterwards (so we
+    /// can tell whether anything else has happened since).
+    last_resize: Option<(f64, u32)>,
+    /// How many copies across and down the "Tile" button makes.
+    tile_counts: (usize, usize),
     solve_report: String,
     pub solve_mode: bool,
     pub solve_gui: Option<SolveGui>,
     show_save_share_window: bool,
     share_string: String,
+    /// `share_string`, ready to scan; `None` if it's too long.
+    share_qr: Option<Vec<egui::TextureHandle>>,
     pasted_string: String,
     quality_warnings: Vec<String>,
 }
@@ -284,6 +401,12 @@ impl CanvasGui {
                         return;
                     }
                 }
+                // Consecutive `ReplaceDocument`s collapse; the undo entry keeps the oldest document.
+                (Some(ReplaceDocument { .. }), ReplaceDocument { .. })
+                    if self.redo_stack.is_empty() =>
+                {
+                    Merge
+                }
                 _ => Normal, // Unable to merge; add a new undo entry.
             }
         } else {
@@ -358,6 +481,15 @@ impl CanvasGui {
 
         self.tool_selector(ui);
 
+        ui.horizontal(|ui| {
+            ui.label("Ruling every");
+            ui.add(egui::DragValue::new(&mut self.ruling).range(0..=50));
+        })
+        .response
+        .on_hover_text("Draws heavier gridlines every N cells (0 for none)");
+        ui.checkbox(&mut self.show_coordinates, "Show coordinates")
+            .on_hover_text("Numbers the rows and columns along the edges of the grid");
+
         ui.separator();
 
         self.palette_editor(ui, palette_read_only);
@@ -377,7 +509,7 @@ impl CanvasGui {
                 Tool::OrthographicLine,
                 egui::RichText::new(icons::ICON_LINE_START).size(24.0),
             )
-            .on_hover_text("Orthographic line");
+            .on_hover_text("Orthographic line (hold shift for diagonals)");
             ui.selectable_value(
                 &mut self.current_tool,
                 Tool::FloodFill,
@@ -401,8 +533,8 @@ impl CanvasGui {
         let mut visited = std::collections::HashSet::new();
         visited.insert((x, y));
 
-        let x_size = picture.grid.len();
-        let y_size = picture.grid.first().unwrap().len();
+        let x_size = picture.x_size();
+        let y_size = picture.y_size();
 
         while let Some((px, py)) = q.pop_front() {
             changes.insert((px, py), self.current_color);
@@ -435,8 +567,8 @@ impl CanvasGui {
         render_style: RenderStyle,
     ) -> Option<(usize, usize)> {
         let picture = self.document.solution_mut();
-        let x_size = picture.grid.len();
-        let y_size = picture.grid.first().unwrap().len();
+        let x_size = picture.x_size();
+        let y_size = picture.y_size();
 
         let (mut response, painter) = ui.allocate_painter(
             Vec2::new(scale * x_size as f32, scale * y_size as f32) + Vec2::new(2.0, 2.0), // for the border
@@ -521,7 +653,12 @@ impl CanvasGui {
 
                                 let horiz = x.abs_diff(start_x) > y.abs_diff(start_y);
 
-                                if horiz {
+                                // Holding shift draws a straight line at any angle instead.
+                                if ui.input(|i| i.modifiers.shift) {
+                                    for point in line_cells((start_x, start_y), (x, y)) {
+                                        new_points.insert(point, self.drag_start_color);
+                                    }
+                                } else if horiz {
                                     let xlo = min(start_x, x);
                                     let xhi = max(start_x, x);
                                     for xi in xlo..=xhi {
@@ -552,6 +689,8 @@ impl CanvasGui {
         let mut shapes = vec![];
         let disambiguator = self.disambiguator.get_if_fresh(self.version);
         let disambig_report = disambiguator.as_ref().and_then(|d| d.report.as_ref());
+        let disambig_threshold = disambig_report
+            .map(|report| disambig_display_threshold(report, self.disambig_threshold));
 
         let picture = self.document.try_solution().unwrap();
         for y in 0..y_size {
@@ -566,9 +705,13 @@ impl CanvasGui {
                     || disambiguator.map_or(false, |d| d.progress > 0.0 && d.progress < 1.0);
                 let mut dr = (&picture.palette[&BACKGROUND], 1.0);
 
-                if let Some(disambig_report) = disambig_report.as_ref() {
+                if let (Some(disambig_report), Some(threshold)) =
+                    (disambig_report.as_ref(), disambig_threshold)
+                {
                     let (c, score) = disambig_report[x][y];
-                    dr = (&picture.palette[&c], score);
+                    if score <= threshold {
+                        dr = (&picture.palette[&c], score);
+                    }
                 }
                 for shape in cell_shape(color_info, solved, dr, x, y, &to_screen, render_style) {
                     shapes.push(shape);
@@ -576,6 +719,110 @@ impl CanvasGui {
             }
         }
 
+        if let Some((row, lane, first, last)) = self.clue_span {
+            let (min, max) = if row {
+                (
+                    Pos2::new(first as f32, lane as f32),
+                    Pos2::new(last as f32 + 1.0, lane as f32 + 1.0),
+                )
+            } else {
+                (
+                    Pos2::new(lane as f32, first as f32),
+                    Pos2::new(lane as f32 + 1.0, last as f32 + 1.0),
+                )
+            };
+            shapes.push(egui::Shape::rect_filled(
+                Rect::from_min_max(to_screen * min, to_screen * max),
+                0.0,
+                Color32::from_rgba_unmultiplied(0, 120, 255, 48),
+            ));
+        }
+
+        if let Some(Some((x, y, color))) = self.hint.get_if_fresh(self.version) {
+            let bg = (&picture.palette[&BACKGROUND], 1.0);
+            shapes.extend(cell_shape(
+                &picture.palette[color],
+                true,
+                bg,
+                *x,
+                *y,
+                &to_screen,
+                render_style,
+            ));
+            shapes.push(egui::Shape::rect_stroke(
+                Rect::from_min_size(
+                    to_screen * Pos2::new(*x as f32, *y as f32),
+                    to_screen.scale(),
+                ),
+                0.0,
+                egui::Stroke::new(2.0, Color32::from_rgb(255, 200, 0)),
+                egui::StrokeKind::Inside,
+            ));
+        }
+
+        if let Some((x, y)) = self
+            .selected_cell
+            .filter(|(x, y)| *x < x_size && *y < y_size)
+        {
+            shapes.push(egui::Shape::rect_stroke(
+                Rect::from_min_size(to_screen * Pos2::new(x as f32, y as f32), to_screen.scale()),
+                0.0,
+                egui::Stroke::new(2.0, Color32::from_rgb(0, 120, 255)),
+                egui::StrokeKind::Inside,
+            ));
+        }
+
+        if let Some(flagged_cells) = self.flagged_cells.get_if_fresh(self.version) {
+            let stroke = egui::Stroke::new(2.0, Color32::RED);
+            for (x, y) in flagged_cells {
+                let min = to_screen * Pos2::new(*x as f32, *y as f32);
+                let max = to_screen * Pos2::new(*x as f32 + 1.0, *y as f32 + 1.0);
+                shapes.push(egui::Shape::line_segment([min, max], stroke));
+                shapes.push(egui::Shape::line_segment(
+                    [Pos2::new(max.x, min.y), Pos2::new(min.x, max.y)],
+                    stroke,
+                ));
+            }
+        }
+
+        // Heatmap of when each cell was deduced, from blue (early) to red (late):
+        if let Some(order) = self
+            .solve_order
+            .get_if_fresh(self.version)
+            .filter(|_| self.show_solve_order)
+        {
+            let last_step = order
+                .iter()
+                .flatten()
+                .filter(|step| **step != usize::MAX)
+                .max()
+                .copied()
+                .unwrap_or(0)
+                .max(1);
+            for (x, col) in order.iter().enumerate() {
+                for (y, step) in col.iter().enumerate() {
+                    if *step == usize::MAX {
+                        continue;
+                    }
+                    let t = *step as f32 / last_step as f32;
+                    let tint = egui::Color32::from_rgba_unmultiplied(
+                        (255.0 * t) as u8,
+                        60,
+                        (255.0 * (1.0 - t)) as u8,
+                        120,
+                    );
+                    shapes.push(egui::Shape::rect_filled(
+                        Rect::from_min_size(
+                            to_screen * Pos2::new(x as f32, y as f32),
+                            to_screen.scale(),
+                        ),
+                        0.0,
+                        tint,
+                    ));
+                }
+            }
+        }
+
         // Grid lines:
         for y in 0..=y_size {
             let points = [
@@ -584,7 +831,7 @@ impl CanvasGui {
             ];
             let stroke = egui::Stroke::new(
                 1.0,
-                egui::Color32::from_black_alpha(if y % 5 == 0 { 64 } else { 16 }),
+                egui::Color32::from_black_alpha(if is_ruled(y, self.ruling) { 64 } else { 16 }),
             );
             shapes.push(egui::Shape::line_segment(points, stroke));
         }
@@ -595,12 +842,33 @@ impl CanvasGui {
             ];
             let stroke = egui::Stroke::new(
                 1.0,
-                egui::Color32::from_black_alpha(if x % 5 == 0 { 64 } else { 16 }),
+                egui::Color32::from_black_alpha(if is_ruled(x, self.ruling) { 64 } else { 16 }),
             );
             shapes.push(egui::Shape::line_segment(points, stroke));
         }
 
         painter.extend(shapes);
+
+        if let Some(counts) = self.candidate_counts.get_if_fresh(self.version) {
+            let rgb = picture
+                .palette
+                .get(&UNSOLVED)
+                .map_or((128, 128, 128), |ci| ci.rgb);
+            for (x, y, count) in counts {
+                let rect = Rect::from_min_size(
+                    to_screen * Pos2::new(*x as f32, *y as f32),
+                    to_screen.scale(),
+                );
+                draw_string_in_box(
+                    ui,
+                    &painter,
+                    rect.shrink(1.0),
+                    &count.to_string(),
+                    scale,
+                    rgb,
+                );
+            }
+        }
         response.mark_changed();
 
         hovered_cell
@@ -610,6 +878,7 @@ impl CanvasGui {
         let mut picked_color = self.current_color;
         let mut removed_color = None;
         let mut add_color = false;
+        let mut palette_preview = self.palette_preview;
 
         use itertools::Itertools;
 
@@ -652,8 +921,10 @@ impl CanvasGui {
                     let mut edited_color = [r as f32 / 256.0, g as f32 / 256.0, b as f32 / 256.0];
 
                     if ui.color_edit_button_rgb(&mut edited_color).changed() {
-                        // TODO: this should probably also be undoable
                         picked_color = *color;
+                        if palette_preview.is_none() {
+                            palette_preview = Some((*color, color_info.rgb));
+                        }
                         color_info.rgb = (
                             (edited_color[0] * 256.0) as u8,
                             (edited_color[1] * 256.0) as u8,
@@ -668,11 +939,37 @@ impl CanvasGui {
                 }
             });
         }
-        if !read_only && ui.button("New color").clicked() {
-            add_color = true;
+        let mut prune_colors = false;
+        if !read_only {
+            ui.horizontal(|ui| {
+                if ui.button("New color").clicked() {
+                    add_color = true;
+                }
+                if ui.button("Prune colors").clicked() {
+                    prune_colors = true;
+                }
+            });
         }
         self.current_color = picked_color;
 
+        if let Some((color, original_rgb)) = palette_preview {
+            if !ui.input(|i| i.pointer.any_down()) {
+                let new_document = self.document.clone();
+                // Put the original back, so that undoing returns to it.
+                if let Some(color_info) = self.document.solution_mut().palette.get_mut(&color) {
+                    color_info.rgb = original_rgb;
+                }
+                self.perform(
+                    Action::ReplaceDocument {
+                        document: new_document,
+                    },
+                    ActionMood::Normal,
+                );
+                palette_preview = None;
+            }
+        }
+        self.palette_preview = palette_preview;
+
         if Some(self.current_color) == removed_color {
             self.current_color = BACKGROUND;
         }
@@ -695,6 +992,23 @@ impl CanvasGui {
                 ActionMood::Normal,
             );
         }
+        if prune_colors {
+            let mut new_document = self.document.clone();
+            new_document.solution_mut().prune_palette();
+            if !new_document
+                .solution_mut()
+                .palette
+                .contains_key(&self.current_color)
+            {
+                self.current_color = BACKGROUND;
+            }
+            self.perform(
+                Action::ReplaceDocument {
+                    document: new_document,
+                },
+                ActionMood::Normal,
+            );
+        }
         if add_color {
             let mut new_document = self.document.clone();
             let new_picture = new_document.solution_mut();
@@ -702,13 +1016,14 @@ impl CanvasGui {
             new_picture.palette.insert(
                 next_color,
                 ColorInfo {
-                    ch: (next_color.0 + 65) as char, // TODO: will break chargrid export
+                    ch: '\0', // Replaced by `assign_palette_chars`, below.
                     name: "New color".to_string(),
                     rgb: (128, 128, 128),
                     color: next_color,
                     corner: None,
                 },
             );
+            crate::puzzle::assign_palette_chars(new_picture);
             self.perform(
                 Action::ReplaceDocument {
                     document: new_document,
@@ -719,6 +1034,107 @@ impl CanvasGui {
     }
 }
 
+/// The cells along a straight line from `start` to `end` (inclusive), by Bresenham's algorithm.
+fn line_cells(start: (usize, usize), end: (usize, usize)) -> Vec<(usize, usize)> {
+    let (mut x, mut y) = (start.0 as i64, start.1 as i64);
+    let (end_x, end_y) = (end.0 as i64, end.1 as i64);
+    let (dx, dy) = ((end_x - x).abs(), -(end_y - y).abs());
+    let (step_x, step_y) = ((end_x - x).signum(), (end_y - y).signum());
+    let mut err = dx + dy;
+
+    let mut cells = vec![];
+    loop {
+        cells.push((x as usize, y as usize));
+        if (x, y) == (end_x, end_y) {
+            return cells;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += step_x;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += step_y;
+        }
+    }
+}
+
+/// How many files the "Recent" menu remembers.
+const MAX_RECENT_FILES: usize = 10;
+
+/// Recently-opened files, newest first, as `(name, source)`. On native, `source` is the path to
+/// load from. The web has no paths, so there it's the puzzle itself, as a woven string.
+fn recent_files() -> Vec<(String, String)> {
+    UserSettings::get(consts::EDITOR_RECENT_FILES)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn remember_recent_file(name: String, source: String) {
+    let mut recent = recent_files();
+    recent.retain(|(old_name, _)| *old_name != name);
+    recent.insert(0, (name, source));
+    recent.truncate(MAX_RECENT_FILES);
+    if let Ok(json) = serde_json::to_string(&recent) {
+        let _ = UserSettings::set(consts::EDITOR_RECENT_FILES, &json);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn reopen_recent_file(source: &str) -> anyhow::Result<Document> {
+    import::load_path(&std::path::PathBuf::from(source), None)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn reopen_recent_file(source: &str) -> anyhow::Result<Document> {
+    crate::formats::woven::from_woven(source)
+}
+
+/// Asks where to save `document`, and saves it there, in whatever format the name implies. The
+/// receiver gets the outcome, if the user picked a file.
+fn save_with_dialog(mut document: Document) -> mpsc::Receiver<anyhow::Result<()>> {
+    let (sender, receiver) = mpsc::channel();
+    spawn_async(async move {
+        let handle = rfd::AsyncFileDialog::new()
+            .add_filter(
+                "all recognized formats",
+                &[
+                    "png", "gif", "bmp", "xml", "pbn", "txt", "g", "json", "html", "clues", "nin",
+                ],
+            )
+            .add_filter("image", &["png", "gif", "bmp"])
+            .add_filter("PBN", &["xml", "pbn"])
+            .add_filter("chargrid", &["txt"])
+            .add_filter("Olšák", &["g"])
+            .add_filter("woven", &["woven"])
+            .add_filter("JSON", &["json"])
+            .add_filter("HTML (for printing)", &["html"])
+            .add_filter("clue list", &["clues"])
+            .add_filter("nin", &["nin"])
+            .set_file_name(document.file.clone())
+            .save_file()
+            .await;
+
+        if let Some(handle) = handle {
+            let result = match to_bytes(&mut document, Some(handle.file_name()), None, false) {
+                Ok(bytes) => handle
+                    .write(&bytes)
+                    .await
+                    .with_context(|| format!("unable to write {}", handle.file_name())),
+                Err(e) => Err(e.context(format!("unable to save as {}", handle.file_name()))),
+            };
+            let _ = sender.send(result);
+        }
+    });
+    receiver
+}
+
+/// Whether there should be a heavier line before line `i`.
+pub fn is_ruled(i: usize, ruling: usize) -> bool {
+    ruling != 0 && i % ruling == 0
+}
+
 pub fn triangle_shape(corner: Corner, color: egui::Color32, scale: Vec2) -> egui::Shape {
     let Corner { left, upper } = corner;
 
@@ -740,6 +1156,66 @@ pub fn triangle_shape(corner: Corner, color: egui::Color32, scale: Vec2) -> egui
     Shape::convex_polygon(points, color, (0.0, color))
 }
 
+/// How many directions hatching lines can run in, and how many of them a cell can have. Together,
+/// they give a distinct pattern to every color a cell can hold.
+const HATCH_ANGLES: usize = 8;
+const HATCH_DENSITIES: usize = 4;
+
+/// The direction (in radians) and number of lines of the hatching for `color`. Color 1 is usually
+/// the main color, so it's kept plain.
+fn hatch_pattern(color: Color) -> Option<(f32, usize)> {
+    let idx = (color.0 as usize).checked_sub(2)? % (HATCH_ANGLES * HATCH_DENSITIES);
+    let angle = (idx % HATCH_ANGLES) as f32 * std::f32::consts::PI / HATCH_ANGLES as f32;
+    Some((angle, 1 + idx / HATCH_ANGLES))
+}
+
+/// The part of the line through `point` in direction `dir` that's inside `rect`.
+fn clip_line(rect: Rect, point: Pos2, dir: Vec2) -> Option<[Pos2; 2]> {
+    let (mut t_min, mut t_max) = (f32::NEG_INFINITY, f32::INFINITY);
+    for (p, d, min, max) in [
+        (point.x, dir.x, rect.min.x, rect.max.x),
+        (point.y, dir.y, rect.min.y, rect.max.y),
+    ] {
+        if d.abs() < f32::EPSILON {
+            if p < min || p > max {
+                return None;
+            }
+        } else {
+            let (t0, t1) = ((min - p) / d, (max - p) / d);
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+        }
+    }
+    (t_min < t_max).then(|| [point + dir * t_min, point + dir * t_max])
+}
+
+/// Gives each color its own stripes, so colors can be told apart by more than hue.
+fn hatching(ci: &ColorInfo, rect: Rect) -> Vec<egui::Shape> {
+    let Some((angle, lines)) = hatch_pattern(ci.color) else {
+        return vec![];
+    };
+    let (r, g, b) = ci.rgb;
+    let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    let ink = if luminance > 128.0 {
+        Color32::from_black_alpha(160)
+    } else {
+        Color32::from_white_alpha(160)
+    };
+    let stroke = egui::Stroke::new(1.0, ink);
+
+    // Space the lines evenly across the cell, measured perpendicular to them.
+    let dir = Vec2::angled(angle);
+    let normal = dir.rot90();
+    let half_width = (normal.x.abs() * rect.width() + normal.y.abs() * rect.height()) / 2.0;
+    (0..lines)
+        .filter_map(|i| {
+            let offset = half_width * (2.0 * (i + 1) as f32 / (lines + 1) as f32 - 1.0);
+            clip_line(rect, rect.center() + normal * offset, dir)
+        })
+        .map(|points| egui::Shape::line_segment(points, stroke))
+        .collect()
+}
+
 fn cell_shape(
     ci: &ColorInfo,
     solved: bool,
@@ -776,13 +1252,20 @@ fn cell_shape(
     if ci.color == BACKGROUND {
         let center = to_screen * Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
         match render_style {
-            RenderStyle::TraditionalDots => {
+            RenderStyle::TraditionalDots | RenderStyle::Patterned => {
                 res.push(egui::Shape::circle_filled(
                     center,
                     to_screen.scale().x * 0.1,
                     egui::Color32::from_rgb(190, 190, 190),
                 ));
             }
+            RenderStyle::HighContrast => {
+                res.push(egui::Shape::circle_filled(
+                    center,
+                    to_screen.scale().x * 0.15,
+                    egui::Color32::BLACK,
+                ));
+            }
             RenderStyle::TraditionalXes => {
                 let stroke = egui::Stroke::new(2.0, Color32::from_rgb(190, 190, 190));
                 let radius = to_screen.scale().x * 0.2;
@@ -803,6 +1286,21 @@ fn cell_shape(
             }
             RenderStyle::Experimental => {}
         }
+    } else if ci.color != UNSOLVED && ci.corner.is_none() {
+        let cell_rect =
+            Rect::from_min_size(to_screen * Pos2::new(x as f32, y as f32), to_screen.scale());
+        match render_style {
+            RenderStyle::HighContrast => {
+                res.push(egui::Shape::rect_stroke(
+                    cell_rect.shrink(1.0),
+                    0.0,
+                    egui::Stroke::new(2.0, Color32::BLACK),
+                    egui::StrokeKind::Inside,
+                ));
+            }
+            RenderStyle::Patterned => res.extend(hatching(ci, cell_rect)),
+            _ => {}
+        }
     }
 
     if ci.color == UNSOLVED && render_style == RenderStyle::Experimental {
@@ -841,11 +1339,71 @@ fn cell_shape(
     res
 }
 
+/// The smallest width or height that the resizer will shrink the canvas to.
+const MIN_GRID_SIDE: usize = 1;
+
+/// Resizes closer together than this share an undo entry.
+const RESIZE_MERGE_SECONDS: f64 = 1.0;
+
+/// Past this, the auto-solver's line cache is thrown away rather than growing forever.
+const MAX_CACHED_LINES: usize = 100_000;
+
+/// Many chat apps cut off (or refuse) messages longer than this.
+const LONG_SHARE_STRING_CHARS: usize = 2000;
+
+/// Characters per QR code; a version-40 code at the lowest error correction holds 2953 bytes.
+const QR_CHUNK_CHARS: usize = 2500;
+
+/// Past this many QR codes, it's easier to just copy the text.
+const MAX_QR_CODES: usize = 4;
+
+/// Licenses offered with one click, and where to read them.
+const LICENSE_PRESETS: [(&str, Option<&str>); 3] = [
+    (
+        "CC BY 4.0",
+        Some("https://creativecommons.org/licenses/by/4.0/"),
+    ),
+    (
+        "CC0 1.0",
+        Some("https://creativecommons.org/publicdomain/zero/1.0/"),
+    ),
+    ("All rights reserved", None),
+];
+
+/// One black-on-white image (a module per pixel, with the standard quiet zone) for each piece of
+/// `text`; pasting the pieces together in order gives back `text`. `None` if it's hopelessly long.
+fn qr_images(text: &str) -> Option<Vec<egui::ColorImage>> {
+    const QUIET: usize = 4;
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len().div_ceil(QR_CHUNK_CHARS) > MAX_QR_CODES {
+        return None;
+    }
+    chars
+        .chunks(QR_CHUNK_CHARS)
+        .map(|chunk| {
+            let chunk: String = chunk.iter().collect();
+            let code =
+                qrcode::QrCode::with_error_correction_level(chunk.as_bytes(), qrcode::EcLevel::L)
+                    .ok()?;
+            let width = code.width();
+            let side = width + 2 * QUIET;
+            let mut image = egui::ColorImage::new([side, side], Color32::WHITE);
+            for (i, module) in code.to_colors().into_iter().enumerate() {
+                if module == qrcode::Color::Dark {
+                    image[(i % width + QUIET, i / width + QUIET)] = Color32::BLACK;
+                }
+            }
+            Some(image)
+        })
+        .collect()
+}
+
 impl NonogramGui {
     pub fn new(mut document: Document) -> Self {
         // (Public for testing)
+        let (solve_cache_sender, solve_cache_receiver) = mpsc::channel();
         let picture = document.try_solution().unwrap();
-        let solved_mask = vec![vec![true; picture.grid[0].len()]; picture.grid.len()];
+        let solved_mask = vec![vec![true; picture.y_size()]; picture.x_size()];
 
         let mut current_color = BACKGROUND;
         if picture.palette.contains_key(&Color(1)) {
@@ -872,35 +1430,90 @@ impl NonogramGui {
                     val: ("".to_string(), solved_mask),
                     version: 0,
                 },
+                solve_order: Staleable {
+                    val: vec![],
+                    version: u32::MAX,
+                },
+                show_solve_order: false,
+                flagged_cells: Staleable {
+                    val: vec![],
+                    version: u32::MAX,
+                },
+                ruling: 5,
+                show_coordinates: false,
+                hint: Staleable {
+                    val: None,
+                    version: u32::MAX,
+                },
+                selected_cell: None,
+                candidate_counts: Staleable {
+                    val: vec![],
+                    version: u32::MAX,
+                },
+                clue_span: None,
                 disambiguator: Staleable {
                     val: Disambiguator::new(),
                     version: 0,
                 },
+                disambig_threshold: grid_solve::DEFAULT_DISAMBIG_THRESHOLD,
                 id: Staleable {
                     val: "".to_string(),
                     version: 0,
                 },
+                palette_preview: None,
             },
             scale: 16.0,
             opened_file_receiver: mpsc::channel().1,
+            saved_file_receiver: mpsc::channel().1,
+            opened_zip_receiver: mpsc::channel().1,
             library_receiver: mpsc::channel().1,
+            library_loading: false,
+            library_error: None,
+            library_seen: HashSet::new(),
             new_dialog: None,
             library_dialog: None,
+            library_version: 0,
+            thumbnails: Staleable {
+                val: HashMap::new(),
+                version: u32::MAX,
+            },
+            solvability: HashMap::new(),
+            solvability_receiver: mpsc::channel().1,
+            solvability_pending: false,
+            solvability_separation: SeparationRule::default(),
+            separation: SeparationRule::default(),
+            library_duplicates: 0,
+            library_url: "".to_string(),
             auto_solve: false,
+            solve_receiver: mpsc::channel().1,
+            solve_cache: None,
+            solve_cache_sender,
+            solve_cache_receiver,
+            solve_running: None,
+            show_run_lengths: false,
+            line_stats: Staleable {
+                val: None,
+                version: u32::MAX,
+            },
+            show_line_stats: false,
+            hovered_cell: None,
             lines_to_affect_string: "5".to_string(),
+            tile_counts: (2, 2),
+            last_resize: None,
             solve_report: "".to_string(),
             solve_mode: false,
             solve_gui: None,
             show_save_share_window: false,
             share_string: "".to_string(),
+            share_qr: None,
             pasted_string: "".to_string(),
             quality_warnings: vec![],
         }
     }
 
-    fn resize(&mut self, top: Option<bool>, left: Option<bool>, add: bool) {
+    fn resize(&mut self, top: Option<bool>, left: Option<bool>, add: bool, now: f64) {
         let picture = self.editor_gui.document.solution_mut();
-        let mut g = picture.grid.clone();
+        let (x_size, y_size) = (picture.x_size(), picture.y_size());
         let lines = match self.lines_to_affect_string.parse::<usize>() {
             Ok(lines) => lines,
             Err(_) => {
@@ -908,45 +1521,48 @@ impl NonogramGui {
                 return;
             }
         };
-        if let Some(left) = left {
+        // Never remove the last row or column; an empty grid can't be edited (or grown back).
+        let new_size = |size: usize| {
             if add {
-                g.resize(g.len() + lines, vec![BACKGROUND; g.first().unwrap().len()]);
-                if left {
-                    g.rotate_right(lines);
-                }
+                size + lines
             } else {
-                if left {
-                    g.rotate_left(lines);
-                }
-                g.truncate(g.len() - lines);
-            }
-        } else if let Some(top) = top {
-            if add {
-                for row in g.iter_mut() {
-                    row.resize(row.len() + lines, BACKGROUND);
-                    if top {
-                        row.rotate_right(lines);
-                    }
-                }
-            } else {
-                for row in g.iter_mut() {
-                    if top {
-                        row.rotate_left(lines);
-                    }
-                    row.truncate(row.len() - lines);
-                }
+                size - lines.min(size.saturating_sub(MIN_GRID_SIDE))
             }
+        };
+        // The side that's *not* changing stays put.
+        let (new_x_size, new_y_size, anchor) = match (left, top) {
+            (Some(true), _) => (new_size(x_size), y_size, Anchor::Right),
+            (Some(false), _) => (new_size(x_size), y_size, Anchor::Left),
+            (None, Some(true)) => (x_size, new_size(y_size), Anchor::Bottom),
+            (None, Some(false)) => (x_size, new_size(y_size), Anchor::Top),
+            (None, None) => return,
+        };
+        if (new_x_size, new_y_size) == (x_size, y_size) {
+            return;
         }
+        let resized = picture.resized(new_x_size, new_y_size, anchor);
+
+        // A burst of clicks becomes one undo entry, instead of a pile of near-identical documents.
+        // But only if the last thing to happen was one of those resizes; otherwise, the undo entry
+        // on top belongs to some other action.
+        let mood = match self.last_resize {
+            Some((time, version))
+                if now - time < RESIZE_MERGE_SECONDS && version == self.editor_gui.version =>
+            {
+                ActionMood::Merge
+            }
+            _ => ActionMood::Normal,
+        };
 
         let mut new_doc = self.editor_gui.document.clone();
-        new_doc.solution_mut().grid = g;
-        self.editor_gui.perform(
-            Action::ReplaceDocument { document: new_doc },
-            ActionMood::Normal,
-        );
+        *new_doc.solution_mut() = resized;
+        self.editor_gui
+            .perform(Action::ReplaceDocument { document: new_doc }, mood);
+        self.last_resize = Some((now, self.editor_gui.version));
     }
 
     fn resizer(&mut self, ui: &mut egui::Ui) {
+        let now = ui.input(|i| i.time);
         let picture = self.editor_gui.document.try_solution().unwrap();
         ui.label(format!(
             "Canvas size: {}x{}",
@@ -958,10 +1574,10 @@ impl NonogramGui {
             ui.label("");
             ui.horizontal(|ui| {
                 if ui.button(icons::ICON_ADD).clicked() {
-                    self.resize(Some(true), None, true);
+                    self.resize(Some(true), None, true, now);
                 }
                 if ui.button(icons::ICON_REMOVE).clicked() {
-                    self.resize(Some(true), None, false);
+                    self.resize(Some(true), None, false, now);
                 }
             });
             ui.label("");
@@ -969,20 +1585,20 @@ impl NonogramGui {
 
             ui.vertical(|ui| {
                 if ui.button(icons::ICON_ADD).clicked() {
-                    self.resize(None, Some(true), true);
+                    self.resize(None, Some(true), true, now);
                 }
                 if ui.button(icons::ICON_REMOVE).clicked() {
-                    self.resize(None, Some(true), false);
+                    self.resize(None, Some(true), false, now);
                 }
             });
             ui.text_edit_singleline(&mut self.lines_to_affect_string);
 
             ui.vertical(|ui| {
                 if ui.button(icons::ICON_ADD).clicked() {
-                    self.resize(None, Some(false), true);
+                    self.resize(None, Some(false), true, now);
                 }
                 if ui.button(icons::ICON_REMOVE).clicked() {
-                    self.resize(None, Some(false), false);
+                    self.resize(None, Some(false), false, now);
                 }
             });
             ui.end_row();
@@ -990,10 +1606,10 @@ impl NonogramGui {
             ui.label("");
             ui.horizontal(|ui| {
                 if ui.button(icons::ICON_ADD).clicked() {
-                    self.resize(Some(false), None, true);
+                    self.resize(Some(false), None, true, now);
                 }
                 if ui.button(icons::ICON_REMOVE).clicked() {
-                    self.resize(Some(false), None, false);
+                    self.resize(Some(false), None, false, now);
                 }
             });
             ui.label("");
@@ -1038,77 +1654,184 @@ impl NonogramGui {
 
             self.resizer(ui);
 
+            ui.horizontal(|ui| {
+                let (across, down) = &mut self.tile_counts;
+                if ui
+                    .button("Tile")
+                    .on_hover_text("Repeat the whole picture, growing the canvas to fit")
+                    .clicked()
+                {
+                    let mut new_doc = self.editor_gui.document.clone();
+                    let tiled = new_doc.solution_mut().tiled(*across, *down);
+                    *new_doc.solution_mut() = tiled;
+                    self.editor_gui.perform(
+                        Action::ReplaceDocument { document: new_doc },
+                        ActionMood::Normal,
+                    );
+                }
+                ui.add(egui::DragValue::new(across).range(1..=10));
+                ui.label("×");
+                ui.add(egui::DragValue::new(down).range(1..=10));
+            });
+
+            let current_color = self.editor_gui.current_color;
+            if ui
+                .add_enabled(
+                    current_color != BACKGROUND,
+                    egui::Button::new("Swap with background"),
+                )
+                .on_hover_text("Trade the selected color and the background, to see the negative")
+                .clicked()
+            {
+                let mut new_doc = self.editor_gui.document.clone();
+                new_doc
+                    .solution_mut()
+                    .swap_colors(BACKGROUND, current_color);
+                self.editor_gui.perform(
+                    Action::ReplaceDocument { document: new_doc },
+                    ActionMood::Normal,
+                );
+            }
+
+            ui.separator();
+            ui.checkbox(&mut self.show_run_lengths, "Show run lengths");
+            if self.show_run_lengths {
+                run_lengths_widget(
+                    ui,
+                    self.editor_gui.document.try_solution().unwrap(),
+                    self.hovered_cell,
+                );
+            }
+
             ui.separator();
             ui.checkbox(&mut self.auto_solve, "auto-solve");
-            if ui.button("Solve").clicked() || self.auto_solve {
+            while let Ok(cache) = self.solve_cache_receiver.try_recv() {
+                // If an interrupted solve overlapped with a newer one, keep the bigger cache.
+                if self
+                    .solve_cache
+                    .as_ref()
+                    .is_none_or(|old| old.cached_lines() < cache.cached_lines())
+                {
+                    self.solve_cache = Some(cache);
+                }
+            }
+            if let Ok((version, puzzle, result)) = self.solve_receiver.try_recv() {
+                self.solve_running = None;
+                let (report, solved_mask) = match result {
+                    Ok(report) => {
+                        let summary = format!(
+                            "{} unsolved cells: {}  difficulty: {:.1} ({})",
+                            report.solve_counts,
+                            report.cells_left,
+                            report.difficulty(),
+                            report.difficulty_band().name()
+                        );
+                        self.editor_gui
+                            .solve_order
+                            .update(report.solve_order, version);
+                        self.line_stats
+                            .update(Some((puzzle, report.row_stats, report.col_stats)), version);
+                        (summary, report.solved_mask)
+                    }
+                    Err(e) => (format!("Error: {:?}", e), vec![]),
+                };
+                self.solve_report = report.clone();
+                self.editor_gui
+                    .solved_mask
+                    .update((report, solved_mask), version);
+            }
+
+            let version = self.editor_gui.version;
+            let already_solving = self
+                .solve_running
+                .as_ref()
+                .is_some_and(|(running_version, _)| *running_version == version);
+            if (ui.button("Solve").clicked() || self.auto_solve)
+                && !self.editor_gui.solved_mask.fresh(version)
+                && !already_solving
+            {
+                if let Some((_, terminate)) = self.solve_running.take() {
+                    let _ = terminate.send(()); // It may have already finished.
+                }
                 let puzzle = self.editor_gui.document.try_solution().unwrap().to_puzzle();
+                let (result_s, result_r) = mpsc::channel();
+                let (terminate_s, terminate_r) = mpsc::channel();
+                self.solve_receiver = result_r;
+                self.solve_running = Some((version, terminate_s));
+
+                let mut cache = self
+                    .solve_cache
+                    .take()
+                    .filter(|cache| cache.cached_lines() < MAX_CACHED_LINES)
+                    .unwrap_or_else(DynSolveCache::new);
+                let cache_s = self.solve_cache_sender.clone();
 
-                let (report, _solved_mask) =
-                    self.editor_gui
-                        .solved_mask
-                        .get_or_refresh(self.editor_gui.version, || match puzzle.plain_solve() {
-                            Ok(grid_solve::Report {
-                                solve_counts,
-                                cells_left,
-                                solution: _solution,
-                                solved_mask,
-                            }) => (
-                                format!("{solve_counts} unsolved cells: {cells_left}"),
-                                solved_mask,
-                            ),
-                            Err(e) => (format!("Error: {:?}", e), vec![]),
-                        });
-                self.solve_report = report.clone();
+                spawn_async(async move {
+                    let options = grid_solve::SolveOptions {
+                        terminate: Some(terminate_r),
+                        ..Default::default()
+                    };
+                    let report = cache.solve_with(&puzzle, &options);
+                    let _ = cache_s.send(cache);
+                    let _ = result_s.send((version, puzzle, report));
+                });
             }
 
-            ui.colored_label(
-                if self.editor_gui.solved_mask.fresh(self.editor_gui.version) {
-                    Color32::BLACK
-                } else {
-                    Color32::GRAY
-                },
-                &self.solve_report,
-            );
+            ui.horizontal(|ui| {
+                if self.solve_running.is_some() {
+                    ui.spinner();
+                }
+                ui.colored_label(
+                    if self.editor_gui.solved_mask.fresh(self.editor_gui.version) {
+                        Color32::BLACK
+                    } else {
+                        Color32::GRAY
+                    },
+                    &self.solve_report,
+                );
+            });
+            ui.checkbox(&mut self.editor_gui.show_solve_order, "Show solve order");
+            ui.checkbox(&mut self.show_line_stats, "Show line difficulty")
+                .on_hover_text("Tints each line's clues by how often the solver had to revisit it");
 
             ui.separator();
 
             self.editor_gui
                 .disambiguator
                 .get_or_refresh(self.editor_gui.version, Disambiguator::new)
-                .disambig_widget(self.editor_gui.document.try_solution().unwrap(), ui);
+                .disambig_widget(
+                    self.editor_gui.document.try_solution().unwrap(),
+                    &mut self.editor_gui.disambig_threshold,
+                    ui,
+                );
 
             ui.separator();
 
             ui.label("Description:");
             ui.text_edit_multiline(&mut self.editor_gui.document.description);
 
-            let cc_by_license_str = "CC BY 4.0";
-            let mut is_cc_by = self.editor_gui.document.license == cc_by_license_str;
-
             ui.label("License:");
-
-            ui.horizontal(|ui| {
-                if ui.radio_value(&mut is_cc_by, true, "").changed() {
-                    self.editor_gui.document.license = cc_by_license_str.to_string();
-                };
-                ui.add(
-                    egui::Hyperlink::from_label_and_url(
-                        cc_by_license_str,
-                        "https://creativecommons.org/licenses/by/4.0/",
-                    )
-                    .open_in_new_tab(true),
-                );
-            });
-
-            ui.horizontal(|ui| {
-                if ui.radio_value(&mut is_cc_by, false, "").changed() {
-                    self.editor_gui.document.license.clear();
-                };
-                ui.add_enabled(
-                    !is_cc_by,
-                    egui::TextEdit::singleline(&mut self.editor_gui.document.license),
-                );
+            let license = &mut self.editor_gui.document.license;
+            let mut license_changed = false;
+            ui.horizontal_wrapped(|ui| {
+                for (name, _) in LICENSE_PRESETS {
+                    if ui.selectable_label(license == name, name).clicked() {
+                        *license = name.to_string();
+                        license_changed = true;
+                    }
+                }
             });
+            license_changed |= ui
+                .add(egui::TextEdit::singleline(license).hint_text("License"))
+                .changed();
+            if let Some((name, Some(url))) =
+                LICENSE_PRESETS.iter().find(|(name, _)| license == name)
+            {
+                ui.add(egui::Hyperlink::from_label_and_url(*name, *url).open_in_new_tab(true));
+            }
+            if license_changed {
+                let _ = UserSettings::set(consts::EDITOR_LICENSE, license);
+            }
         });
     }
 
@@ -1116,41 +1839,252 @@ impl NonogramGui {
         if ui.button("Open").clicked() {
             let (sender, receiver) = mpsc::channel();
             self.opened_file_receiver = receiver;
+            let (zip_sender, zip_receiver) = mpsc::channel();
+            self.opened_zip_receiver = zip_receiver;
 
             spawn_async(async move {
                 let handle = rfd::AsyncFileDialog::new()
                     .add_filter(
                         "all recognized formats",
-                        &["png", "gif", "bmp", "xml", "pbn", "txt", "g"],
+                        &[
+                            "png", "gif", "bmp", "xml", "pbn", "txt", "g", "json", "clues", "nin",
+                            "zip",
+                        ],
                     )
                     .add_filter("image", &["png", "gif", "bmp"])
                     .add_filter("PBN", &["xml", "pbn"])
                     .add_filter("chargrid", &["txt"])
                     .add_filter("Olsak", &["g"])
                     .add_filter("woven", &["woven"])
+                    .add_filter("JSON", &["json"])
+                    .add_filter("clue list", &["clues"])
+                    .add_filter("nin", &["nin"])
+                    .add_filter("zip archive of puzzles", &["zip"])
                     .pick_file()
                     .await;
 
                 if let Some(handle) = handle {
-                    let document =
-                        crate::import::load(&handle.file_name(), handle.read().await, None);
+                    if handle.file_name().ends_with(".zip") {
+                        match crate::import::load_zip(handle.read().await) {
+                            Ok(library) => zip_sender.send(library).unwrap(),
+                            Err(e) => eprintln!("number-loom: unable to read zip: {e:?}"),
+                        }
+                    } else {
+                        let document =
+                            crate::import::load(&handle.file_name(), handle.read().await, None);
 
-                    sender.send(document).unwrap();
+                        #[cfg(not(target_arch = "wasm32"))]
+                        if document.is_ok() {
+                            let path = handle.path().to_string_lossy().to_string();
+                            remember_recent_file(path.clone(), path);
+                        }
+
+                        sender.send(document).unwrap();
+                    }
                 }
             });
         }
 
-        if let Ok(document) = self.opened_file_receiver.try_recv() {
-            self.editor_gui
-                .perform(Action::ReplaceDocument { document }, ActionMood::Normal);
+        ui.menu_button("Recent", |ui| {
+            let recent = recent_files();
+            if recent.is_empty() {
+                ui.label("Nothing opened yet");
+            }
+            for (name, source) in recent {
+                if ui.button(&name).clicked() {
+                    match reopen_recent_file(&source) {
+                        Ok(document) => {
+                            remember_recent_file(name, source);
+                            self.editor_gui
+                                .perform(Action::ReplaceDocument { document }, ActionMood::Normal);
+                        }
+                        Err(e) => {
+                            self.solve_report = format!("Error: {:?}", e);
+                        }
+                    }
+                    ui.close_menu();
+                }
+            }
+        });
+
+        if ui
+            .button("Paste image")
+            .on_hover_text("Turn an image on the clipboard into a puzzle")
+            .clicked()
+        {
+            let (sender, receiver) = mpsc::channel();
+            self.opened_file_receiver = receiver;
+
+            spawn_async(async move {
+                match clipboard_image().await {
+                    Ok(img) => {
+                        let solution = import::image_to_solution(&img);
+                        sender
+                            .send(Ok(Document::from_solution(
+                                solution,
+                                "pasted.png".to_string(),
+                            )))
+                            .unwrap();
+                    }
+                    Err(e) => eprintln!("number-loom: unable to paste image: {e:?}"),
+                }
+            });
+        }
+
+        match self.opened_file_receiver.try_recv() {
+            Ok(Ok(document)) => {
+                self.editor_gui
+                    .perform(Action::ReplaceDocument { document }, ActionMood::Normal);
+
+                #[cfg(target_arch = "wasm32")]
+                if let Ok(woven) = crate::formats::woven::to_woven(&mut self.editor_gui.document) {
+                    remember_recent_file(self.editor_gui.document.file.clone(), woven);
+                }
+            }
+            Ok(Err(e)) => {
+                self.solve_report = format!("Error: {:?}", e);
+            }
+            Err(_) => {}
+        }
+
+        if let Ok(Err(e)) = self.saved_file_receiver.try_recv() {
+            self.solve_report = format!("Error: {:?}", e);
+        }
+
+        if let Ok(library) = self.opened_zip_receiver.try_recv() {
+            // This replaces whatever library was showing, and stops any download in progress.
+            self.library_receiver = mpsc::channel().1;
+            self.open_library_dialog();
+            for document in library {
+                match document {
+                    Ok(document) => self.add_library_document(document),
+                    Err(e) => {
+                        eprintln!("number-loom: {e:?}");
+                        self.library_error = Some(format!("{e:#}"));
+                    }
+                }
+            }
         }
     }
 
+    /// Fetches from `library_url`, or the Number Loom library if it's empty. The library dialog
+    /// opens right away, and fills in as the puzzles arrive.
+    fn fetch_library(&mut self) {
+        let (sender, receiver) = mpsc::channel();
+        self.library_receiver = receiver;
+        self.open_library_dialog();
+        self.library_loading = true;
+
+        let url = self.library_url.trim().to_string();
+        let url = if url.is_empty() {
+            crate::import::GITHUB_PUZZLES_URL.to_string()
+        } else {
+            url
+        };
+        spawn_async(async move {
+            // If the dialog has been closed, nobody's listening; that's fine.
+            let result = crate::import::stream_puzzles_from_url(&url, |document| {
+                let _ = sender.send(Ok(document));
+            })
+            .await;
+            if let Err(e) = result {
+                let _ = sender.send(Err(
+                    e.context(format!("unable to load the library from {url}"))
+                ));
+            }
+        });
+    }
+
+    /// Opens an empty library dialog, forgetting everything about the last library.
+    fn open_library_dialog(&mut self) {
+        self.library_loading = false;
+        self.library_error = None;
+        self.library_dialog = Some(vec![]);
+        self.library_seen.clear();
+        self.library_duplicates = 0;
+        self.library_version = self.library_version.wrapping_add(1);
+        self.solvability.clear();
+        self.solvability_receiver = mpsc::channel().1;
+        self.solvability_pending = false;
+    }
+
+    /// Adds `document` to the library dialog, unless it duplicates a puzzle that's already there.
+    fn add_library_document(&mut self, document: Document) {
+        let Some(docs) = &mut self.library_dialog else {
+            return;
+        };
+        if self.library_seen.insert(document.content_hash()) {
+            docs.push(document);
+        } else {
+            self.library_duplicates += 1;
+        }
+    }
+
+    /// Takes in any newly-downloaded library puzzles.
+    fn receive_library(&mut self) {
+        loop {
+            match self.library_receiver.try_recv() {
+                Ok(Err(e)) => {
+                    eprintln!("number-loom: {e:?}");
+                    self.library_error = Some(format!("{e:#}"));
+                }
+                Ok(Ok(document)) => self.add_library_document(document),
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    self.library_loading = false;
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Works out the solvability of the library puzzles one at a time, in the background.
+    fn classify_library(&mut self) {
+        if self.solvability_separation != self.separation {
+            self.solvability.clear();
+            self.solvability_receiver = mpsc::channel().1;
+            self.solvability_pending = false;
+            self.solvability_separation = self.separation;
+        }
+        if let Ok((key, solvability)) = self.solvability_receiver.try_recv() {
+            self.solvability.insert(key, solvability);
+            self.solvability_pending = false;
+        }
+        if self.solvability_pending {
+            return;
+        }
+        let Some(docs) = &self.library_dialog else {
+            return;
+        };
+        let Some(doc) = docs.iter().find(|doc| {
+            !self
+                .solvability
+                .contains_key(&crate::gui_gallery::gallery_key(doc))
+        }) else {
+            return;
+        };
+
+        let mut doc = doc.clone();
+        let options = grid_solve::SolveOptions {
+            separation: self.separation,
+            ..Default::default()
+        };
+        let (sender, receiver) = mpsc::channel();
+        self.solvability_receiver = receiver;
+        self.solvability_pending = true;
+        spawn_async(async move {
+            let key = crate::gui_gallery::gallery_key(&doc);
+            let solvability = crate::gui_gallery::classify_solvability(&mut doc, &options);
+            let _ = sender.send((key, solvability));
+        });
+    }
+
     fn enter_solve_mode(&mut self) {
         self.solve_mode = true;
 
         self.solve_gui = Some(crate::gui_solver::SolveGui::new(
             self.editor_gui.document.clone(),
+            self.separation,
         ));
     }
 
@@ -1166,13 +2100,8 @@ impl NonogramGui {
             {
                 self.scale = (self.scale - 2.0).max(1.0);
             }
-            let picture = self.editor_gui.document.solution_mut();
             if ui.button("New").clicked() {
-                self.new_dialog = Some(NewPuzzleDialog {
-                    clue_style: picture.clue_style,
-                    x_size: picture.x_size(),
-                    y_size: picture.y_size(),
-                });
+                self.new_dialog = Some(NewPuzzleDialog::from_settings());
             }
             let mut new_document = None;
             if let Some(dialog) = self.new_dialog.as_mut() {
@@ -1198,49 +2127,74 @@ impl NonogramGui {
                         "Trianogram",
                     );
                     if ui.button("Ok").clicked() {
-                        let new_solution = Solution {
-                            grid: vec![vec![BACKGROUND; dialog.y_size]; dialog.x_size],
-                            palette: match dialog.clue_style {
-                                ClueStyle::Nono => import::bw_palette(),
-                                ClueStyle::Triano => import::triano_palette(),
-                            },
-                            clue_style: dialog.clue_style,
-                        };
-                        new_document = Some(Document::from_solution(
-                            new_solution,
-                            "blank.xml".to_owned(),
-                        ));
+                        dialog.save_settings();
+                        new_document = Some(dialog.blank_document());
                         self.solve_mode = false;
                     }
                 });
             }
 
             if ui.button("Library").clicked() {
-                let (sender, receiver) = mpsc::channel();
-                self.library_receiver = receiver;
-
-                spawn_async(async move {
-                    let result = crate::import::puzzles_from_github().await;
-                    if let Ok(library) = result {
-                        sender.send(library).unwrap();
-                    }
-                });
+                self.fetch_library();
             }
 
-            if let Ok(library) = self.library_receiver.try_recv() {
-                self.library_dialog = Some(library);
-            }
+            self.receive_library();
+            self.classify_library();
 
             let mut next_enter_solve_mode = false;
             let mut close_library = false;
+            let mut refetch_library = false;
             if let Some(docs) = &self.library_dialog {
+                let thumbnails = self
+                    .thumbnails
+                    .get_or_refresh(self.library_version, HashMap::new);
+                let solvability = &self.solvability;
                 egui::Window::new("Puzzle Library")
                     .max_size(ctx.screen_rect().size() * 0.9)
                     .show(ctx, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("Source:");
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.library_url)
+                                    .hint_text("Number Loom library"),
+                            )
+                            .on_hover_text(
+                                "A GitHub contents API URL, a directory listing, \
+                                or a text file listing one puzzle per line",
+                            );
+                            if ui.button("Load").clicked() {
+                                refetch_library = true;
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            if self.library_loading {
+                                ui.spinner();
+                                ui.label(format!("Loading... ({} so far)", docs.len()));
+                            }
+                            if self.library_duplicates > 0 {
+                                ui.label(format!("{} duplicates hidden", self.library_duplicates));
+                            }
+                        });
+                        if let Some(error) = &self.library_error {
+                            ui.horizontal(|ui| {
+                                ui.colored_label(ui.visuals().error_fg_color, error);
+                                if ui.button("Retry").clicked() {
+                                    refetch_library = true;
+                                }
+                            });
+                        }
                         egui::ScrollArea::vertical().show(ui, |ui| {
                             egui::Grid::new("library_grid").show(ui, |ui| {
                                 for (i, doc) in docs.iter().enumerate() {
-                                    if crate::gui_gallery::gallery_puzzle_preview(ui, doc).clicked()
+                                    if crate::gui_gallery::gallery_puzzle_preview(
+                                        ui,
+                                        doc,
+                                        thumbnails,
+                                        solvability
+                                            .get(&crate::gui_gallery::gallery_key(doc))
+                                            .copied(),
+                                    )
+                                    .clicked()
                                     {
                                         new_document = Some(doc.clone());
                                         next_enter_solve_mode = true;
@@ -1259,13 +2213,30 @@ impl NonogramGui {
             }
             if close_library {
                 self.library_dialog = None;
+                self.library_receiver = mpsc::channel().1;
+            }
+            if refetch_library {
+                self.fetch_library();
             }
             self.loader(ui);
 
             if ui.button("Save/share").clicked() {
                 self.share_string =
                     crate::formats::woven::to_woven(&mut self.editor_gui.document).unwrap();
-                self.quality_warnings = self.editor_gui.document.quality_check();
+                self.share_qr = qr_images(&self.share_string).map(|images| {
+                    images
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, image)| {
+                            ctx.load_texture(
+                                format!("share_qr_{i}"),
+                                image,
+                                egui::TextureOptions::NEAREST,
+                            )
+                        })
+                        .collect()
+                });
+                self.quality_warnings = self.editor_gui.document.quality_check(self.separation);
                 self.show_save_share_window = true;
             }
 
@@ -1285,7 +2256,15 @@ impl NonogramGui {
                             }
                             ui.separator();
                         }
-                        ui.label("Share String:");
+                        let share_len = self.share_string.chars().count();
+                        ui.label(format!("Share String ({share_len} characters):"));
+                        if share_len > LONG_SHARE_STRING_CHARS {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                "This is long enough that a chat app might truncate it. \
+                                Consider saving it as a file and sharing that instead.",
+                            );
+                        }
                         ui.add(
                             egui::TextEdit::multiline(&mut self.share_string.clone())
                                 .font(TextStyle::Monospace)
@@ -1295,6 +2274,24 @@ impl NonogramGui {
                             ctx.copy_text(self.share_string.clone());
                         }
 
+                        match &self.share_qr {
+                            Some(codes) => {
+                                if codes.len() > 1 {
+                                    ui.label("Scan these in order, and paste the pieces together:");
+                                }
+                                ui.horizontal_wrapped(|ui| {
+                                    for code in codes {
+                                        // Big enough to scan off of a screen.
+                                        let scale = (400.0 / code.size_vec2().x).max(2.0);
+                                        ui.image((code.id(), code.size_vec2() * scale));
+                                    }
+                                });
+                            }
+                            None => {
+                                ui.label("(This puzzle is too big to share as a QR code.)");
+                            }
+                        }
+
                         if self.editor_gui.document.license == "CC BY 4.0" {
                             if self.editor_gui.document.author.trim().is_empty() {
                                 ui.label(
@@ -1318,7 +2315,10 @@ impl NonogramGui {
 
                         ui.separator();
 
-                        ui.label("Paste a 'WOVEN' string to load:");
+                        ui.label(
+                            "Paste a puzzle to load (a 'WOVEN' string, a grid of characters, \
+                            a list of clues, JSON, PBN XML, or Olšák):",
+                        );
                         ui.add(
                             egui::TextEdit::multiline(&mut self.pasted_string)
                                 .font(TextStyle::Monospace)
@@ -1326,7 +2326,9 @@ impl NonogramGui {
                         );
 
                         if ui.button("Load").clicked() {
-                            match crate::formats::woven::from_woven(&self.pasted_string) {
+                            // With no filename to go on, `load` sniffs the format.
+                            let bytes = self.pasted_string.clone().into_bytes();
+                            match crate::import::load("", bytes, None) {
                                 Ok(doc) => {
                                     new_document = Some(doc);
                                     next_enter_solve_mode = true;
@@ -1346,6 +2348,7 @@ impl NonogramGui {
                         ui.label("  .txt: grid of characters");
                         ui.label("  .g: the format used by the Olšák solver");
                         ui.label("  .woven: Number Loom's custom format");
+                        ui.label("  .json: Number Loom's custom format, human-readable");
                         ui.label("  .html: printable puzzle");
 
                         ui.horizontal(|ui| {
@@ -1355,36 +2358,25 @@ impl NonogramGui {
                                     .desired_width(450.0),
                             );
                         });
-                        if ui.button("Save").clicked() {
-                            let mut document_copy = self.editor_gui.document.clone();
-
-                            spawn_async(async move {
-                                let handle = rfd::AsyncFileDialog::new()
-                                    .add_filter(
-                                        "all recognized formats",
-                                        &["png", "gif", "bmp", "xml", "pbn", "txt", "g", "html"],
-                                    )
-                                    .add_filter("image", &["png", "gif", "bmp"])
-                                    .add_filter("PBN", &["xml", "pbn"])
-                                    .add_filter("chargrid", &["txt"])
-                                    .add_filter("Olšák", &["g"])
-                                    .add_filter("woven", &["woven"])
-                                    .add_filter("HTML (for printing)", &["html"])
-                                    .set_file_name(document_copy.file.clone())
-                                    .save_file()
-                                    .await;
-
-                                if let Some(handle) = handle {
-                                    let bytes = to_bytes(
-                                        &mut document_copy,
-                                        Some(handle.file_name()),
-                                        None,
-                                    )
-                                    .unwrap();
-                                    handle.write(&bytes).await.unwrap();
-                                }
-                            });
-                        }
+                        ui.horizontal(|ui| {
+                            if ui.button("Save").clicked() {
+                                self.saved_file_receiver =
+                                    save_with_dialog(self.editor_gui.document.clone());
+                            }
+                            if ui
+                                .button("Save cropped")
+                                .on_hover_text(
+                                    "Save just the part of the picture that isn't background, \
+                                    as its own puzzle",
+                                )
+                                .clicked()
+                            {
+                                let mut document = self.editor_gui.document.clone();
+                                let cropped = document.solution_mut().cropped_to_content();
+                                *document.solution_mut() = cropped;
+                                self.saved_file_receiver = save_with_dialog(document);
+                            }
+                        });
                     });
             }
 
@@ -1421,10 +2413,86 @@ impl NonogramGui {
             if let Some(solve_gui) = &mut self.solve_gui {
                 solve_gui.sidebar(ui);
                 solve_gui.body(ui, self.scale);
+                self.separation = solve_gui.separation;
             } else {
                 self.edit_sidebar(ui);
-                self.editor_gui
-                    .canvas(ui, self.scale, RenderStyle::Experimental);
+                let is_stale = !self.line_stats.fresh(self.editor_gui.version);
+                let line_stats = self
+                    .line_stats
+                    .val
+                    .as_ref()
+                    .filter(|_| self.show_line_stats);
+                let show_coordinates = self.editor_gui.show_coordinates;
+                if line_stats.is_none() && !show_coordinates {
+                    self.hovered_cell =
+                        self.editor_gui
+                            .canvas(ui, self.scale, RenderStyle::Experimental);
+                    return;
+                }
+
+                let (x_size, y_size) = self.editor_gui.document.dimensions();
+                egui::Grid::new("editor_grid").show(ui, |ui| {
+                    if let Some((puzzle, _, col_stats)) = line_stats {
+                        ui.label(""); // Top-left is empty
+                        if show_coordinates {
+                            ui.label("");
+                        }
+                        draw_dyn_clues(
+                            ui,
+                            puzzle,
+                            self.scale,
+                            Orientation::Vertical,
+                            None,
+                            None,
+                            self.editor_gui.ruling,
+                            is_stale,
+                            Some(col_stats),
+                        );
+                        ui.end_row();
+                    }
+
+                    if show_coordinates {
+                        if line_stats.is_some() {
+                            ui.label("");
+                        }
+                        ui.label("");
+                        draw_coordinate_ruler(
+                            ui,
+                            x_size,
+                            self.scale,
+                            Orientation::Vertical,
+                            self.editor_gui.ruling,
+                        );
+                        ui.end_row();
+                    }
+
+                    if let Some((puzzle, row_stats, _)) = line_stats {
+                        draw_dyn_clues(
+                            ui,
+                            puzzle,
+                            self.scale,
+                            Orientation::Horizontal,
+                            None,
+                            None,
+                            self.editor_gui.ruling,
+                            is_stale,
+                            Some(row_stats),
+                        );
+                    }
+                    if show_coordinates {
+                        draw_coordinate_ruler(
+                            ui,
+                            y_size,
+                            self.scale,
+                            Orientation::Horizontal,
+                            self.editor_gui.ruling,
+                        );
+                    }
+                    self.hovered_cell =
+                        self.editor_gui
+                            .canvas(ui, self.scale, RenderStyle::Experimental);
+                    ui.end_row();
+                });
             }
         });
     }
@@ -1436,6 +2504,56 @@ struct NewPuzzleDialog {
     y_size: usize,
 }
 
+impl NewPuzzleDialog {
+    /// Whatever was used last time (or a 20x20 nonogram).
+    fn from_settings() -> Self {
+        let size = |key| {
+            UserSettings::get(key)
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(20)
+        };
+        let clue_style = match UserSettings::get(consts::EDITOR_NEW_CLUE_STYLE).as_deref() {
+            Some("triano") => ClueStyle::Triano,
+            _ => ClueStyle::Nono,
+        };
+        NewPuzzleDialog {
+            clue_style,
+            x_size: size(consts::EDITOR_NEW_X_SIZE),
+            y_size: size(consts::EDITOR_NEW_Y_SIZE),
+        }
+    }
+
+    fn save_settings(&self) {
+        let clue_style = match self.clue_style {
+            ClueStyle::Nono => "nono",
+            ClueStyle::Triano => "triano",
+        };
+        let _ = UserSettings::set(consts::EDITOR_NEW_X_SIZE, &self.x_size.to_string());
+        let _ = UserSettings::set(consts::EDITOR_NEW_Y_SIZE, &self.y_size.to_string());
+        let _ = UserSettings::set(consts::EDITOR_NEW_CLUE_STYLE, clue_style);
+    }
+
+    fn blank_document(&self) -> Document {
+        let solution = Solution {
+            grid: vec![vec![BACKGROUND; self.y_size]; self.x_size],
+            palette: match self.clue_style {
+                ClueStyle::Nono => import::bw_palette(),
+                ClueStyle::Triano => import::triano_palette(),
+            },
+            clue_style: self.clue_style,
+        };
+        let mut document = Document::from_solution(solution, "blank.xml".to_owned());
+        document.author = UserSettings::get(consts::EDITOR_AUTHOR_NAME).unwrap_or_default();
+        document.license = UserSettings::get(consts::EDITOR_LICENSE).unwrap_or_default();
+        document
+    }
+}
+
+/// An empty puzzle, in the size and style last picked in the "New" dialog.
+pub fn blank_document() -> Document {
+    NewPuzzleDialog::from_settings().blank_document()
+}
+
 impl eframe::App for NonogramGui {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Styling. Has to be here instead of `edit_image` to take effect on the Web.
@@ -1483,7 +2601,7 @@ impl Disambiguator {
         self.progress = 0.0;
     }
 
-    pub fn disambig_widget(&mut self, picture: &Solution, ui: &mut egui::Ui) {
+    pub fn disambig_widget(&mut self, picture: &Solution, threshold: &mut f32, ui: &mut egui::Ui) {
         while let Ok(progress) = self.progress_r.try_recv() {
             self.progress = progress;
         }
@@ -1515,6 +2633,10 @@ impl Disambiguator {
         }
 
         ui.add(egui::ProgressBar::new(self.progress).animate(report_running));
+        ui.add(egui::Slider::new(threshold, 0.0..=1.0).text("Closeness to best"))
+            .on_hover_text(
+                "At 1.0, only show the best suggestions; at 0.0, show everything that helps",
+            );
         if ui
             .add_enabled(self.report.is_some(), egui::Button::new("Clear"))
             .clicked()
@@ -1523,3 +2645,87 @@ impl Disambiguator {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_cells_shallow_and_steep() {
+        assert_eq!(
+            line_cells((0, 0), (4, 2)),
+            vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]
+        );
+        assert_eq!(
+            line_cells((0, 0), (2, 4)),
+            vec![(0, 0), (1, 1), (1, 2), (2, 3), (2, 4)]
+        );
+        // Straight and diagonal lines:
+        assert_eq!(
+            line_cells((1, 3), (4, 3)),
+            vec![(1, 3), (2, 3), (3, 3), (4, 3)]
+        );
+        assert_eq!(line_cells((2, 0), (2, 2)), vec![(2, 0), (2, 1), (2, 2)]);
+        assert_eq!(line_cells((0, 0), (2, 2)), vec![(0, 0), (1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn line_cells_reversed() {
+        for (start, end) in [((0, 0), (4, 2)), ((0, 0), (2, 4)), ((0, 3), (3, 0))] {
+            let forward = line_cells(start, end);
+            let backward = line_cells(end, start);
+            assert_eq!(backward.first(), Some(&end));
+            assert_eq!(backward.last(), Some(&start));
+            assert_eq!(forward.len(), backward.len());
+            // Each step moves to a neighboring cell:
+            for cells in [&forward, &backward] {
+                for pair in cells.windows(2) {
+                    let dx = pair[0].0.abs_diff(pair[1].0);
+                    let dy = pair[0].1.abs_diff(pair[1].1);
+                    assert!(dx <= 1 && dy <= 1 && (dx, dy) != (0, 0), "{cells:?}");
+                }
+            }
+        }
+        assert_eq!(
+            line_cells((4, 2), (0, 0)),
+            vec![(4, 2), (3, 1), (2, 1), (1, 0), (0, 0)]
+        );
+    }
+
+    #[test]
+    fn line_cells_single_point() {
+        assert_eq!(line_cells((3, 5), (3, 5)), vec![(3, 5)]);
+        assert_eq!(line_cells((0, 0), (0, 0)), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn hatch_patterns_are_distinct() {
+        // Every color a cell can hold (the possibility mask has 32 bits):
+        let patterns: Vec<(u32, usize)> = (1..32)
+            .filter_map(|c| hatch_pattern(Color(c)))
+            .map(|(angle, lines)| (angle.to_bits(), lines))
+            .collect();
+        assert_eq!(patterns.len(), 30);
+        assert_eq!(patterns.iter().collect::<HashSet<_>>().len(), 30);
+        assert_eq!(hatch_pattern(Color(1)), None);
+    }
+
+    #[test]
+    fn hatching_stays_in_the_cell() {
+        let rect = Rect::from_min_size(Pos2::new(10.0, 20.0), Vec2::splat(8.0));
+        for c in 2..32 {
+            let ci = ColorInfo::default_fg(Color(c));
+            let (_, lines) = hatch_pattern(Color(c)).unwrap();
+            let shapes = hatching(&ci, rect);
+            assert_eq!(shapes.len(), lines);
+            for shape in shapes {
+                let egui::Shape::LineSegment { points, .. } = shape else {
+                    panic!("expected a line segment");
+                };
+                for point in points {
+                    assert!(rect.expand(0.01).contains(point), "{point:?}");
+                }
+            }
+        }
+    }
+}
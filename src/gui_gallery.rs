@@ -1,6 +1,9 @@
 //! The UI for a gallery of puzzles.
 
-use crate::puzzle::{BACKGROUND, Document, Solution};
+use crate::grid_solve::{
+    DifficultyBand, SolveOptions, UNIQUENESS_GUESS_LIMIT, enumerate_solutions_capped,
+};
+use crate::puzzle::{BACKGROUND, Document, PuzzleDynOps, Solution};
 use eframe::egui;
 use egui::{CornerRadius, Vec2};
 use itertools::Itertools;
@@ -34,8 +37,132 @@ fn palette_bar(ui: &mut egui::Ui, rect: egui::Rect, doc: &Document) {
     }
 }
 
-/// Draws a gallery item for a document.
-pub fn gallery_puzzle_preview(ui: &mut egui::Ui, doc: &Document) -> egui::Response {
+const THUMBNAIL_SIDE: f32 = 64.0;
+
+/// Rendered thumbnails, so that a big library isn't redrawn cell-by-cell every frame.
+pub type ThumbnailCache = HashMap<String, egui::TextureHandle>;
+
+/// Identifies a document in the gallery's caches.
+pub fn gallery_key(doc: &Document) -> String {
+    if doc.id.is_empty() {
+        format!("{:x}", doc.content_hash())
+    } else {
+        doc.id.clone()
+    }
+}
+
+/// Rasterizes the picture, downsampled to fit in `THUMBNAIL_SIDE` if it's big.
+fn thumbnail_image(solution: &Solution) -> egui::ColorImage {
+    let (x_size, y_size) = (solution.x_size(), solution.y_size());
+    let max_cells = THUMBNAIL_SIDE as usize;
+    let (x_drawn, y_drawn) = (x_size.min(max_cells), y_size.min(max_cells));
+    let cell_px = (max_cells / x_drawn.max(y_drawn).max(1)).max(1);
+
+    let bg_rgb = solution.palette[&BACKGROUND].rgb;
+    let mut image = egui::ColorImage::new(
+        [x_drawn * cell_px, y_drawn * cell_px],
+        egui::Color32::TRANSPARENT,
+    );
+    for x in 0..x_drawn {
+        for y in 0..y_drawn {
+            let color = solution.grid[x * x_size / x_drawn][y * y_size / y_drawn];
+            let Some(color_info) = solution.palette.get(&color) else {
+                continue;
+            };
+            for px in 0..cell_px {
+                for py in 0..cell_px {
+                    // Where in the cell this pixel's center is, from 0 to 1.
+                    let u = (px as f32 + 0.5) / cell_px as f32;
+                    let v = (py as f32 + 0.5) / cell_px as f32;
+                    let (r, g, b) = match color_info.corner {
+                        Some(corner) if !corner.covers(u, v) => bg_rgb,
+                        _ => color_info.rgb,
+                    };
+                    image[(x * cell_px + px, y * cell_px + py)] = egui::Color32::from_rgb(r, g, b);
+                }
+            }
+        }
+    }
+    image
+}
+
+/// How far the solver gets with a puzzle, with guessing as a last resort.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Solvability {
+    /// Solvable one line at a time, without guessing.
+    LineSolvable(DifficultyBand),
+    /// The line solver gets stuck, but guessing finds exactly one solution.
+    NeedsGuessing,
+    /// There's more than one solution.
+    Ambiguous,
+    /// The line solver gets stuck, and guessing gave up before settling uniqueness.
+    Stuck,
+    /// The clues contradict each other.
+    Contradictory,
+}
+
+pub type SolvabilityCache = HashMap<String, Solvability>;
+
+/// Runs the solver; this can take a while for a big puzzle, so keep it off the UI thread.
+pub fn classify_solvability(doc: &mut Document, options: &SolveOptions) -> Solvability {
+    let puzzle = doc.puzzle();
+    match puzzle.solve(options) {
+        Ok(report) if report.cells_left == 0 => Solvability::LineSolvable(report.difficulty_band()),
+        Ok(_) => {
+            match enumerate_solutions_capped(puzzle, 2, UNIQUENESS_GUESS_LIMIT, options.separation)
+            {
+                Some(solutions) => match solutions.len() {
+                    0 => Solvability::Contradictory,
+                    1 => Solvability::NeedsGuessing,
+                    _ => Solvability::Ambiguous,
+                },
+                None => Solvability::Stuck,
+            }
+        }
+        Err(_) => Solvability::Contradictory,
+    }
+}
+
+fn solvability_badge(ui: &mut egui::Ui, solvability: Option<Solvability>) {
+    let (text, color, hover) = match solvability {
+        Some(Solvability::LineSolvable(band)) => (
+            "✔",
+            egui::Color32::from_rgb(0, 160, 0),
+            format!("Solvable line-by-line ({})", band.name()),
+        ),
+        Some(Solvability::NeedsGuessing) => (
+            "✔",
+            egui::Color32::from_rgb(200, 160, 0),
+            "Has one solution, but it takes guessing to find".to_string(),
+        ),
+        Some(Solvability::Ambiguous) => (
+            "≠",
+            egui::Color32::from_rgb(200, 80, 0),
+            "Has more than one solution".to_string(),
+        ),
+        Some(Solvability::Stuck) => (
+            "?",
+            egui::Color32::from_rgb(200, 160, 0),
+            "Needs guessing, or has more than one solution (gave up checking)".to_string(),
+        ),
+        Some(Solvability::Contradictory) => (
+            "✖",
+            egui::Color32::from_rgb(200, 0, 0),
+            "The clues contradict each other".to_string(),
+        ),
+        None => ("…", egui::Color32::GRAY, "Checking...".to_string()),
+    };
+    ui.label(egui::RichText::new(text).small().color(color))
+        .on_hover_text(hover);
+}
+
+/// Draws a gallery item for a document. `solvability` is `None` if it hasn't been worked out yet.
+pub fn gallery_puzzle_preview(
+    ui: &mut egui::Ui,
+    doc: &Document,
+    thumbnails: &mut ThumbnailCache,
+    solvability: Option<Solvability>,
+) -> egui::Response {
     let title = doc
         .get_or_make_up_title()
         .unwrap_or_else(|_| "Untitled".to_string());
@@ -68,6 +195,32 @@ pub fn gallery_puzzle_preview(ui: &mut egui::Ui, doc: &Document) -> egui::Respon
         .show(ui, |ui| {
             ui.vertical(|ui| {
                 ui.label(egui::RichText::new(title).strong());
+                if let Some(solution) = doc.try_solution() {
+                    let (rect, _response) = ui.allocate_exact_size(
+                        egui::vec2(250.0, THUMBNAIL_SIDE),
+                        egui::Sense::hover(),
+                    );
+                    if solution.x_size() > 0 && solution.y_size() > 0 {
+                        let key = gallery_key(doc);
+                        let texture = thumbnails.entry(key.clone()).or_insert_with(|| {
+                            ui.ctx().load_texture(
+                                format!("thumbnail_{key}"),
+                                thumbnail_image(solution),
+                                egui::TextureOptions::NEAREST,
+                            )
+                        });
+                        let size = texture.size_vec2();
+                        ui.painter().image(
+                            texture.id(),
+                            egui::Rect::from_min_size(
+                                rect.min,
+                                size * (THUMBNAIL_SIDE / size.x.max(size.y)),
+                            ),
+                            egui::Rect::from_min_max(egui::pos2(0.0, 0.0), egui::pos2(1.0, 1.0)),
+                            egui::Color32::WHITE,
+                        );
+                    }
+                }
                 let (mut rect, _response) =
                     ui.allocate_exact_size(egui::vec2(250.0, 10.0), egui::Sense::hover());
 
@@ -78,6 +231,7 @@ pub fn gallery_puzzle_preview(ui: &mut egui::Ui, doc: &Document) -> egui::Respon
                 ui.horizontal(|ui| {
                     ui.small(format!("{}x{}", width, height));
                     ui.small(puzzle_type);
+                    solvability_badge(ui, solvability);
                 });
             });
         });
@@ -1,10 +1,13 @@
 use crate::{
-    grid_solve::LineStatus,
+    formats::woven::SerializableSolution,
+    grid_solve::{Comparison, DEFAULT_DISAMBIG_THRESHOLD, LineStats, LineStatus, SolveOptions},
     gui::{Action, ActionMood, CanvasGui, Disambiguator, Staleable, Tool},
+    line_solve::SeparationRule,
     puzzle::{BACKGROUND, Color, DynPuzzle, PuzzleDynOps, Solution, UNSOLVED},
     user_settings::{UserSettings, consts},
 };
 use egui::{Color32, Pos2, Rect, RichText, Vec2, text::Fonts};
+use std::sync::mpsc;
 
 use crate::puzzle::Document;
 pub struct SolveGui {
@@ -14,10 +17,31 @@ pub struct SolveGui {
     pub analyze_lines: bool,
     pub detect_errors: bool,
     pub infer_background: bool,
+    /// Let inference treat blank (background) cells as undecided, for working from a picture
+    /// that's only partly painted in. This only exists in the solver: the editor's clues come
+    /// from its picture, so there's nothing for a half-drawn picture to be inferred from.
+    pub blank_is_unknown: bool,
+    /// Which adjacent blocks the puzzle's clues require background between.
+    pub separation: SeparationRule,
+    /// For practicing one color at a time.
+    pub only_solve_color: Option<Color>,
     pub line_analysis: Staleable<Option<(Vec<LineStatus>, Vec<LineStatus>)>>,
+    /// Rows and columns that can be filled in right away; a hint for getting started.
+    forced_lines: (Vec<bool>, Vec<bool>),
+    pub show_forced_lines: bool,
+    /// Overlay how many colors each unknown cell could still be.
+    pub show_candidate_counts: bool,
     pub render_style: RenderStyle,
+    /// The result of solving from scratch and comparing to the intended picture, once asked for.
+    comparison: Option<Result<Comparison, String>>,
+    /// Where a comparison that's still running will send its result.
+    comparison_receiver: mpsc::Receiver<Result<Comparison, String>>,
+    comparison_running: bool,
     last_inferred_version: u32,
     pub hovered_cell: Option<(usize, usize)>,
+    progress_key: String,
+    saved_progress: Option<Solution>,
+    last_saved_version: u32,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -25,10 +49,36 @@ pub enum RenderStyle {
     TraditionalDots,
     TraditionalXes,
     Experimental,
+    /// Black-and-white marks, for when grays are hard to tell apart.
+    HighContrast,
+    /// Each color gets a distinct hatching pattern.
+    Patterned,
+}
+
+impl RenderStyle {
+    pub fn all() -> &'static [RenderStyle] {
+        &[
+            RenderStyle::TraditionalDots,
+            RenderStyle::TraditionalXes,
+            RenderStyle::Experimental,
+            RenderStyle::HighContrast,
+            RenderStyle::Patterned,
+        ]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RenderStyle::TraditionalDots => "traditional (dots)",
+            RenderStyle::TraditionalXes => "traditional (Xes)",
+            RenderStyle::Experimental => "experimental",
+            RenderStyle::HighContrast => "high contrast",
+            RenderStyle::Patterned => "patterned",
+        }
+    }
 }
 
 impl SolveGui {
-    pub fn new(mut document: Document) -> Self {
+    pub fn new(mut document: Document, separation: SeparationRule) -> Self {
         let mut working_doc = document.clone();
         for line in &mut working_doc.solution_mut().grid {
             for cell in line {
@@ -51,10 +101,8 @@ impl SolveGui {
         }
 
         let clues = document.puzzle().clone();
-        let solved_mask = vec![
-            vec![true; document.solution_mut().grid[0].len()];
-            document.solution_mut().grid.len()
-        ];
+        let solved_mask =
+            vec![vec![true; document.solution_mut().y_size()]; document.solution_mut().x_size()];
 
         fn get_bool_setting(key: &str) -> bool {
             UserSettings::get(key)
@@ -62,6 +110,31 @@ impl SolveGui {
                 .unwrap_or(false)
         }
 
+        let progress_key = format!(
+            "{}.{}",
+            consts::SOLVER_PROGRESS,
+            if document.id.is_empty() {
+                document.get_or_make_up_title().unwrap()
+            } else {
+                document.id.clone()
+            }
+        );
+        let working_picture = working_doc.solution_mut();
+        let saved_progress = UserSettings::get(&progress_key)
+            .and_then(|s| serde_json::from_str::<SerializableSolution>(&s).ok())
+            .map(|s| Solution::from(&s))
+            .filter(|saved| {
+                // Don't offer anything that doesn't fit the puzzle as it is now.
+                saved.x_size() == working_picture.x_size()
+                    && saved.y_size() == working_picture.y_size()
+                    && saved
+                        .grid
+                        .iter()
+                        .flatten()
+                        .all(|c| working_picture.palette.contains_key(c))
+                    && saved.grid.iter().flatten().any(|c| *c != UNSOLVED)
+            });
+
         SolveGui {
             canvas: CanvasGui {
                 document: working_doc,
@@ -76,41 +149,113 @@ impl SolveGui {
                     val: ("".to_string(), solved_mask),
                     version: 0,
                 },
+                solve_order: Staleable {
+                    val: vec![],
+                    version: u32::MAX,
+                },
+                show_solve_order: false,
+                flagged_cells: Staleable {
+                    val: vec![],
+                    version: u32::MAX,
+                },
+                ruling: 5,
+                show_coordinates: false,
+                hint: Staleable {
+                    val: None,
+                    version: u32::MAX,
+                },
+                selected_cell: None,
+                candidate_counts: Staleable {
+                    val: vec![],
+                    version: u32::MAX,
+                },
+                clue_span: None,
                 disambiguator: Staleable {
                     val: Disambiguator::new(),
                     version: 0,
                 },
+                disambig_threshold: DEFAULT_DISAMBIG_THRESHOLD,
                 id: Staleable {
                     val: "".to_string(),
                     version: 0,
                 },
+                palette_preview: None,
             },
+            forced_lines: clues.forced_lines(separation),
+            show_forced_lines: false,
+            show_candidate_counts: false,
             clues,
             intended_solution: document.take_solution().unwrap(),
             analyze_lines: get_bool_setting(consts::SOLVER_ANALYZE_LINES),
             detect_errors: get_bool_setting(consts::SOLVER_DETECT_ERRORS),
             infer_background: get_bool_setting(consts::SOLVER_INFER_BACKGROUND),
+            blank_is_unknown: false,
+            separation,
+            only_solve_color: None,
             line_analysis: Staleable {
                 val: None,
                 version: u32::MAX,
             },
-            render_style: RenderStyle::Experimental,
+            render_style: UserSettings::get(consts::SOLVER_RENDER_STYLE)
+                .and_then(|name| {
+                    RenderStyle::all()
+                        .iter()
+                        .find(|style| style.name() == name)
+                        .copied()
+                })
+                .unwrap_or(RenderStyle::Experimental),
+            comparison: None,
+            comparison_receiver: mpsc::channel().1,
+            comparison_running: false,
             last_inferred_version: u32::MAX,
             hovered_cell: None,
+            progress_key,
+            saved_progress,
+            last_saved_version: 0,
         }
     }
 
-    fn detect_any_errors(&self) -> bool {
+    /// Stashes the player's marks so they survive closing the app.
+    fn save_progress(&mut self) {
+        // Once the player has started over, the old progress is gone.
+        self.saved_progress = None;
+
+        let picture = self.canvas.document.try_solution().unwrap();
+        if let Ok(serialized) = serde_json::to_string(&SerializableSolution::from(picture)) {
+            let _ = UserSettings::set(&self.progress_key, &serialized);
+        }
+    }
+
+    fn restore_progress(&mut self) {
+        if let Some(saved) = self.saved_progress.take() {
+            let mut changes = std::collections::HashMap::new();
+            for (x, col) in saved.grid.iter().enumerate() {
+                for (y, color) in col.iter().enumerate() {
+                    changes.insert((x, y), *color);
+                }
+            }
+            self.canvas
+                .perform(Action::ChangeColor { changes }, ActionMood::Normal);
+        }
+    }
+
+    /// Cells the player has marked with something other than the intended color.
+    fn wrong_cells(&self) -> Vec<(usize, usize)> {
         let picture = self.canvas.document.try_solution().unwrap();
+        let mut res = vec![];
         for (x, row) in picture.grid.iter().enumerate() {
             for (y, color) in row.iter().enumerate() {
                 if *color != self.intended_solution.grid[x][y] && *color != crate::puzzle::UNSOLVED
                 {
-                    return true;
+                    res.push((x, y));
                 }
             }
         }
-        false
+        res
+    }
+
+    fn detect_any_errors(&self) -> bool {
+        !self.wrong_cells().is_empty()
     }
 
     fn is_correctly_solved(&self) -> bool {
@@ -119,9 +264,17 @@ impl SolveGui {
 
     fn infer_background(&mut self) {
         let picture = self.canvas.document.solution_mut();
-        let mut grid = picture.to_partial();
+        let mut grid = if self.blank_is_unknown {
+            picture.to_partial_with_unknown(&[UNSOLVED, BACKGROUND])
+        } else {
+            picture.to_partial()
+        };
 
-        if self.clues.settle_solution(&mut grid).is_ok() {
+        if self
+            .clues
+            .settle_solution(&mut grid, self.separation)
+            .is_ok()
+        {
             let mut changes = std::collections::HashMap::new();
             for ((y, x), cell) in grid.indexed_iter() {
                 let current_color = picture.grid[x][y];
@@ -137,6 +290,31 @@ impl SolveGui {
         }
     }
 
+    /// Fills in every cell of `color` that line logic can deduce from the current marks.
+    fn deduce_color(&mut self, color: Color) {
+        let picture = self.canvas.document.try_solution().unwrap();
+        let mut grid = picture.to_partial();
+        let options = SolveOptions {
+            only_solve_color: Some(color),
+            separation: self.separation,
+            ..SolveOptions::default()
+        };
+
+        if self.clues.partial_solve(&mut grid, &options).is_ok() {
+            let mut changes = std::collections::HashMap::new();
+            for ((y, x), cell) in grid.indexed_iter() {
+                if cell.known_or() == Some(color) && picture.grid[x][y] != color {
+                    changes.insert((x, y), color);
+                }
+            }
+
+            if !changes.is_empty() {
+                self.canvas
+                    .perform(Action::ChangeColor { changes }, ActionMood::Normal);
+            }
+        }
+    }
+
     pub fn sidebar(&mut self, ui: &mut egui::Ui) {
         ui.vertical(|ui| {
             ui.set_width(150.0);
@@ -148,71 +326,45 @@ impl SolveGui {
                 ui.label(format!("by {}", &self.canvas.document.author));
             }
 
-            self.canvas.common_sidebar_items(ui, true);
-
-            ui.separator();
-            let scale = 20.0;
-            let plus_size = scale * 3.0;
-
-            if let Some((x, y)) = self.hovered_cell {
-                let picture = self.canvas.document.try_solution().unwrap();
-                let (up, down, left, right) = picture.count_contiguous(x, y);
-
-                let color = picture.grid[x][y];
-                let rgb = picture.palette[&color].rgb;
-
-                let (resp, painter) =
-                    ui.allocate_painter(Vec2::new(plus_size, plus_size), egui::Sense::empty());
-
-                let rect = resp.rect;
-                let size = Vec2::new(20.0, 20.0);
+            if self.saved_progress.is_some() {
+                ui.separator();
+                ui.label("You have saved progress on this puzzle.");
+                ui.horizontal(|ui| {
+                    if ui.button("Restore").clicked() {
+                        self.restore_progress();
+                    }
+                    if ui.button("Discard").clicked() {
+                        self.saved_progress = None;
+                    }
+                });
+                ui.separator();
+            }
 
-                let up_rect = Rect::from_min_size(rect.min + Vec2::new(scale, 0.0), size);
-                let down_rect = Rect::from_min_size(rect.min + Vec2::new(scale, 2.0 * scale), size);
-                let mid_rect = Rect::from_min_size(rect.min + Vec2::new(20.0, 20.0), size);
-                let left_rect = Rect::from_min_size(rect.min + Vec2::new(0.0, scale), size);
-                let right_rect =
-                    Rect::from_min_size(rect.min + Vec2::new(2.0 * scale, scale), size);
+            self.canvas.common_sidebar_items(ui, true);
 
-                if up > 0 {
-                    draw_string_in_box(ui, &painter, up_rect, &up.to_string(), scale, rgb);
-                }
-                if down > 0 {
-                    draw_string_in_box(ui, &painter, down_rect, &down.to_string(), scale, rgb);
-                }
-                if left > 0 {
-                    draw_string_in_box(ui, &painter, left_rect, &left.to_string(), scale, rgb);
-                }
-                if right > 0 {
-                    draw_string_in_box(ui, &painter, right_rect, &right.to_string(), scale, rgb);
-                }
-                if color == UNSOLVED {
-                    draw_string_in_box(ui, &painter, mid_rect, "?", scale, rgb);
-                } else {
-                    draw_string_in_box(ui, &painter, mid_rect, " ", scale, rgb);
-                }
-            } else {
-                ui.add_space(plus_size);
+            if self.canvas.version != self.last_saved_version {
+                self.save_progress();
+                self.last_saved_version = self.canvas.version;
             }
 
+            ui.separator();
+            run_lengths_widget(
+                ui,
+                self.canvas.document.try_solution().unwrap(),
+                self.hovered_cell.or(self.canvas.selected_cell),
+            );
+
             ui.separator();
 
             ui.label("Render style");
-            ui.radio_value(
-                &mut self.render_style,
-                RenderStyle::TraditionalDots,
-                "traditional (dots)",
-            );
-            ui.radio_value(
-                &mut self.render_style,
-                RenderStyle::TraditionalXes,
-                "traditional (Xes)",
-            );
-            ui.radio_value(
-                &mut self.render_style,
-                RenderStyle::Experimental,
-                "experimental",
-            );
+            for style in RenderStyle::all() {
+                if ui
+                    .radio_value(&mut self.render_style, *style, style.name())
+                    .changed()
+                {
+                    let _ = UserSettings::set(consts::SOLVER_RENDER_STYLE, style.name());
+                }
+            }
 
             ui.separator();
 
@@ -224,10 +376,29 @@ impl SolveGui {
             }
             if ui.button("Analyze Lines").clicked() || self.analyze_lines {
                 let clues = &self.clues;
+                let only_solve_color = self.only_solve_color;
+                let separation = self.separation;
                 let picture = self.canvas.document.try_solution().unwrap();
                 let grid = picture.to_partial();
-                self.line_analysis
-                    .get_or_refresh(self.canvas.version, || Some(clues.analyze_lines(&grid)));
+                self.line_analysis.get_or_refresh(self.canvas.version, || {
+                    Some(clues.analyze_lines(&grid, only_solve_color, separation))
+                });
+            }
+            ui.checkbox(&mut self.show_forced_lines, "Star lines to start with");
+            ui.checkbox(&mut self.show_candidate_counts, "Show candidate counts");
+            if !self.show_candidate_counts {
+                self.canvas.candidate_counts.version = u32::MAX;
+            } else if !self.canvas.candidate_counts.fresh(self.canvas.version) {
+                let grid = self.canvas.document.try_solution().unwrap().to_partial();
+                let candidates = self.clues.candidates(&grid, self.separation);
+                let counts = candidates
+                    .indexed_iter()
+                    .filter(|((y, x), _)| !grid[[*y, *x]].is_known())
+                    .map(|((y, x), cell)| (x, y, cell.candidate_count() as usize))
+                    .collect();
+                self.canvas
+                    .candidate_counts
+                    .update(counts, self.canvas.version);
             }
 
             ui.separator();
@@ -238,6 +409,30 @@ impl SolveGui {
                     &self.detect_errors.to_string(),
                 );
             }
+            if ui.button("Hint").clicked() {
+                let grid = self.canvas.document.try_solution().unwrap().to_partial();
+                self.canvas
+                    .hint
+                    .update(self.clues.hint(&grid, self.separation), self.canvas.version);
+            }
+            if self.canvas.hint.fresh(self.canvas.version) && self.canvas.hint.val.is_none() {
+                ui.label("No deductions available");
+            }
+            if ui.button("Check").clicked() {
+                self.canvas
+                    .flagged_cells
+                    .update(self.wrong_cells(), self.canvas.version);
+            }
+            if self.canvas.flagged_cells.fresh(self.canvas.version) {
+                if self.canvas.flagged_cells.val.is_empty() {
+                    ui.label("No mistakes so far");
+                } else {
+                    ui.colored_label(
+                        egui::Color32::RED,
+                        format!("{} wrong cells", self.canvas.flagged_cells.val.len()),
+                    );
+                }
+            }
             if ui.button("Detect errors").clicked() || self.detect_errors {
                 if self.detect_any_errors() {
                     ui.colored_label(egui::Color32::RED, "Error detected");
@@ -253,6 +448,77 @@ impl SolveGui {
 
             ui.separator();
 
+            if ui
+                .button("Compare to intended")
+                .on_hover_text(
+                    "Solve from scratch, and compare what the solver can deduce \
+                    to the intended picture",
+                )
+                .clicked()
+            {
+                let (sender, receiver) = mpsc::channel();
+                self.comparison_receiver = receiver;
+                self.comparison_running = true;
+                self.comparison = None;
+
+                let clues = self.clues.clone();
+                let intended_solution = self.intended_solution.clone();
+                let options = SolveOptions {
+                    separation: self.separation,
+                    ..SolveOptions::default()
+                };
+                crate::gui::spawn_async(async move {
+                    let comparison = clues
+                        .solve(&options)
+                        .map(|report| report.compare_to(&intended_solution))
+                        .map_err(|e| format!("{e:#}"));
+                    // If the puzzle's been closed, nobody's listening; that's fine.
+                    let _ = sender.send(comparison);
+                });
+            }
+            if let Ok(comparison) = self.comparison_receiver.try_recv() {
+                self.comparison = Some(comparison);
+                self.comparison_running = false;
+            }
+            if self.comparison_running {
+                ui.spinner();
+            }
+            match &self.comparison {
+                None => {}
+                Some(Err(e)) => {
+                    ui.colored_label(egui::Color32::RED, format!("Unsolvable: {e}"));
+                }
+                Some(Ok(comparison)) => {
+                    if comparison.undetermined.is_empty() && comparison.disagreements.is_empty() {
+                        ui.colored_label(
+                            egui::Color32::GREEN,
+                            "The solver deduces exactly the intended picture",
+                        );
+                    }
+                    if !comparison.undetermined.is_empty() {
+                        ui.colored_label(
+                            egui::Color32::from_rgb(200, 160, 0),
+                            format!(
+                                "{} cells can't be deduced (guessing needed, or another \
+                                solution)",
+                                comparison.undetermined.len()
+                            ),
+                        );
+                    }
+                    if !comparison.disagreements.is_empty() {
+                        ui.colored_label(
+                            egui::Color32::RED,
+                            format!(
+                                "{} cells deduced differently from the intended picture",
+                                comparison.disagreements.len()
+                            ),
+                        );
+                    }
+                }
+            }
+
+            ui.separator();
+
             if ui.checkbox(&mut self.infer_background, "[auto]").changed() {
                 let _ = UserSettings::set(
                     consts::SOLVER_INFER_BACKGROUND,
@@ -265,33 +531,221 @@ impl SolveGui {
                     self.last_inferred_version = self.canvas.version;
                 }
             }
+            if ui
+                .checkbox(&mut self.blank_is_unknown, "Blank cells are undecided")
+                .on_hover_text("Infer from a picture that's only partly painted in")
+                .changed()
+            {
+                // Infer again under the new assumption.
+                self.last_inferred_version = u32::MAX;
+            }
+
+            ui.separator();
+
+            let palette = self.clues.palette();
+            let color_name = |color: Option<Color>| match color {
+                Some(color) => palette[&color].name.clone(),
+                None => "all colors".to_string(),
+            };
+            let mut only_solve_color = self.only_solve_color;
+            egui::ComboBox::from_label("Focus")
+                .selected_text(color_name(only_solve_color))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut only_solve_color, None, color_name(None));
+                    let mut colors: Vec<Color> = palette.keys().cloned().collect();
+                    colors.sort();
+                    for color in colors {
+                        ui.selectable_value(
+                            &mut only_solve_color,
+                            Some(color),
+                            color_name(Some(color)),
+                        );
+                    }
+                });
+            if let Some(color) = only_solve_color {
+                if ui
+                    .button(format!("Deduce {}", color_name(Some(color))))
+                    .clicked()
+                {
+                    self.deduce_color(color);
+                }
+            }
+            if only_solve_color != self.only_solve_color {
+                // The analysis is only about the focused color.
+                self.line_analysis.version = u32::MAX;
+            }
+            self.only_solve_color = only_solve_color;
+
+            let separation_name = |rule: SeparationRule| match rule {
+                SeparationRule::SameColor => "same color",
+                SeparationRule::Always => "always (no-split)",
+                SeparationRule::Never => "never",
+            };
+            let mut separation = self.separation;
+            egui::ComboBox::from_label("Blocks separated")
+                .selected_text(separation_name(separation))
+                .show_ui(ui, |ui| {
+                    for rule in [
+                        SeparationRule::SameColor,
+                        SeparationRule::Always,
+                        SeparationRule::Never,
+                    ] {
+                        ui.selectable_value(&mut separation, rule, separation_name(rule));
+                    }
+                });
+            if separation != self.separation {
+                // Everything deduced so far assumed the old rule.
+                self.separation = separation;
+                self.forced_lines = self.clues.forced_lines(separation);
+                self.line_analysis.version = u32::MAX;
+                self.canvas.candidate_counts.version = u32::MAX;
+                self.canvas.hint.version = u32::MAX;
+                self.last_inferred_version = u32::MAX;
+                self.comparison = None;
+                self.comparison_receiver = mpsc::channel().1;
+                self.comparison_running = false;
+            }
         });
     }
 
+    /// Arrow keys move the cursor; space cycles the cell under it through the palette, and the
+    /// number keys pick a color directly (0 is the background).
+    fn handle_keys(&mut self, ui: &egui::Ui) {
+        use egui::Key;
+
+        let picture = self.canvas.document.try_solution().unwrap();
+        let (x_size, y_size) = (picture.x_size(), picture.y_size());
+        if x_size == 0 || y_size == 0 {
+            return;
+        }
+
+        let (mut x, mut y) = self.canvas.selected_cell.unwrap_or((0, 0));
+        let mut moved = false;
+        let mut new_color = None;
+        ui.input(|i| {
+            if i.key_pressed(Key::ArrowLeft) {
+                x = x.saturating_sub(1);
+                moved = true;
+            }
+            if i.key_pressed(Key::ArrowRight) {
+                x = (x + 1).min(x_size - 1);
+                moved = true;
+            }
+            if i.key_pressed(Key::ArrowUp) {
+                y = y.saturating_sub(1);
+                moved = true;
+            }
+            if i.key_pressed(Key::ArrowDown) {
+                y = (y + 1).min(y_size - 1);
+                moved = true;
+            }
+            if i.key_pressed(Key::Space) {
+                let mut colors: Vec<Color> = picture.palette.keys().copied().collect();
+                colors.sort();
+                let idx = colors.iter().position(|c| *c == picture.grid[x][y]);
+                new_color = Some(colors[idx.map_or(0, |idx| (idx + 1) % colors.len())]);
+            }
+            let digits = [
+                Key::Num0,
+                Key::Num1,
+                Key::Num2,
+                Key::Num3,
+                Key::Num4,
+                Key::Num5,
+                Key::Num6,
+                Key::Num7,
+                Key::Num8,
+                Key::Num9,
+            ];
+            for (n, key) in digits.into_iter().enumerate() {
+                if i.key_pressed(key) && picture.palette.contains_key(&Color(n as u8)) {
+                    new_color = Some(Color(n as u8));
+                }
+            }
+        });
+
+        if moved || new_color.is_some() {
+            self.canvas.selected_cell = Some((x, y));
+        }
+        if let Some(color) = new_color {
+            self.canvas.perform(
+                Action::ChangeColor {
+                    changes: [((x, y), color)].into(),
+                },
+                ActionMood::Normal,
+            );
+        }
+    }
+
     pub fn body(&mut self, ui: &mut egui::Ui, scale: f32) {
+        let (x_size, y_size) = self.canvas.document.dimensions();
+        let show_coordinates = self.canvas.show_coordinates;
         ui.vertical(|ui| {
             egui::Grid::new("solve_grid").show(ui, |ui| {
                 ui.label(""); // Top-left is empty
+                if show_coordinates {
+                    ui.label("");
+                }
                 let is_stale = !self.line_analysis.fresh(self.canvas.version);
                 let line_analysis = self.line_analysis.val.as_ref();
-                draw_dyn_clues(
+                let forced_lines = Some(&self.forced_lines).filter(|_| self.show_forced_lines);
+                let hovered_col_clue = draw_dyn_clues(
                     ui,
                     &self.clues,
                     scale,
                     Orientation::Vertical,
                     line_analysis.map(|la| &la.1[..]),
+                    forced_lines.map(|fl| &fl.1[..]),
+                    self.canvas.ruling,
                     is_stale,
+                    None,
                 );
                 ui.end_row();
 
-                draw_dyn_clues(
+                if show_coordinates {
+                    ui.label("");
+                    ui.label("");
+                    draw_coordinate_ruler(
+                        ui,
+                        x_size,
+                        scale,
+                        Orientation::Vertical,
+                        self.canvas.ruling,
+                    );
+                    ui.end_row();
+                }
+
+                let hovered_row_clue = draw_dyn_clues(
                     ui,
                     &self.clues,
                     scale,
                     Orientation::Horizontal,
                     line_analysis.map(|la| &la.0[..]),
+                    forced_lines.map(|fl| &fl.0[..]),
+                    self.canvas.ruling,
                     is_stale,
+                    None,
                 );
+                if show_coordinates {
+                    draw_coordinate_ruler(
+                        ui,
+                        y_size,
+                        scale,
+                        Orientation::Horizontal,
+                        self.canvas.ruling,
+                    );
+                }
+                let hovered_clue = hovered_row_clue
+                    .map(|(lane, clue)| (true, lane, clue))
+                    .or(hovered_col_clue.map(|(lane, clue)| (false, lane, clue)));
+                self.canvas.clue_span = hovered_clue.and_then(|(row, lane, clue)| {
+                    let grid = self.canvas.document.try_solution().unwrap().to_partial();
+                    let (start, end) =
+                        self.clues
+                            .clue_span(&grid, row, lane, clue, self.separation)?;
+                    Some((row, lane, start, end))
+                });
+                self.handle_keys(ui);
                 self.hovered_cell = self.canvas.canvas(ui, scale, self.render_style);
                 ui.end_row();
             });
@@ -299,15 +753,116 @@ impl SolveGui {
     }
 }
 
-#[derive(Clone, Copy)]
-pub enum Orientation {
-    Horizontal,
-    Vertical,
+pub use crate::clue_layout::Orientation;
+
+/// 1-based line numbers to go alongside the grid: above it for `Vertical` (columns), and to its
+/// left for `Horizontal` (rows). Hovering shows the `R{n}`/`C{n}` name the solve trace uses.
+pub fn draw_coordinate_ruler(
+    ui: &mut egui::Ui,
+    count: usize,
+    scale: f32,
+    orientation: Orientation,
+    ruling: usize,
+) {
+    let along = scale * count as f32 + 2.0; // Matches the canvas border
+    let size = match orientation {
+        Orientation::Vertical => Vec2::new(along, scale * 0.6),
+        Orientation::Horizontal => Vec2::new(scale, along),
+    };
+    let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+    let start = response.rect.min + Vec2::new(1.0, 1.0);
+
+    for i in 0..count {
+        let offset = scale * (i as f32 + 0.5);
+        let (pos, align) = match orientation {
+            Orientation::Vertical => (
+                Pos2::new(start.x + offset, response.rect.max.y),
+                egui::Align2::CENTER_BOTTOM,
+            ),
+            Orientation::Horizontal => (
+                Pos2::new(response.rect.max.x - 2.0, start.y + offset),
+                egui::Align2::RIGHT_CENTER,
+            ),
+        };
+        // Emphasize the same lines the heavier gridlines close off:
+        let (font, color) = if crate::gui::is_ruled(i + 1, ruling) {
+            (
+                egui::FontId::proportional(scale * 0.5),
+                ui.visuals().strong_text_color(),
+            )
+        } else {
+            (
+                egui::FontId::proportional(scale * 0.4),
+                ui.visuals().weak_text_color(),
+            )
+        };
+        painter.text(pos, align, (i + 1).to_string(), font, color);
+    }
+
+    if let Some(hover) = response.hover_pos() {
+        let (offset, prefix) = match orientation {
+            Orientation::Vertical => (hover.x - start.x, "C"),
+            Orientation::Horizontal => (hover.y - start.y, "R"),
+        };
+        let i = (offset / scale).floor();
+        if i >= 0.0 && (i as usize) < count {
+            response.on_hover_text(format!("{}{}", prefix, i as usize + 1));
+        }
+    }
 }
 
 use crate::line_solve::SolveMode;
 
-fn draw_string_in_box(
+/// A plus shape showing how far the hovered cell's color runs in each direction.
+pub fn run_lengths_widget(
+    ui: &mut egui::Ui,
+    picture: &Solution,
+    hovered_cell: Option<(usize, usize)>,
+) {
+    let scale = 20.0;
+    let plus_size = scale * 3.0;
+
+    if let Some((x, y)) = hovered_cell {
+        let (up, down, left, right) = picture.count_contiguous(x, y);
+
+        let color = picture.grid[x][y];
+        let rgb = picture.palette[&color].rgb;
+
+        let (resp, painter) =
+            ui.allocate_painter(Vec2::new(plus_size, plus_size), egui::Sense::empty());
+
+        let rect = resp.rect;
+        let size = Vec2::new(20.0, 20.0);
+
+        let up_rect = Rect::from_min_size(rect.min + Vec2::new(scale, 0.0), size);
+        let down_rect = Rect::from_min_size(rect.min + Vec2::new(scale, 2.0 * scale), size);
+        let mid_rect = Rect::from_min_size(rect.min + Vec2::new(20.0, 20.0), size);
+        let left_rect = Rect::from_min_size(rect.min + Vec2::new(0.0, scale), size);
+        let right_rect = Rect::from_min_size(rect.min + Vec2::new(2.0 * scale, scale), size);
+
+        if up > 0 {
+            draw_string_in_box(ui, &painter, up_rect, &up.to_string(), scale, rgb);
+        }
+        if down > 0 {
+            draw_string_in_box(ui, &painter, down_rect, &down.to_string(), scale, rgb);
+        }
+        if left > 0 {
+            draw_string_in_box(ui, &painter, left_rect, &left.to_string(), scale, rgb);
+        }
+        if right > 0 {
+            draw_string_in_box(ui, &painter, right_rect, &right.to_string(), scale, rgb);
+        }
+        if color == UNSOLVED {
+            draw_string_in_box(ui, &painter, mid_rect, "?", scale, rgb);
+        } else {
+            draw_string_in_box(ui, &painter, mid_rect, " ", scale, rgb);
+        }
+    } else {
+        ui.add_space(plus_size);
+    }
+}
+
+pub fn draw_string_in_box(
     ui: &egui::Ui,
     painter: &egui::Painter,
     rect: Rect,
@@ -323,11 +878,8 @@ fn draw_string_in_box(
             .rect
             .width()
     };
-    let text_color = if r as u16 + g as u16 + b as u16 > 384 {
-        Color32::BLACK
-    } else {
-        Color32::WHITE
-    };
+    let (text_r, text_g, text_b) = crate::clue_layout::text_rgb_on((r, g, b));
+    let text_color = Color32::from_rgb(text_r, text_g, text_b);
 
     let (width_2, width_3) = ui.fonts(|f| {
         (
@@ -359,37 +911,60 @@ fn draw_clues<C: crate::puzzle::Clue>(
     scale: f32,
     orientation: Orientation,
     line_analysis: Option<&[LineStatus]>,
+    forced_lines: Option<&[bool]>,
+    ruling: usize,
     is_stale: bool,
-) {
-    let puzz_padding = 10.0;
-    let between_clues = scale * 0.5;
-    let box_side = scale * 0.9;
-    let box_margin = (scale - box_side) / 2.0;
-
-    let clues_vec = match orientation {
-        Orientation::Horizontal => &puzzle.rows,
-        Orientation::Vertical => &puzzle.cols,
-    };
-
-    let mut max_size: f32 = 0.0;
-    for line_clues in clues_vec {
-        let mut this_size = 0.0;
-        for clue in line_clues {
-            this_size += box_side * (clue.express(puzzle).len() as f32) + between_clues;
-        }
-        max_size = max_size.max(this_size);
-    }
-    max_size += puzz_padding;
+    line_stats: Option<&[LineStats]>,
+) -> Option<(usize, usize)> {
+    let puzz_padding = crate::clue_layout::PUZZ_PADDING;
+    let layout =
+        crate::clue_layout::layout_clues(puzzle, scale, orientation, forced_lines.is_some());
+    let num_lanes = layout.lane_starts.len();
 
     let (response, painter) = ui.allocate_painter(
-        match orientation {
-            Orientation::Horizontal => Vec2::new(max_size, scale * puzzle.rows.len() as f32),
-            Orientation::Vertical => Vec2::new(scale * puzzle.cols.len() as f32, max_size),
-        } + Vec2::new(2.0, 2.0),
-        egui::Sense::empty(),
+        Vec2::new(layout.size.0, layout.size.1) + Vec2::new(2.0, 2.0),
+        egui::Sense::hover(),
     );
+    // The clues hug the grid, so the layout's far edge lines up with the far edge of the painter.
+    let origin = match orientation {
+        Orientation::Horizontal => {
+            Pos2::new(response.rect.max.x - layout.size.0, response.rect.min.y)
+        }
+        Orientation::Vertical => {
+            Pos2::new(response.rect.min.x, response.rect.max.y - layout.size.1)
+        }
+    };
+
+    let most_visits = line_stats.map_or(0, |ls| ls.iter().map(|s| s.visits).max().unwrap_or(0));
+
+    for i in 0..num_lanes {
+        // Lines the solver kept coming back to are tinted more heavily.
+        if let Some(stats) = line_stats.filter(|_| most_visits > 0) {
+            let line_rect = match orientation {
+                Orientation::Horizontal => Rect::from_x_y_ranges(
+                    response.rect.x_range(),
+                    response.rect.min.y + i as f32 * scale
+                        ..=response.rect.min.y + (i + 1) as f32 * scale,
+                ),
+                Orientation::Vertical => Rect::from_x_y_ranges(
+                    response.rect.min.x + i as f32 * scale
+                        ..=response.rect.min.x + (i + 1) as f32 * scale,
+                    response.rect.y_range(),
+                ),
+            };
+            let heat = stats[i].visits as f32 / most_visits as f32;
+            let alpha = (heat * 96.0) as u8;
+            painter.rect_filled(
+                line_rect,
+                0.0,
+                if is_stale {
+                    Color32::from_black_alpha(alpha / 2)
+                } else {
+                    Color32::from_rgba_unmultiplied(255, 128, 0, alpha)
+                },
+            );
+        }
 
-    for i in 0..clues_vec.len() {
         if let Some(analysis) = line_analysis {
             let center = match orientation {
                 Orientation::Horizontal => Pos2::new(
@@ -412,7 +987,7 @@ fn draw_clues<C: crate::puzzle::Clue>(
                 Ok(Some(SolveMode::Skim)) => {
                     painter.circle_filled(center, radius, color);
                 }
-                Ok(Some(SolveMode::Scrub)) => {
+                Ok(Some(SolveMode::Scrub | SolveMode::Enumerate)) => {
                     let points = vec![
                         center + Vec2::new(0.0, -radius),
                         center + Vec2::new(radius, 0.0),
@@ -426,6 +1001,26 @@ fn draw_clues<C: crate::puzzle::Clue>(
                     ));
                 }
                 Err(_) => {
+                    if !is_stale {
+                        // Highlight the whole line, so it's easy to find the broken clues.
+                        let line_rect = match orientation {
+                            Orientation::Horizontal => Rect::from_x_y_ranges(
+                                response.rect.x_range(),
+                                response.rect.min.y + i as f32 * scale
+                                    ..=response.rect.min.y + (i + 1) as f32 * scale,
+                            ),
+                            Orientation::Vertical => Rect::from_x_y_ranges(
+                                response.rect.min.x + i as f32 * scale
+                                    ..=response.rect.min.x + (i + 1) as f32 * scale,
+                                response.rect.y_range(),
+                            ),
+                        };
+                        painter.rect_filled(
+                            line_rect,
+                            0.0,
+                            Color32::from_rgba_unmultiplied(255, 0, 0, 48),
+                        );
+                    }
                     let stroke = egui::Stroke::new(2.0, Color32::RED);
                     painter.line_segment(
                         [
@@ -446,93 +1041,123 @@ fn draw_clues<C: crate::puzzle::Clue>(
             }
         }
 
-        let line_clues = &clues_vec[i];
-        let mut current_pos = match orientation {
-            Orientation::Horizontal => response.rect.max.x - puzz_padding,
-            Orientation::Vertical => response.rect.max.y - puzz_padding,
-        };
-
-        for clue in line_clues.iter().rev() {
-            let expressed_clues = clue.express(puzzle);
-
-            for (color_info, len) in expressed_clues.into_iter().rev() {
-                let (r, g, b) = color_info.rgb;
-                let bg_color = egui::Color32::from_rgb(r, g, b);
-
-                let corner = match orientation {
-                    Orientation::Horizontal => Pos2::new(
-                        current_pos,
-                        response.rect.min.y + (i as f32) * scale + box_margin,
-                    ),
-                    Orientation::Vertical => Pos2::new(
-                        response.rect.min.x + (i as f32) * scale + box_margin,
-                        current_pos,
-                    ),
+        if forced_lines.is_some_and(|forced| forced[i]) {
+            let along = layout.lane_starts[i] - scale * 0.5;
+            let across = (i as f32 + 0.5) * scale;
+            let center = origin
+                + match orientation {
+                    Orientation::Horizontal => Vec2::new(along, across),
+                    Orientation::Vertical => Vec2::new(across, along),
                 };
+            painter.add(star_shape(center, scale * 0.4));
+        }
+    }
 
-                if let Some(len) = len {
-                    assert!(len > 0);
-
-                    let translated_corner = corner
-                        + match orientation {
-                            Orientation::Horizontal => Vec2::new(-box_side, 0.0),
-                            Orientation::Vertical => Vec2::new(0.0, -box_side),
-                        };
-
-                    let rect =
-                        Rect::from_min_size(translated_corner, Vec2::new(box_side, box_side));
-                    draw_string_in_box(ui, &painter, rect, &len.to_string(), scale, color_info.rgb);
-                    current_pos -= box_side;
-                } else {
-                    let mut triangle = crate::gui::triangle_shape(
-                        color_info.corner.expect("must be a corner"),
-                        bg_color,
-                        Vec2::new(box_side, box_side),
-                    );
-                    let translated_corner = corner
-                        + match orientation {
-                            Orientation::Horizontal => Vec2::new(-box_side, 0.0),
-                            Orientation::Vertical => Vec2::new(0.0, -box_side),
-                        };
-                    triangle.translate(translated_corner.to_vec2());
-                    current_pos -= box_side;
+    let hover_pos = response.hover_pos();
+    let mut hovered_clue = None;
+    for clue_box in &layout.boxes {
+        let rect = Rect::from_min_size(
+            origin + Vec2::new(clue_box.min.0, clue_box.min.1),
+            Vec2::splat(clue_box.side),
+        );
+        if hover_pos.is_some_and(|pos| rect.contains(pos)) {
+            hovered_clue = Some((clue_box.lane, clue_box.clue));
+        }
+        let color_info = clue_box.color_info;
+        if let Some(count) = clue_box.count {
+            assert!(count > 0);
+            draw_string_in_box(
+                ui,
+                &painter,
+                rect,
+                &count.to_string(),
+                scale,
+                color_info.rgb,
+            );
+        } else {
+            let (r, g, b) = color_info.rgb;
+            let mut triangle = crate::gui::triangle_shape(
+                color_info.corner.expect("must be a corner"),
+                Color32::from_rgb(r, g, b),
+                Vec2::splat(clue_box.side),
+            );
+            triangle.translate(rect.min.to_vec2());
+            painter.add(triangle);
+        }
+    }
 
-                    painter.add(triangle);
-                }
-            }
-            current_pos -= between_clues;
+    // Match the heavier gridlines of the canvas:
+    let stroke = egui::Stroke::new(1.0, Color32::from_black_alpha(64));
+    let rect = response.rect;
+    for i in 1..num_lanes {
+        if !crate::gui::is_ruled(i, ruling) {
+            continue;
         }
+        let offset = i as f32 * scale;
+        let points = match orientation {
+            Orientation::Horizontal => [
+                Pos2::new(rect.min.x, rect.min.y + offset),
+                Pos2::new(rect.max.x, rect.min.y + offset),
+            ],
+            Orientation::Vertical => [
+                Pos2::new(rect.min.x + offset, rect.min.y),
+                Pos2::new(rect.min.x + offset, rect.max.y),
+            ],
+        };
+        painter.line_segment(points, stroke);
     }
+
+    hovered_clue
+}
+
+fn star_shape(center: Pos2, radius: f32) -> egui::Shape {
+    let points = (0..10)
+        .map(|i| {
+            let angle = std::f32::consts::PI * (i as f32 / 5.0 - 0.5);
+            let r = if i % 2 == 0 { radius } else { radius * 0.4 };
+            center + Vec2::angled(angle) * r
+        })
+        .collect();
+    egui::Shape::closed_line(
+        points,
+        egui::Stroke::new(1.5, Color32::from_rgb(200, 150, 0)),
+    )
 }
 
+/// Returns the `(lane, clue)` index of the clue under the pointer, if any.
 pub fn draw_dyn_clues(
     ui: &mut egui::Ui,
     puzzle: &DynPuzzle,
     scale: f32,
     orientation: Orientation,
     line_analysis: Option<&[LineStatus]>,
+    forced_lines: Option<&[bool]>,
+    ruling: usize,
     is_stale: bool,
-) {
+    line_stats: Option<&[LineStats]>,
+) -> Option<(usize, usize)> {
     match puzzle {
-        DynPuzzle::Nono(puzzle) => {
-            draw_clues::<crate::puzzle::Nono>(
-                ui,
-                puzzle,
-                scale,
-                orientation,
-                line_analysis,
-                is_stale,
-            );
-        }
-        DynPuzzle::Triano(puzzle) => {
-            draw_clues::<crate::puzzle::Triano>(
-                ui,
-                puzzle,
-                scale,
-                orientation,
-                line_analysis,
-                is_stale,
-            );
-        }
+        DynPuzzle::Nono(puzzle) => draw_clues::<crate::puzzle::Nono>(
+            ui,
+            puzzle,
+            scale,
+            orientation,
+            line_analysis,
+            forced_lines,
+            ruling,
+            is_stale,
+            line_stats,
+        ),
+        DynPuzzle::Triano(puzzle) => draw_clues::<crate::puzzle::Triano>(
+            ui,
+            puzzle,
+            scale,
+            orientation,
+            line_analysis,
+            forced_lines,
+            ruling,
+            is_stale,
+            line_stats,
+        ),
     }
 }
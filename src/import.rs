@@ -1,71 +1,182 @@
-use anyhow::bail;
-use image::{DynamicImage, GenericImageView, Pixel, Rgba};
+use anyhow::{Context, bail};
+use image::{DynamicImage, GenericImageView, Rgba};
 use std::{
     char::from_digit,
     collections::{BTreeMap, BTreeSet, HashMap, HashSet},
-    io::Cursor,
-    io::Read,
+    io::{BufRead, BufReader, Cursor, Read, Seek},
     iter::FromIterator,
     path::PathBuf,
 };
 
 use crate::{
-    formats::woven::from_woven,
+    formats::woven::{from_json, from_woven},
+    line_solve::{Cell, SeparationRule},
     puzzle::{
         self, BACKGROUND, ClueStyle, Color, ColorInfo, Corner, Document, DynPuzzle, Nono,
-        NonogramFormat, Puzzle, Solution, Triano,
+        NonogramFormat, PartialSolution, Puzzle, Solution, Triano, UNSOLVED,
     },
 };
 
-pub fn load_path(path: &PathBuf, format: Option<NonogramFormat>) -> Document {
+pub fn load_path(path: &PathBuf, format: Option<NonogramFormat>) -> anyhow::Result<Document> {
+    let filename = path.to_string_lossy();
     let mut bytes = vec![];
     if path == &PathBuf::from("-") {
-        std::io::stdin().read_to_end(&mut bytes).unwrap();
-    } else {
-        bytes = std::fs::read(path).unwrap();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        return load(&filename, bytes, format);
     }
 
-    load(&path.to_str().unwrap(), bytes, format)
+    let file = std::fs::File::open(path).with_context(|| format!("unable to read {path:?}"))?;
+    let mut reader = BufReader::new(file);
+    let format = match format.or_else(|| puzzle::format_from_extension(&filename)) {
+        Some(format) => format,
+        None => puzzle::sniff_format(
+            reader
+                .fill_buf()
+                .with_context(|| format!("unable to read {path:?}"))?,
+        ),
+    };
+    // Big images can be decoded straight from the file.
+    if matches!(format, NonogramFormat::Image) {
+        return load_image(&filename, reader);
+    }
+    reader
+        .read_to_end(&mut bytes)
+        .with_context(|| format!("unable to read {path:?}"))?;
+    load(&filename, bytes, Some(format))
+}
+
+/// Decodes an image (in any format `image` can recognize) into a document.
+pub fn load_image<R: BufRead + Seek>(filename: &str, mut reader: R) -> anyhow::Result<Document> {
+    let source_palette = source_palette(
+        reader
+            .fill_buf()
+            .with_context(|| format!("unable to read {filename}"))?,
+    )
+    .unwrap_or_default();
+    let img = image::ImageReader::new(reader)
+        .with_guessed_format()?
+        .decode()
+        .with_context(|| format!("unable to read {filename} as an image"))?;
+    let solution = image_to_solution_in_order(&img, &source_palette);
+    Ok(Document::from_solution(solution, filename.to_string()))
 }
 
-pub fn load(filename: &str, bytes: Vec<u8>, format: Option<NonogramFormat>) -> Document {
+/// The color table of an indexed GIF or BMP, in index order, read from the start of the file.
+/// (`image` decodes these to truecolor, so this is the only way to learn the author's order.)
+fn source_palette(header: &[u8]) -> Option<Vec<(u8, u8, u8)>> {
+    let u16_at = |i: usize| -> Option<usize> {
+        Some(u16::from_le_bytes(header.get(i..i + 2)?.try_into().ok()?) as usize)
+    };
+    let u32_at = |i: usize| -> Option<usize> {
+        Some(u32::from_le_bytes(header.get(i..i + 4)?.try_into().ok()?) as usize)
+    };
+
+    let (start, count, entry_len, bgr) = if header.starts_with(b"GIF8") {
+        let packed = *header.get(10)?;
+        if packed & 0x80 == 0 {
+            return None; // No global color table
+        }
+        (13, 2_usize << (packed & 0x07), 3, false)
+    } else if header.starts_with(b"BM") {
+        let dib_len = u32_at(14)?;
+        let (bits, colors_used, entry_len) = if dib_len == 12 {
+            (u16_at(24)?, 0, 3) // The old OS/2 header
+        } else {
+            (u16_at(28)?, u32_at(46)?, 4)
+        };
+        if bits > 8 {
+            return None; // Truecolor
+        }
+        let count = if colors_used == 0 {
+            1 << bits
+        } else {
+            colors_used
+        };
+        (14 + dib_len, count, entry_len, true)
+    } else {
+        return None;
+    };
+
+    let table = header.get(start..start.checked_add(count.checked_mul(entry_len)?)?)?;
+    Some(
+        table
+            .chunks_exact(entry_len)
+            .map(|entry| {
+                if bgr {
+                    (entry[2], entry[1], entry[0])
+                } else {
+                    (entry[0], entry[1], entry[2])
+                }
+            })
+            .collect(),
+    )
+}
+
+pub fn load(
+    filename: &str,
+    bytes: Vec<u8>,
+    format: Option<NonogramFormat>,
+) -> anyhow::Result<Document> {
     use crate::formats::webpbn::webpbn_to_document;
 
-    let input_format = puzzle::infer_format(&filename, format);
+    let input_format = format
+        .or_else(|| puzzle::format_from_extension(filename))
+        .unwrap_or_else(|| puzzle::sniff_format(&bytes));
 
-    match input_format {
+    let text = |bytes: Vec<u8>| {
+        String::from_utf8(bytes).with_context(|| format!("{filename} is not valid UTF-8"))
+    };
+
+    let document = match input_format {
         NonogramFormat::Html => {
-            panic!("HTML input is not supported.")
+            bail!("HTML input is not supported.")
         }
-        NonogramFormat::Image => {
-            let img = image::load_from_memory(&bytes).unwrap();
-            let solution = image_to_solution(&img);
-            Document::from_solution(solution, filename.to_string())
+        NonogramFormat::ClueList => {
+            let puzzle = clue_text_to_puzzle(&text(bytes)?)?;
+            Document::from_puzzle(DynPuzzle::Nono(puzzle), filename.to_string())
+        }
+        NonogramFormat::Nin => {
+            let puzzle = crate::formats::nin::nin_to_puzzle(&text(bytes)?)?;
+            Document::from_puzzle(DynPuzzle::Nono(puzzle), filename.to_string())
         }
+        NonogramFormat::Image => load_image(filename, Cursor::new(bytes))?,
         NonogramFormat::Webpbn => {
-            let webpbn_string = String::from_utf8(bytes).unwrap();
-            let mut doc = webpbn_to_document(&webpbn_string);
+            let mut doc = webpbn_to_document(&text(bytes)?)?;
             doc.file = filename.to_string();
             doc
         }
         NonogramFormat::CharGrid => {
-            let grid_string = String::from_utf8(bytes).unwrap();
-            let solution = char_grid_to_solution(&grid_string);
+            let solution = char_grid_to_solution(&text(bytes)?);
             Document::from_solution(solution, filename.to_string())
         }
-        NonogramFormat::Woven => {
-            let woven_string = String::from_utf8(bytes).unwrap();
-            from_woven(&woven_string).unwrap()
-        }
+        NonogramFormat::Woven => from_woven(text(bytes)?.trim())?,
+        NonogramFormat::Json => from_json(&text(bytes)?)?,
         NonogramFormat::Olsak => {
-            let olsak_string = String::from_utf8(bytes).unwrap();
-            let puzzle = olsak_to_puzzle(&olsak_string).unwrap();
-            Document::from_puzzle(puzzle, filename.to_string())
+            let (puzzle, goal) = olsak_to_puzzle_and_goal(&text(bytes)?)?;
+            let mut doc = Document::new(
+                Some(puzzle),
+                goal,
+                filename.to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            );
+            doc.drop_inconsistent_solution();
+            doc
         }
-    }
+    };
+    Ok(document)
 }
 
 pub fn image_to_solution(image: &DynamicImage) -> Solution {
+    image_to_solution_in_order(image, &[])
+}
+
+/// Like `image_to_solution`, but the colors in `source_palette` (the file's own color table) are
+/// numbered in its order, ahead of any others.
+fn image_to_solution_in_order(image: &DynamicImage, source_palette: &[(u8, u8, u8)]) -> Solution {
     let (width, height) = image.dimensions();
 
     let mut palette = HashMap::<image::Rgba<u8>, ColorInfo>::new();
@@ -80,37 +191,66 @@ pub fn image_to_solution(image: &DynamicImage) -> Solution {
     let mut next_char = 'a';
     let mut next_color_idx: u8 = 1; // BACKGROUND is 0
 
-    // Gather the palette
-    for y in 0..height {
-        for x in 0..width {
-            let pixel: Rgba<u8> = image.get_pixel(x, y);
-            let color = palette.entry(pixel).or_insert_with(|| {
-                let this_char = next_char;
-                let [r, g, b] = pixel.channels()[0..3] else {
-                    panic!("Image with fewer than three channels?")
-                };
-                let this_color = Color(next_color_idx);
-
-                // Don't crash for too many colors, but the quality check should complain:
-                next_color_idx = next_color_idx.wrapping_add(1);
-
-                if r == 0 && g == 0 && b == 0 {
-                    return ColorInfo::default_fg(this_color);
-                }
+    // `image` expands indexed GIFs/BMPs/PNGs to truecolor when decoding, so we rebuild the palette
+    // from the pixels. Converting once is much faster than `get_pixel` on a `DynamicImage`, and
+    // since indexed pictures are mostly runs of one color, we skip the palette lookup when a pixel
+    // repeats the previous one.
+    let rgba = image.to_rgba8();
+
+    // Gather the palette. Characters are handed out in the file's palette order, and then in rgb
+    // order, not in the order the pixels turn up, so the same colors always get the same
+    // characters (even if the image is cropped).
+    let mut distinct = BTreeSet::<[u8; 4]>::new();
+    let mut last_pixel = None;
+    for &pixel in rgba.pixels() {
+        if last_pixel != Some(pixel) {
+            distinct.insert(pixel.0);
+            last_pixel = Some(pixel);
+        }
+    }
+    let source_idx = |[r, g, b, a]: [u8; 4]| {
+        (a == 255)
+            .then(|| source_palette.iter().position(|rgb| *rgb == (r, g, b)))
+            .flatten()
+            .unwrap_or(usize::MAX)
+    };
+    let mut distinct: Vec<[u8; 4]> = distinct.into_iter().collect();
+    distinct.sort_by_key(|channels| source_idx(*channels)); // Stable, so ties stay in rgb order.
+    for channels in distinct {
+        let pixel = Rgba(channels);
+        if palette.contains_key(&pixel) {
+            continue;
+        }
+        let [r, g, b, _] = channels;
+        let this_color = Color(next_color_idx);
 
-                next_char = (next_char as u8).wrapping_add(1) as char;
+        // Don't crash for too many colors, but the quality check should complain:
+        next_color_idx = next_color_idx.wrapping_add(1);
 
-                ColorInfo {
-                    ch: this_char,
-                    name: format!("{}{}", this_char, format!("{:02X}{:02X}{:02X}", r, g, b)),
-                    rgb: (r, g, b),
-                    color: this_color,
-                    corner: None,
-                }
-            });
+        let color_info = if r == 0 && g == 0 && b == 0 {
+            ColorInfo::default_fg(this_color)
+        } else {
+            let this_char = next_char;
+            next_char = (next_char as u8).wrapping_add(1) as char;
+            ColorInfo {
+                ch: this_char,
+                name: format!("{}{}", this_char, format!("{:02X}{:02X}{:02X}", r, g, b)),
+                rgb: (r, g, b),
+                color: this_color,
+                corner: None,
+            }
+        };
+        palette.insert(pixel, color_info);
+    }
 
-            grid[x as usize][y as usize] = color.color;
-        }
+    let mut last: Option<(Rgba<u8>, Color)> = None;
+    for (x, y, &pixel) in rgba.enumerate_pixels() {
+        let color = match last.filter(|(last_pixel, _)| *last_pixel == pixel) {
+            Some((_, last_color)) => last_color,
+            None => palette[&pixel].color,
+        };
+        grid[x as usize][y as usize] = color;
+        last = Some((pixel, color));
     }
 
     Solution {
@@ -123,22 +263,239 @@ pub fn image_to_solution(image: &DynamicImage) -> Solution {
     }
 }
 
+/// Like `load_image`, but reads the image at 2x2 pixels per cell (see `image_to_triano_solution`).
+pub fn load_image_path_as_triano(path: &PathBuf) -> anyhow::Result<Document> {
+    let img = image::ImageReader::open(path)
+        .with_context(|| format!("unable to read {path:?}"))?
+        .with_guessed_format()?
+        .decode()
+        .with_context(|| format!("unable to read {path:?} as an image"))?;
+    let solution = image_to_triano_solution(&img)?;
+    Ok(Document::from_solution(
+        solution,
+        path.to_string_lossy().to_string(),
+    ))
+}
+
+/// Which triangle a 2x2 block of pixels depicts, if any: the pixel in its corner is colored, the
+/// opposite one is background, and the other two match each other.
+fn block_corner([tl, tr, bl, br]: [Color; 4]) -> Option<(Color, Corner)> {
+    let corner = |upper, left| Corner { upper, left };
+    [
+        (corner(true, true), tl, br, (tr, bl)),
+        (corner(true, false), tr, bl, (tl, br)),
+        (corner(false, true), bl, tr, (tl, br)),
+        (corner(false, false), br, tl, (tr, bl)),
+    ]
+    .into_iter()
+    .find(|&(_, near, far, (side_a, side_b))| {
+        near != BACKGROUND
+            && far == BACKGROUND
+            && side_a == side_b
+            && (side_a == near || side_a == BACKGROUND)
+    })
+    .map(|(corner, near, _, _)| (near, corner))
+}
+
+/// Reads a Trianogram drawn at 2x: each cell is a 2x2 block of pixels, which is either a solid
+/// color or a diagonal split between a color and the (white) background.
+pub fn image_to_triano_solution(image: &DynamicImage) -> anyhow::Result<Solution> {
+    let (width, height) = image.dimensions();
+    if width % 2 != 0 || height % 2 != 0 {
+        bail!(
+            "triangles need two pixels per cell in each direction, but the image is {width}x{height}"
+        );
+    }
+    let pixels = image_to_solution(image);
+
+    let mut palette = HashMap::from([(BACKGROUND, pixels.palette[&BACKGROUND].clone())]);
+    let mut triangles = HashMap::<(Color, Corner), Color>::new();
+    // Solid colors keep their indices, so triangles get new ones after them.
+    let mut next_color_idx = pixels.palette.len();
+
+    let (x_size, y_size) = (width as usize / 2, height as usize / 2);
+    let mut grid = vec![vec![BACKGROUND; y_size]; x_size];
+    for x in 0..x_size {
+        for y in 0..y_size {
+            let at = |dx: usize, dy: usize| pixels.grid[2 * x + dx][2 * y + dy];
+            let block = [at(0, 0), at(1, 0), at(0, 1), at(1, 1)];
+
+            grid[x][y] = if block.iter().all(|c| *c == block[0]) {
+                palette
+                    .entry(block[0])
+                    .or_insert_with(|| pixels.palette[&block[0]].clone());
+                block[0]
+            } else if let Some((color, corner)) = block_corner(block) {
+                if let Some(existing) = triangles.get(&(color, corner)) {
+                    *existing
+                } else {
+                    // (`UNSOLVED` is the one index that isn't available.)
+                    let this_color = u8::try_from(next_color_idx)
+                        .ok()
+                        .map(Color)
+                        .filter(|color| *color != UNSOLVED)
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("too many colors (counting each triangle orientation)")
+                        })?;
+                    next_color_idx += 1;
+
+                    let base = &pixels.palette[&color];
+                    let used: HashSet<char> = palette.values().map(|ci| ci.ch).collect();
+                    let ch = corner
+                        .chars()
+                        .into_iter()
+                        .chain('A'..='Z')
+                        .find(|ch| !used.contains(ch))
+                        .ok_or_else(|| {
+                            anyhow::anyhow!("ran out of characters for the triangle colors")
+                        })?;
+                    palette.insert(
+                        this_color,
+                        ColorInfo {
+                            ch,
+                            name: format!("{} {ch}", base.name),
+                            rgb: base.rgb,
+                            color: this_color,
+                            corner: Some(corner),
+                        },
+                    );
+                    triangles.insert((color, corner), this_color);
+                    this_color
+                }
+            } else {
+                bail!(
+                    "the pixels for cell ({x}, {y}) are neither one color nor split along a diagonal"
+                );
+            };
+        }
+    }
+
+    Ok(Solution {
+        clue_style: ClueStyle::Triano,
+        palette,
+        grid,
+    })
+}
+
+/// Reads a partly-decided grid, drawn with the characters of `palette` (any of
+/// `puzzle::BACKGROUND_CHARS` also work for the background). `unknown_ch` marks undecided cells.
+pub fn char_grid_to_partial(
+    char_grid: &str,
+    palette: &HashMap<Color, ColorInfo>,
+    unknown_ch: char,
+) -> anyhow::Result<PartialSolution> {
+    let rows: Vec<Vec<char>> = char_grid
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.chars().collect())
+        .collect();
+    let width = rows.first().map_or(0, |row| row.len());
+
+    let mut partial = PartialSolution::from_elem((rows.len(), width), Cell::new_anything());
+    for (y, row) in rows.iter().enumerate() {
+        if row.len() != width {
+            bail!(
+                "line {} has {} cells, but the first has {width}",
+                y + 1,
+                row.len()
+            );
+        }
+        for (x, ch) in row.iter().enumerate() {
+            if *ch == unknown_ch {
+                continue;
+            }
+            let color = match palette.values().find(|ci| ci.ch == *ch) {
+                Some(color_info) => color_info.color,
+                None if puzzle::BACKGROUND_CHARS.contains(ch) => BACKGROUND,
+                None => bail!("line {}: {ch:?} isn't one of the puzzle's colors", y + 1),
+            };
+            partial[[y, x]] = Cell::from_color(color);
+        }
+    }
+    Ok(partial)
+}
+
+/// Characters in a chargrid that stand for their own color. (Using '🟥' and 'r' in the same
+/// puzzle, etc., will cause a warning.)
+const CHAR_GRID_NAMED_COLORS: [(char, (u8, u8, u8)); 13] = [
+    ('r', (255, 0, 0)),
+    ('g', (0, 255, 0)),
+    ('b', (0, 0, 255)),
+    ('y', (255, 255, 0)),
+    ('c', (0, 255, 255)),
+    ('m', (255, 0, 255)),
+    ('🟥', (255, 0, 0)),
+    ('🟩', (0, 255, 0)),
+    ('🟦', (0, 0, 255)),
+    ('🟨', (255, 255, 0)),
+    ('🟧', (255, 165, 0)),
+    ('🟪', (128, 0, 128)),
+    ('🟫', (139, 69, 19)),
+];
+
+/// The characters `char_grid_to_solution` understands without having to make up a color:
+/// backgrounds, blacks, triangles, and characters that name their own color.
+pub fn known_char_grid_chars() -> BTreeSet<char> {
+    let mut res = BTreeSet::new();
+    res.extend(puzzle::BACKGROUND_CHARS);
+    res.extend(puzzle::BLACK_CHARS);
+    res.extend(CHAR_GRID_NAMED_COLORS.iter().map(|(ch, _)| *ch));
+    for upper in [false, true] {
+        for left in [false, true] {
+            res.extend(Corner { upper, left }.chars());
+        }
+    }
+    res
+}
+
+/// Like `char_grid_to_solution`, but any character outside of `allowed` is an error, instead of
+/// silently becoming a new color.
+pub fn char_grid_to_solution_strict(
+    char_grid: &str,
+    allowed: &BTreeSet<char>,
+) -> anyhow::Result<Solution> {
+    let unexpected: BTreeSet<char> = char_grid
+        .lines()
+        .flat_map(str::chars)
+        .filter(|ch| !allowed.contains(ch))
+        .collect();
+    if !unexpected.is_empty() {
+        let listed: Vec<String> = unexpected.iter().map(|ch| format!("{ch:?}")).collect();
+        bail!("unexpected characters: {}", listed.join(", "));
+    }
+    Ok(char_grid_to_solution(char_grid))
+}
+
+/// Reads a chargrid (from stdin, if `path` is "-") with `char_grid_to_solution_strict`.
+pub fn load_path_as_strict_char_grid(
+    path: &PathBuf,
+    allowed: &BTreeSet<char>,
+) -> anyhow::Result<Document> {
+    let text = if path == &PathBuf::from("-") {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text)?;
+        text
+    } else {
+        std::fs::read_to_string(path).with_context(|| format!("unable to read {path:?}"))?
+    };
+    let solution = char_grid_to_solution_strict(&text, allowed)
+        .with_context(|| format!("{path:?} isn't a strict chargrid"))?;
+    Ok(Document::from_solution(
+        solution,
+        path.to_string_lossy().to_string(),
+    ))
+}
+
 pub fn char_grid_to_solution(char_grid: &str) -> Solution {
     let mut palette = HashMap::<char, ColorInfo>::new();
 
     // We want deterministic behavior
-    let mut unused_chars = BTreeSet::<char>::new();
-    for ch in char_grid.chars() {
-        if ch == '\n' {
-            continue;
-        }
-        unused_chars.insert(ch);
-    }
+    let mut unused_chars: BTreeSet<char> = char_grid.lines().flat_map(str::chars).collect();
 
     let mut bg_ch: Option<char> = None;
 
     // Look for a character that seems to represent a white background.
-    for possible_bg in [' ', '.', '_', 'w', 'W', '·', '☐', '0', '⬜'] {
+    for possible_bg in puzzle::BACKGROUND_CHARS {
         if unused_chars.contains(&possible_bg) {
             bg_ch = Some(possible_bg);
         }
@@ -151,7 +508,7 @@ pub fn char_grid_to_solution(char_grid: &str) -> Solution {
             eprintln!(
                 "number-loom: Warning: unable to guess which character is supposed to be the background; using the upper-left corner"
             );
-            char_grid.trim_start().chars().next().unwrap()
+            char_grid.trim_start().chars().next().unwrap_or(' ')
         }
     };
 
@@ -167,7 +524,7 @@ pub fn char_grid_to_solution(char_grid: &str) -> Solution {
     let mut next_color: u8 = 1;
 
     // Look for a character that might be black (but it's not required to exist).
-    for possible_black in ['#', 'B', 'b', '.', '■', '█', '1', '⬛'] {
+    for possible_black in puzzle::BLACK_CHARS {
         if unused_chars.contains(&possible_black) {
             palette.insert(possible_black, ColorInfo::default_fg(Color(next_color)));
             next_color += 1;
@@ -176,10 +533,11 @@ pub fn char_grid_to_solution(char_grid: &str) -> Solution {
         }
     }
 
-    let lower_right_tri = HashSet::<char>::from_iter(['◢', '🮞', '◿']);
-    let lower_left_tri = HashSet::<char>::from_iter(['◣', '🮟', '◺']);
-    let upper_left_tri = HashSet::<char>::from_iter(['◤', '🮜', '◸']);
-    let upper_right_tri = HashSet::<char>::from_iter(['◥', '🮝', '◹']);
+    let tri = |upper, left| HashSet::<char>::from_iter(Corner { upper, left }.chars());
+    let lower_right_tri = tri(false, false);
+    let lower_left_tri = tri(false, true);
+    let upper_left_tri = tri(true, true);
+    let upper_right_tri = tri(true, false);
     let mut any_tri = HashSet::<char>::new();
     any_tri.extend(lower_right_tri.iter());
     any_tri.extend(lower_left_tri.iter());
@@ -187,24 +545,10 @@ pub fn char_grid_to_solution(char_grid: &str) -> Solution {
     any_tri.extend(upper_right_tri.iter());
 
     // By default, use primary and secondary colors:
-    let mut unused_colors = BTreeMap::<char, (u8, u8, u8)>::new();
-    unused_colors.insert('r', (255, 0, 0));
-    unused_colors.insert('g', (0, 255, 0));
-    unused_colors.insert('b', (0, 0, 255));
-
-    unused_colors.insert('y', (255, 255, 0));
-    unused_colors.insert('c', (0, 255, 255));
-    unused_colors.insert('m', (255, 0, 255));
-
-    // Using '🟥' and 'r' in the same puzzle (etc.) will cause a warning.
-    unused_colors.insert('🟥', (255, 0, 0));
-    unused_colors.insert('🟩', (0, 255, 0));
-    unused_colors.insert('🟦', (0, 0, 255));
-    unused_colors.insert('🟨', (255, 255, 0));
-    unused_colors.insert('🟧', (255, 165, 0));
-    unused_colors.insert('🟪', (128, 0, 128));
-    unused_colors.insert('🟫', (139, 69, 19));
+    let mut unused_colors = BTreeMap::<char, (u8, u8, u8)>::from(CHAR_GRID_NAMED_COLORS);
 
+    // Characters that name their own color, like 'r' or '🟥'.
+    let mut named_chars = HashSet::<char>::new();
     for ch in unused_chars {
         if unused_colors.is_empty() {
             // If desperate, use grays and dark colors:
@@ -219,9 +563,13 @@ pub fn char_grid_to_solution(char_grid: &str) -> Solution {
             unused_colors.insert('C', (0, 127, 127));
             unused_colors.insert('M', (127, 0, 127));
         }
-        let rgb = unused_colors
-            .remove(&ch)
-            .unwrap_or_else(|| unused_colors.pop_first().unwrap().1);
+        let rgb = match unused_colors.remove(&ch) {
+            Some(rgb) => {
+                named_chars.insert(ch);
+                rgb
+            }
+            None => unused_colors.pop_first().unwrap().1,
+        };
 
         palette.insert(
             ch,
@@ -247,7 +595,7 @@ pub fn char_grid_to_solution(char_grid: &str) -> Solution {
 
     // TODO: check that rows are the same length!
     for (y, row) in char_grid
-        .split("\n")
+        .lines()
         .filter(|line| !line.is_empty())
         .enumerate()
     {
@@ -264,9 +612,10 @@ pub fn char_grid_to_solution(char_grid: &str) -> Solution {
     let has_triangles = palette.values().any(|ci| ci.corner.is_some());
 
     let clue_style = if has_triangles {
-        // Let's assume triano clues are black-and-white; fix the palette!
-        for (_, color_info) in &mut palette {
-            if color_info.color == BACKGROUND {
+        // A chargrid can't say what color a triangle is, so assume that anything that doesn't
+        // name its own color is black.
+        for (ch, color_info) in &mut palette {
+            if color_info.color == BACKGROUND || named_chars.contains(ch) {
                 continue;
             }
             color_info.rgb = (0, 0, 0);
@@ -301,7 +650,114 @@ enum Glue {
     Right,
 }
 
+/// Reads clues typed in by hand (or written by `as_clue_text`). The rows come first, then a
+/// separator (a blank line, a line of dashes, or "Columns"), then the columns. Each line may have
+/// a label like "Row 1:", and lists counts (`3 1 2`; `0` for an empty line). For colored puzzles,
+/// each count is followed by a color name (`3 red, 1 blue`); counts without one are black.
+pub fn clue_text_to_puzzle(text: &str) -> anyhow::Result<Puzzle<Nono>> {
+    let named_colors = BTreeMap::<&str, (u8, u8, u8)>::from([
+        ("black", (0, 0, 0)),
+        ("red", (255, 0, 0)),
+        ("green", (0, 255, 0)),
+        ("blue", (0, 0, 255)),
+        ("pink", (255, 128, 128)),
+        ("yellow", (255, 255, 0)),
+        ("orange", (255, 165, 0)),
+        ("brown", (139, 69, 19)),
+        ("gray", (128, 128, 128)),
+    ]);
+
+    let mut palette = HashMap::from([(BACKGROUND, ColorInfo::default_bg())]);
+    let mut color_for_name = |name: &str| -> Color {
+        if let Some(ci) = palette.values().find(|ci| ci.name == name) {
+            return ci.color;
+        }
+        let color = Color(palette.len() as u8);
+        let used: HashSet<char> = palette.values().map(|ci| ci.ch).collect();
+        let ch = name
+            .chars()
+            .chain('a'..='z')
+            .find(|c| c.is_alphanumeric() && !used.contains(c))
+            .unwrap_or('?');
+        palette.insert(
+            color,
+            ColorInfo {
+                ch,
+                name: name.to_string(),
+                rgb: named_colors.get(name).copied().unwrap_or((128, 128, 128)),
+                color,
+                corner: None,
+            },
+        );
+        color
+    };
+
+    let mut sections: Vec<Vec<Vec<Nono>>> = vec![vec![], vec![]];
+    let mut cur_section = 0;
+    for line in text.lines() {
+        let line = line.trim();
+        let lowercase = line.trim_end_matches(':').to_lowercase();
+        let is_separator = line.is_empty()
+            || (line.chars().all(|c| c == '-') && line.len() > 1)
+            || lowercase == "columns"
+            || lowercase == "cols";
+        if is_separator {
+            if !sections[0].is_empty() {
+                cur_section = 1;
+            }
+            continue;
+        }
+        if lowercase == "rows" {
+            continue;
+        }
+
+        let clue_str = line.split_once(':').map_or(line, |(_, rest)| rest).trim();
+        let mut clues = vec![];
+        let mut words = clue_str
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty())
+            .peekable();
+        while let Some(count) = words.next() {
+            let count: u16 = count
+                .parse()
+                .map_err(|_| anyhow::anyhow!("unexpected clue {count:?} in line {line:?}"))?;
+            let mut name = vec![];
+            while let Some(word) = words.next_if(|word| word.parse::<u16>().is_err()) {
+                name.push(word);
+            }
+            let name = if name.is_empty() {
+                "black".to_string()
+            } else {
+                name.join(" ")
+            };
+            if count > 0 {
+                clues.push(Nono {
+                    color: color_for_name(&name),
+                    count,
+                });
+            }
+        }
+        sections[cur_section].push(clues);
+    }
+
+    if sections[1].is_empty() {
+        bail!("no columns found (separate them from the rows with a blank line)");
+    }
+    let [rows, cols]: [Vec<Vec<Nono>>; 2] = sections.try_into().unwrap();
+    Ok(Puzzle {
+        palette,
+        rows,
+        cols,
+    })
+}
+
 pub fn olsak_to_puzzle(olsak: &str) -> anyhow::Result<DynPuzzle> {
+    Ok(olsak_to_puzzle_and_goal(olsak)?.0)
+}
+
+/// Like `olsak_to_puzzle`, but also returns the goal (the intended solution), if the file has a
+/// third `:` section holding a grid of color characters.
+pub fn olsak_to_puzzle_and_goal(olsak: &str) -> anyhow::Result<(DynPuzzle, Option<Solution>)> {
     use Glue::*;
     use OlsakStanza::*;
     let mut cur_stanza = Preamble;
@@ -332,9 +788,14 @@ pub fn olsak_to_puzzle(olsak: &str) -> anyhow::Result<DynPuzzle> {
     // Dimension > Position > Clue index
     let mut nono_clues: Vec<Vec<Vec<Nono>>> = vec![vec![], vec![]];
     let mut triano_clues: Vec<Vec<Vec<Triano>>> = vec![vec![], vec![]];
+    let mut goal_lines: Vec<&str> = vec![];
 
     for line in olsak.lines() {
-        if let Some(palette_ch) = line.strip_prefix("#") {
+        if cur_stanza == Dimension(2) && !line.starts_with(":") {
+            // Either the goal, or just comments after the end. (The goal may use '#'!) Rows are
+            // kept verbatim, because the background is usually written as a space.
+            goal_lines.push(line);
+        } else if let Some(palette_ch) = line.strip_prefix("#") {
             if cur_stanza != Preamble {
                 bail!("Palette initiator (line beginning with '#') must be the first content");
             }
@@ -348,6 +809,9 @@ pub fn olsak_to_puzzle(olsak: &str) -> anyhow::Result<DynPuzzle> {
             assert!(palette_ch.starts_with("d"));
             cur_stanza = Palette;
         } else if line.starts_with(":") {
+            if cur_stanza == Palette {
+                use_white_as_background(&mut olsak_palette);
+            }
             cur_stanza = Dimension(if let Dimension(n) = cur_stanza {
                 n + 1
             } else {
@@ -369,58 +833,79 @@ pub fn olsak_to_puzzle(olsak: &str) -> anyhow::Result<DynPuzzle> {
                 _ => NoGlue,
             };
 
+            let parse_rgb = |name: &str| {
+                if let Some((_, [rs, gs, bs])) = regex::Regex::new(r"^#(..)(..)(..)$")
+                    .unwrap()
+                    .captures(name)
+                    .map(|c| c.extract())
+                {
+                    Some((
+                        u8::from_str_radix(rs, 16).ok()?,
+                        u8::from_str_radix(gs, 16).ok()?,
+                        u8::from_str_radix(bs, 16).ok()?,
+                    ))
+                } else {
+                    named_colors.get(name).copied()
+                }
+            };
+
             let rising = color_name.contains('/');
 
-            let (corner, unique_ch) = match (color_name.split_once(&['/', '\\']), rising) {
-                (None, _) => (None, unique_ch.chars().next().unwrap()),
-                (Some(("white", "black")), true) => (
-                    Some(Corner {
-                        upper: false,
-                        left: false,
-                    }),
-                    '◢',
-                ),
-                (Some(("white", "black")), false) => (
-                    Some(Corner {
-                        upper: true,
-                        left: false,
-                    }),
-                    '◥',
-                ),
-                (Some(("black", "white")), true) => (
-                    Some(Corner {
-                        upper: true,
-                        left: true,
-                    }),
-                    '◤',
-                ),
-                (Some(("black", "white")), false) => (
-                    Some(Corner {
-                        upper: false,
-                        left: true,
-                    }),
-                    '◣',
-                ),
-                (Some((_, _)), _) => {
-                    eprintln!("Unsupported triangle color combination: {color_name}");
-                    (None, unique_ch.chars().next().unwrap())
+            // A triangle is written as its left color, a slash, and its right color. One side is
+            // the background, and the other is the color of the triangle.
+            let bg_rgb = olsak_palette.get(&'0').map_or((255, 255, 255), |ci| ci.rgb);
+            // Older files say "white" even when the background is some other color.
+            let is_empty_half = |rgb: (u8, u8, u8)| rgb == bg_rgb || rgb == (255, 255, 255);
+            let triangle = match color_name.split_once(&['/', '\\']) {
+                None => None,
+                Some((left_name, right_name)) => {
+                    match (parse_rgb(left_name), parse_rgb(right_name)) {
+                        (Some(left_rgb), Some(right_rgb)) if is_empty_half(right_rgb) => Some((
+                            Corner {
+                                upper: rising,
+                                left: true,
+                            },
+                            left_rgb,
+                        )),
+                        (Some(left_rgb), Some(right_rgb)) if is_empty_half(left_rgb) => Some((
+                            Corner {
+                                upper: !rising,
+                                left: false,
+                            },
+                            right_rgb,
+                        )),
+                        // A cell holds one color, so a triangle can't have two (non-background)
+                        // halves.
+                        _ => bail!(
+                            "{color_name:?} isn't a triangle of one color on the background, \
+                            which is the only kind that's supported"
+                        ),
+                    }
                 }
             };
+            let corner = triangle.map(|(corner, _)| corner);
+
+            let unique_ch = match corner {
+                // Prefer the usual glyphs, but colored caps can need more than one per corner.
+                Some(corner) => {
+                    let used: HashSet<char> = olsak_palette
+                        .values()
+                        .chain(olsak_glued_palettes[0].values())
+                        .map(|ci| ci.ch)
+                        .collect();
+                    corner
+                        .chars()
+                        .into_iter()
+                        .find(|ch| !used.contains(ch))
+                        .unwrap_or_else(|| unique_ch.chars().next().unwrap())
+                }
+                None => unique_ch.chars().next().unwrap(),
+            };
 
-            let rgb = if let Some((_, [rs, gs, bs])) = regex::Regex::new(r"^#(..)(..)(..)$")
-                .unwrap()
-                .captures(color_name)
-                .map(|c| c.extract())
-            {
-                (
-                    u8::from_str_radix(rs, 16).unwrap(),
-                    u8::from_str_radix(gs, 16).unwrap(),
-                    u8::from_str_radix(bs, 16).unwrap(),
-                )
-            } else if corner.is_some() {
-                (0, 0, 0) // Assumes Triano puzzles are black-and-white!
-            } else if let Some((r, g, b)) = named_colors.get(color_name) {
-                (*r, *g, *b)
+            let rgb = if let Some((_, rgb)) = triangle {
+                rgb
+            } else if let Some(rgb) = parse_rgb(color_name) {
+                rgb
             } else if let Some((r, g, b)) = named_colors.get(input_ch) {
                 (*r, *g, *b)
             } else {
@@ -460,7 +945,8 @@ pub fn olsak_to_puzzle(olsak: &str) -> anyhow::Result<DynPuzzle> {
 
             next_color += 1;
         } else if let Dimension(d) = cur_stanza {
-            if !olsak_palette.contains_key(&'1') {
+            // Without a palette, plain numbers are black. (With one, they have to use its `1`.)
+            if !olsak_palette.values().any(|ci| ci.color != BACKGROUND) {
                 olsak_palette.insert(
                     '1',
                     ColorInfo {
@@ -484,17 +970,23 @@ pub fn olsak_to_puzzle(olsak: &str) -> anyhow::Result<DynPuzzle> {
                     for clue_str in clue_strs {
                         if let Ok(count) = clue_str.parse::<u16>() {
                             clues.push(Nono {
-                                color: olsak_palette[&'1'].color,
+                                color: default_olsak_color(&olsak_palette, clue_str)?,
                                 count,
                             })
                         } else {
-                            let count: u8 = clue_str
+                            let count: u16 = clue_str
                                 .trim_end_matches(|c: char| !c.is_numeric())
-                                .parse()?;
+                                .parse()
+                                .with_context(|| format!("expected a count, not {clue_str:?}"))?;
                             let input_ch = clue_str.chars().last().unwrap();
+                            let color_info = olsak_palette.get(&input_ch).with_context(|| {
+                                format!(
+                                    "{clue_str:?} uses {input_ch:?}, which isn't in the palette"
+                                )
+                            })?;
                             clues.push(Nono {
-                                color: olsak_palette[&input_ch].color,
-                                count: count as u16,
+                                color: color_info.color,
+                                count,
                             })
                         }
                     }
@@ -519,14 +1011,31 @@ pub fn olsak_to_puzzle(olsak: &str) -> anyhow::Result<DynPuzzle> {
                         if back_cap.is_some() {
                             chars.pop();
                         }
-                        let body_color = if !chars.last().unwrap().is_numeric() {
-                            olsak_palette[&chars.pop().unwrap()].color
-                        } else {
-                            olsak_palette[&'1'].color
+                        let body_color = match chars.last() {
+                            Some(ch) if !ch.is_numeric() => {
+                                let ch = *ch;
+                                chars.pop();
+                                olsak_palette
+                                    .get(&ch)
+                                    .with_context(|| {
+                                        format!(
+                                            "{clue_str:?} uses {ch:?}, which isn't in the palette"
+                                        )
+                                    })?
+                                    .color
+                            }
+                            _ => default_olsak_color(&olsak_palette, clue_str)?,
                         };
 
-                        let body_len = chars.iter().collect::<String>().parse::<u16>()?
-                            - (front_cap.is_some() as u16 + back_cap.is_some() as u16);
+                        let body_len = chars
+                            .iter()
+                            .collect::<String>()
+                            .parse::<u16>()
+                            .with_context(|| format!("expected a count in {clue_str:?}"))?
+                            .checked_sub(front_cap.is_some() as u16 + back_cap.is_some() as u16)
+                            .with_context(|| {
+                                format!("{clue_str:?} is too short to hold its caps")
+                            })?;
 
                         clues.push(Triano {
                             front_cap,
@@ -540,10 +1049,55 @@ pub fn olsak_to_puzzle(olsak: &str) -> anyhow::Result<DynPuzzle> {
             }
         }
     }
-    if !olsak_palette.contains_key(&'0') {
+    if !olsak_palette.values().any(|ci| ci.color == BACKGROUND) {
         olsak_palette.insert('0', ColorInfo::default_bg());
     }
 
+    // Goal cells may be written with either the input character or the unique one:
+    let mut goal_chars = HashMap::<char, Color>::new();
+    for (input_ch, ci) in olsak_palette.iter() {
+        goal_chars.insert(*input_ch, ci.color);
+        goal_chars.insert(ci.ch, ci.color);
+    }
+    for ((input_ch, _), ci) in olsak_glued_palettes.iter().flatten() {
+        goal_chars.insert(*input_ch, ci.color);
+        goal_chars.insert(ci.ch, ci.color);
+    }
+    let (num_rows, num_cols) = match clue_style {
+        ClueStyle::Nono => (nono_clues[0].len(), nono_clues[1].len()),
+        ClueStyle::Triano => (triano_clues[0].len(), triano_clues[1].len()),
+    };
+    // The goal ends at the first line that isn't made of color characters. Editors often strip
+    // trailing spaces, so a short row is padded out with the background, if that's a space.
+    let bg_is_space = goal_chars.get(&' ') == Some(&BACKGROUND);
+    let goal_rows: Vec<Vec<Color>> = goal_lines
+        .iter()
+        .map_while(|line| {
+            line.chars()
+                .map(|ch| goal_chars.get(&ch).copied())
+                .collect::<Option<Vec<Color>>>()
+        })
+        .take(num_rows)
+        .map(|mut row| {
+            if bg_is_space && row.len() < num_cols {
+                row.resize(num_cols, BACKGROUND);
+            }
+            row
+        })
+        .collect();
+    let goal_grid = (num_rows > 0
+        && goal_rows.len() == num_rows
+        && goal_rows.iter().all(|row| row.len() == num_cols))
+    .then(|| {
+        let mut grid = vec![vec![BACKGROUND; num_rows]; num_cols];
+        for (y, row) in goal_rows.into_iter().enumerate() {
+            for (x, color) in row.into_iter().enumerate() {
+                grid[x][y] = color;
+            }
+        }
+        grid
+    });
+
     let mut palette: HashMap<Color, ColorInfo> = olsak_palette
         .into_values()
         .map(|ci| (ci.color, ci))
@@ -554,7 +1108,13 @@ pub fn olsak_to_puzzle(olsak: &str) -> anyhow::Result<DynPuzzle> {
         }
     }
 
-    Ok(match clue_style {
+    let goal = goal_grid.map(|grid| Solution {
+        clue_style,
+        palette: palette.clone(),
+        grid,
+    });
+
+    let puzzle = match clue_style {
         ClueStyle::Nono => DynPuzzle::Nono(Puzzle::<Nono> {
             palette,
             rows: nono_clues[0].clone(),
@@ -565,12 +1125,51 @@ pub fn olsak_to_puzzle(olsak: &str) -> anyhow::Result<DynPuzzle> {
             rows: triano_clues[0].clone(),
             cols: triano_clues[1].clone(),
         }),
+    };
+    if num_rows > 0 && num_cols > 0 {
+        // The file doesn't say which separation rule it's for, so only reject clues that can't
+        // fit under any of them.
+        let rule = SeparationRule::Never;
+        puzzle.specialize(|p| p.check_lines_fit(rule), |p| p.check_lines_fit(rule))?;
+    }
+    Ok((puzzle, goal))
+}
+
+/// The color of a clue with no color character, which is the palette's `1`.
+fn default_olsak_color(
+    olsak_palette: &HashMap<char, ColorInfo>,
+    clue_str: &str,
+) -> anyhow::Result<Color> {
+    olsak_palette.get(&'1').map(|ci| ci.color).ok_or_else(|| {
+        anyhow::anyhow!("{clue_str:?} has no color, but the palette doesn't define a default `1`")
     })
 }
 
+/// Olšák files conventionally declare the background as color `0`, but not all of them do (and
+/// some list it after other colors). If there's a white entry, it's the background.
+fn use_white_as_background(olsak_palette: &mut HashMap<char, ColorInfo>) {
+    let Some(white_color) = olsak_palette
+        .values()
+        .find(|ci| ci.rgb == (255, 255, 255) && ci.corner.is_none())
+        .map(|ci| ci.color)
+    else {
+        return;
+    };
+    if white_color == BACKGROUND {
+        return;
+    }
+    for ci in olsak_palette.values_mut() {
+        if ci.color == white_color {
+            ci.color = BACKGROUND;
+        } else if ci.color == BACKGROUND {
+            ci.color = white_color;
+        }
+    }
+}
+
 pub fn solution_to_triano_puzzle(solution: &Solution) -> Puzzle<Triano> {
-    let width = solution.grid.len();
-    let height = solution.grid.first().unwrap().len();
+    let width = solution.x_size();
+    let height = solution.y_size();
 
     let mut rows: Vec<Vec<Triano>> = Vec::new();
     let mut cols: Vec<Vec<Triano>> = Vec::new();
@@ -681,8 +1280,8 @@ pub fn solution_to_triano_puzzle(solution: &Solution) -> Puzzle<Triano> {
 }
 
 pub fn solution_to_puzzle(solution: &Solution) -> Puzzle<Nono> {
-    let width = solution.grid.len();
-    let height = solution.grid.first().unwrap().len();
+    let width = solution.x_size();
+    let height = solution.y_size();
 
     let mut rows: Vec<Vec<Nono>> = Vec::new();
     let mut cols: Vec<Vec<Nono>> = Vec::new();
@@ -757,62 +1356,232 @@ pub fn bw_palette() -> HashMap<Color, ColorInfo> {
 
 // It's impossible to get released assests from GitHub for CORS reasons (!?), so
 // we grab the raw files:
+pub const GITHUB_PUZZLES_URL: &str =
+    "https://api.github.com/repos/paulstansifer/number-loom/contents/puzzles?ref=main";
+
 pub async fn puzzles_from_github() -> anyhow::Result<Vec<Document>> {
-    let client = reqwest::Client::new();
+    puzzles_from_url(GITHUB_PUZZLES_URL).await
+}
+
+/// `url` can be a GitHub contents API URL (which produces a JSON listing), an HTML directory
+/// listing, or a plain-text index with one filename per line. Relative names are resolved against
+/// `url`, so directory URLs should end in '/'.
+pub async fn puzzles_from_url(url: &str) -> anyhow::Result<Vec<Document>> {
+    let mut res: Vec<Document> = vec![];
+    stream_puzzles_from_url(url, |document| res.push(document)).await?;
+    Ok(res)
+}
 
-    let puzzles_url =
-        "https://api.github.com/repos/paulstansifer/number-loom/contents/puzzles?ref=main";
+/// Like `puzzles_from_url`, but hands each puzzle to `sink` as soon as it's downloaded, so a
+/// slow connection can show the first ones early.
+pub async fn stream_puzzles_from_url(
+    url: &str,
+    mut sink: impl FnMut(Document),
+) -> anyhow::Result<()> {
+    let client = reqwest::Client::new();
 
     let contents = client
-        .get(puzzles_url)
+        .get(url)
         .header("User-Agent", "number-loom")
         .send()
         .await?
         .bytes()
         .await?;
 
-    let files: Vec<serde_json::Value> = serde_json::from_slice(&contents)?;
-
-    let mut res: Vec<Document> = vec![];
+    for (name, download_url) in listing_entries(url, &contents)? {
+        let content = client.get(&download_url).send().await?.bytes().await?;
 
-    for file in files {
-        if file["type"] == "file" {
-            let name = file["name"].as_str().unwrap();
-            let download_url = file["download_url"].as_str().unwrap();
+        sink(load(&name, content.to_vec(), None)?);
+    }
 
-            let content = client.get(download_url).send().await?.bytes().await?;
+    Ok(())
+}
 
-            res.push(load(name, content.to_vec(), None));
+/// Returns (filename, download URL) pairs.
+fn listing_entries(url: &str, listing: &[u8]) -> anyhow::Result<Vec<(String, String)>> {
+    if let Ok(files) = serde_json::from_slice::<Vec<serde_json::Value>>(listing) {
+        let mut res = vec![];
+        for file in files {
+            // Skip directories, and anything too malformed to download:
+            if file["type"] != "file" {
+                continue;
+            }
+            let (Some(name), Some(download_url)) =
+                (file["name"].as_str(), file["download_url"].as_str())
+            else {
+                continue;
+            };
+            res.push((name.to_string(), download_url.to_string()));
         }
+        return Ok(res);
     }
 
-    Ok(res)
+    let listing = std::str::from_utf8(listing)?;
+
+    let names: Vec<&str> = if listing.contains("href=") {
+        regex::Regex::new(r#"href="([^"?#]+)""#)
+            .unwrap()
+            .captures_iter(listing)
+            .map(|c| c.extract::<1>().1[0])
+            .collect()
+    } else {
+        listing
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .collect()
+    };
+
+    let dir = &url[..url.rfind('/').map_or(0, |idx| idx + 1)];
+    let origin = match url.find("://") {
+        Some(scheme_end) => match url[scheme_end + 3..].find('/') {
+            Some(idx) => &url[..scheme_end + 3 + idx],
+            None => url,
+        },
+        None => "",
+    };
+
+    Ok(names
+        .into_iter()
+        // Skips subdirectories, parent links, and the index page itself:
+        .filter(|name| is_loadable(name))
+        .map(|name| {
+            let download_url = if name.contains("://") {
+                name.to_string()
+            } else if name.starts_with('/') {
+                format!("{origin}{name}")
+            } else {
+                format!("{dir}{}", name.trim_start_matches("./"))
+            };
+            (name.rsplit('/').next().unwrap().to_string(), download_url)
+        })
+        .collect())
 }
 
-pub async fn load_zip_from_url(url: &str) -> anyhow::Result<Vec<Document>> {
+/// Drops documents with the same picture as an earlier one. Returns how many were dropped.
+pub fn dedup_documents(documents: &mut Vec<Document>) -> usize {
+    let before = documents.len();
+    let mut seen = std::collections::HashSet::new();
+    documents.retain(|doc| seen.insert(doc.content_hash()));
+    before - documents.len()
+}
+
+pub async fn load_zip_from_url(url: &str) -> anyhow::Result<Vec<anyhow::Result<Document>>> {
     let response = reqwest::get(url).await?;
     let zip_bytes = response.bytes().await?;
-    let zip_cursor = Cursor::new(zip_bytes);
 
-    let mut archive = zip::ZipArchive::new(zip_cursor)?;
+    load_zip(zip_bytes.to_vec())
+}
+
+/// Loads every puzzle in the archive, ignoring its directory structure. A file that fails to load
+/// doesn't stop the rest; its error takes its place in the result.
+pub fn load_zip(bytes: Vec<u8>) -> anyhow::Result<Vec<anyhow::Result<Document>>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
     let mut documents = vec![];
 
     for i in 0..archive.len() {
-        let mut file = archive.by_index(i)?;
-        let filename = file.name().to_string();
+        let mut file = match archive.by_index(i) {
+            Ok(file) => file,
+            Err(e) => {
+                documents.push(Err(anyhow::Error::from(e).context(format!("entry {i}"))));
+                continue;
+            }
+        };
 
         if file.is_dir() {
             continue;
         }
 
+        let filename = file.name().rsplit('/').next().unwrap().to_string();
+        // Skip hidden files (including macOS's "._" metadata files).
+        if filename.starts_with('.') {
+            continue;
+        }
+        if !is_loadable(&filename) {
+            eprintln!(
+                "number-loom: Warning: skipping unrecognized file {}",
+                file.name()
+            );
+            continue;
+        }
+
         let mut bytes = vec![];
-        file.read_to_end(&mut bytes)?;
-        documents.push(load(&filename, bytes, None));
+        let document = file
+            .read_to_end(&mut bytes)
+            .map_err(anyhow::Error::from)
+            .and_then(|_| load(&filename, bytes, None))
+            .with_context(|| format!("unable to load {}", file.name()));
+        documents.push(document);
     }
 
     Ok(documents)
 }
 
+/// Loads every puzzle in a directory (not its subdirectories), in filename order, or in a zip
+/// archive. Files that fail to load are skipped with a warning.
+pub fn load_library(path: &PathBuf) -> anyhow::Result<Vec<Document>> {
+    if !path.is_dir() {
+        let bytes = std::fs::read(path).with_context(|| format!("unable to read {path:?}"))?;
+        let documents =
+            load_zip(bytes).with_context(|| format!("{path:?} is not a directory or zip file"))?;
+        return Ok(documents
+            .into_iter()
+            .filter_map(|document| {
+                document
+                    .inspect_err(|e| eprintln!("number-loom: Warning: skipping {e:?}"))
+                    .ok()
+            })
+            .collect());
+    }
+
+    let mut paths = vec![];
+    for entry in std::fs::read_dir(path).with_context(|| format!("unable to read {path:?}"))? {
+        let entry_path = entry?.path();
+        let filename = entry_path.file_name().unwrap_or_default().to_string_lossy();
+        if !entry_path.is_file() || filename.starts_with('.') {
+            continue;
+        }
+        if !is_loadable(&filename) {
+            eprintln!(
+                "number-loom: Warning: skipping unrecognized file {}",
+                entry_path.display()
+            );
+            continue;
+        }
+        paths.push(entry_path);
+    }
+    paths.sort();
+
+    let mut documents = vec![];
+    for path in paths {
+        match load_path(&path, None) {
+            Ok(document) => documents.push(document),
+            Err(e) => eprintln!("number-loom: Warning: skipping {}: {e:?}", path.display()),
+        }
+    }
+    Ok(documents)
+}
+
+/// Whether `filename` has an extension that we know how to load.
+pub fn is_loadable(filename: &str) -> bool {
+    matches!(
+        filename.rsplit_once('.').map(|x| x.1),
+        Some(
+            "png"
+                | "bmp"
+                | "gif"
+                | "xml"
+                | "pbn"
+                | "g"
+                | "txt"
+                | "woven"
+                | "json"
+                | "clues"
+                | "nin"
+        )
+    )
+}
+
 pub fn triano_palette() -> HashMap<Color, ColorInfo> {
     let mut palette = HashMap::new();
     palette.insert(BACKGROUND, ColorInfo::default_bg());
@@ -873,3 +1642,478 @@ pub fn triano_palette() -> HashMap<Color, ColorInfo> {
 
     palette
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triano_image_blocks() {
+        let (white, red, black) = (
+            Rgba([255, 255, 255, 255]),
+            Rgba([255, 0, 0, 255]),
+            Rgba([0, 0, 0, 255]),
+        );
+        // A red ◢ drawn with a single pixel, a solid black cell, and a red ◤ drawn with three.
+        let mut img = image::RgbaImage::from_pixel(6, 2, white);
+        img.put_pixel(1, 1, red);
+        for (x, y) in [(2, 0), (3, 0), (2, 1), (3, 1)] {
+            img.put_pixel(x, y, black);
+        }
+        for (x, y) in [(4, 0), (5, 0), (4, 1)] {
+            img.put_pixel(x, y, red);
+        }
+
+        let solution = image_to_triano_solution(&DynamicImage::ImageRgba8(img.clone())).unwrap();
+        assert_eq!(solution.clue_style, ClueStyle::Triano);
+        assert_eq!((solution.x_size(), solution.y_size()), (3, 1));
+        let info = |x: usize| &solution.palette[&solution.grid[x][0]];
+        assert_eq!(
+            info(0).corner,
+            Some(Corner {
+                upper: false,
+                left: false
+            })
+        );
+        assert_eq!(info(0).rgb, (255, 0, 0));
+        assert_eq!((info(1).rgb, info(1).corner), ((0, 0, 0), None));
+        assert_eq!(
+            info(2).corner,
+            Some(Corner {
+                upper: true,
+                left: true
+            })
+        );
+        assert_eq!(info(2).rgb, (255, 0, 0));
+
+        // Half-and-half isn't a triangle:
+        img.put_pixel(5, 0, white);
+        img.put_pixel(5, 1, white);
+        img.put_pixel(4, 1, red);
+        assert!(image_to_triano_solution(&DynamicImage::ImageRgba8(img)).is_err());
+        let odd = image::RgbaImage::from_pixel(3, 2, white);
+        assert!(image_to_triano_solution(&DynamicImage::ImageRgba8(odd)).is_err());
+    }
+
+    #[test]
+    fn plain_listings() {
+        let index = "# My puzzles\nfoo.xml\n\nsub/bar.png\n";
+        assert_eq!(
+            listing_entries("https://example.com/pack/index.txt", index.as_bytes()).unwrap(),
+            vec![
+                (
+                    "foo.xml".to_string(),
+                    "https://example.com/pack/foo.xml".to_string()
+                ),
+                (
+                    "bar.png".to_string(),
+                    "https://example.com/pack/sub/bar.png".to_string()
+                ),
+            ]
+        );
+
+        let html = r#"<a href="../">up</a> <a href="?C=N;O=D">sort</a>
+            <a href="baz.g">baz.g</a> <a href="/other/qux.woven">qux</a>
+            <a href="index.html">self</a> <a href="nested/">nested</a>"#;
+        assert_eq!(
+            listing_entries("https://example.com/pack/", html.as_bytes()).unwrap(),
+            vec![
+                (
+                    "baz.g".to_string(),
+                    "https://example.com/pack/baz.g".to_string()
+                ),
+                (
+                    "qux.woven".to_string(),
+                    "https://example.com/other/qux.woven".to_string()
+                ),
+            ]
+        );
+
+        let json = r#"[
+            {"type": "dir", "name": "nested", "download_url": null},
+            {"type": "file", "name": "broken.g", "download_url": null},
+            {"type": "file", "download_url": "https://example.com/nameless.g"},
+            {"type": "file", "name": "ok.g", "download_url": "https://example.com/ok.g"}
+        ]"#;
+        assert_eq!(
+            listing_entries("https://example.com/pack/", json.as_bytes()).unwrap(),
+            vec![("ok.g".to_string(), "https://example.com/ok.g".to_string())]
+        );
+    }
+
+    #[test]
+    fn olsak_white_not_first() {
+        let olsak = "#d
+   a:#   #000000   black
+   w:.   #FFFFFF   white
+   b:r   red
+: rows
+1a
+1b
+: columns
+1a 1b
+";
+        let DynPuzzle::Nono(puzzle) = olsak_to_puzzle(olsak).unwrap() else {
+            panic!("expected a Nonogram");
+        };
+
+        assert_eq!(puzzle.palette[&BACKGROUND].rgb, (255, 255, 255));
+        assert_eq!(puzzle.palette[&BACKGROUND].name, "white");
+        assert_eq!(puzzle.palette[&puzzle.rows[0][0].color].rgb, (0, 0, 0));
+        assert_eq!(puzzle.palette[&puzzle.rows[1][0].color].rgb, (255, 0, 0));
+        for clue in puzzle.rows.iter().chain(puzzle.cols.iter()).flatten() {
+            assert_ne!(clue.color, BACKGROUND);
+        }
+    }
+
+    #[test]
+    fn olsak_goal() {
+        let olsak = "#d
+   0:   #FFFFFF   white
+   a:#   #000000   black
+: rows
+2a
+1a
+: columns
+2a
+1a
+: goal
+##
+#0
+";
+        let (puzzle, goal) = olsak_to_puzzle_and_goal(olsak).unwrap();
+        let goal = goal.expect("goal should be read");
+        assert_eq!(goal.to_puzzle(), puzzle);
+        assert_eq!(goal.grid[1][1], BACKGROUND);
+
+        let (_, no_goal) = olsak_to_puzzle_and_goal(&olsak.replace("\n#0\n", "\n#?\n")).unwrap();
+        assert!(no_goal.is_none());
+
+        // A goal that contradicts the clues is dropped, but the clues are still good:
+        let mismatched = olsak.replace("\n#0\n", "\n0#\n");
+        let mut doc = load("mismatched.g", mismatched.into_bytes(), None).unwrap();
+        assert!(doc.try_puzzle().is_some());
+        assert!(doc.try_solution().is_none());
+        assert!(
+            doc.quality_check(SeparationRule::default())
+                .iter()
+                .any(|problem| problem.contains("doesn't match the clues"))
+        );
+    }
+
+    #[test]
+    fn olsak_goal_with_space_background() {
+        let olsak = "#d
+   0:   #FFFFFF   white
+   a:#   #000000   black
+: rows
+1a
+
+1a
+: columns
+1a
+1a
+: goal
+ #
+
+# 
+Comments after the goal.
+";
+        let (puzzle, goal) = olsak_to_puzzle_and_goal(olsak).unwrap();
+        let goal = goal.expect("goal should be read");
+        assert_eq!(goal.to_puzzle(), puzzle);
+        let black = puzzle.assume_nono().rows[0][0].color;
+        assert_eq!(goal.grid[0][0], BACKGROUND);
+        assert_eq!(goal.grid[1][0], black);
+        assert_eq!(goal.grid[0][1], BACKGROUND);
+        assert_eq!(goal.grid[1][1], BACKGROUND);
+        assert_eq!(goal.grid[0][2], black);
+        assert_eq!(goal.grid[1][2], BACKGROUND);
+    }
+
+    #[test]
+    fn image_chars_are_stable_across_crops() {
+        let red = Rgba([255, 0, 0, 255]);
+        let blue = Rgba([0, 0, 255, 255]);
+        let white = Rgba([255, 255, 255, 255]);
+        // Red comes first in one crop, and blue in the other:
+        let image = image::RgbaImage::from_fn(4, 1, |x, _| [red, white, blue, red][x as usize]);
+        let left =
+            DynamicImage::ImageRgba8(image::imageops::crop_imm(&image, 0, 0, 3, 1).to_image());
+        let right =
+            DynamicImage::ImageRgba8(image::imageops::crop_imm(&image, 1, 0, 3, 1).to_image());
+
+        let chars_by_rgb = |solution: Solution| {
+            solution
+                .palette
+                .into_values()
+                .map(|ci| (ci.rgb, (ci.ch, ci.color)))
+                .collect::<BTreeMap<_, _>>()
+        };
+        assert_eq!(
+            chars_by_rgb(image_to_solution(&left)),
+            chars_by_rgb(image_to_solution(&right))
+        );
+    }
+
+    #[test]
+    fn indexed_bmp_keeps_source_palette_order() {
+        // A 4x1, 8-bit BMP whose palette is blue, white, red, green (stored as BGRx).
+        let palette = [
+            [255, 0, 0, 0],
+            [255, 255, 255, 0],
+            [0, 0, 255, 0],
+            [0, 255, 0, 0],
+        ];
+        let mut bmp = vec![];
+        bmp.extend(b"BM");
+        bmp.extend(74_u32.to_le_bytes()); // File size
+        bmp.extend(0_u32.to_le_bytes());
+        bmp.extend(70_u32.to_le_bytes()); // Pixel data offset
+        bmp.extend(40_u32.to_le_bytes()); // Header size
+        bmp.extend(4_i32.to_le_bytes()); // Width
+        bmp.extend(1_i32.to_le_bytes()); // Height
+        bmp.extend(1_u16.to_le_bytes()); // Planes
+        bmp.extend(8_u16.to_le_bytes()); // Bits per pixel
+        bmp.extend(0_u32.to_le_bytes()); // Uncompressed
+        bmp.extend(4_u32.to_le_bytes()); // Image size
+        bmp.extend(2835_i32.to_le_bytes());
+        bmp.extend(2835_i32.to_le_bytes());
+        bmp.extend(4_u32.to_le_bytes()); // Colors used
+        bmp.extend(0_u32.to_le_bytes());
+        bmp.extend(palette.concat());
+        bmp.extend([2, 3, 0, 1]); // Red, green, blue, white
+
+        let solution = load_image("indexed.bmp", Cursor::new(bmp))
+            .unwrap()
+            .take_solution()
+            .unwrap();
+        let rgb_of = |color: Color| solution.palette[&color].rgb;
+        assert_eq!(rgb_of(BACKGROUND), (255, 255, 255));
+        // In rgb order, green would come before red.
+        assert_eq!(rgb_of(Color(1)), (0, 0, 255));
+        assert_eq!(rgb_of(Color(2)), (255, 0, 0));
+        assert_eq!(rgb_of(Color(3)), (0, 255, 0));
+        assert_eq!(solution.grid[0][0], Color(2));
+        assert_eq!(solution.grid[1][0], Color(3));
+    }
+
+    #[test]
+    fn gif_source_palette() {
+        let mut gif = b"GIF89a".to_vec();
+        gif.extend([2, 0, 2, 0]); // 2x2
+        gif.extend([0x81, 0, 0]); // A global color table of 4 entries
+        gif.extend([0, 0, 0, 255, 0, 0, 255, 255, 255, 0, 0, 255]);
+        assert_eq!(
+            source_palette(&gif),
+            Some(vec![(0, 0, 0), (255, 0, 0), (255, 255, 255), (0, 0, 255)])
+        );
+
+        gif[10] = 0x01; // No global color table
+        assert_eq!(source_palette(&gif), None);
+        assert_eq!(source_palette(b"GIF89a"), None);
+    }
+
+    #[test]
+    fn strict_char_grid() {
+        let known = known_char_grid_chars();
+        assert!(char_grid_to_solution_strict("#.r\n◢.🟦\n", &known).is_ok());
+
+        let err = char_grid_to_solution_strict("#.Q\n.%.\n", &known)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(err, "unexpected characters: '%', 'Q'");
+
+        let only_x = BTreeSet::from(['X', '.']);
+        assert!(char_grid_to_solution_strict("X.\n.X\n", &only_x).is_ok());
+        assert!(char_grid_to_solution_strict("X.\n.#\n", &only_x).is_err());
+
+        // Windows line endings are fine:
+        let crlf = char_grid_to_solution_strict("X.\r\n.X\r\n", &only_x).unwrap();
+        assert_eq!(
+            crlf,
+            char_grid_to_solution_strict("X.\n.X\n", &only_x).unwrap()
+        );
+    }
+
+    #[test]
+    fn olsak_two_colored_triangle() {
+        let olsak = "#d
+   0:   #FFFFFF   white
+   a:#   #000000   black
+   b:x   SPEC   >>
+: rows
+1a
+: columns
+1a
+";
+        assert!(olsak_to_puzzle(&olsak.replace("SPEC", "black/white")).is_ok());
+        let err = olsak_to_puzzle(&olsak.replace("SPEC", "red/blue"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("\"red/blue\" isn't a triangle"), "{err}");
+    }
+
+    #[test]
+    fn olsak_bad_counts() {
+        let olsak = "#d
+   0:   #FFFFFF   white
+   a:#   #000000   black
+: rows
+ROW
+1a
+: columns
+1a
+1a
+";
+        let err = olsak_to_puzzle(&olsak.replace("ROW", "xa"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("expected a count"), "{err}");
+
+        let err = olsak_to_puzzle(&olsak.replace("ROW", "1q"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("isn't in the palette"), "{err}");
+
+        let err = olsak_to_puzzle(&olsak.replace("ROW", "3a"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("row 1 need at least 3 cells"), "{err}");
+
+        assert!(olsak_to_puzzle(&olsak.replace("ROW", "1a")).is_ok());
+
+        // The palette has no `1`, so a plain number doesn't have a color:
+        let err = olsak_to_puzzle(&olsak.replace("ROW", "1"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("doesn't define a default"), "{err}");
+    }
+
+    #[test]
+    fn clue_text_round_trip() {
+        let typed = "Rows:
+3
+1 1
+
+--
+Columns
+2
+0
+Column 3: 2
+";
+        let puzzle = clue_text_to_puzzle(typed).unwrap();
+        assert_eq!(puzzle.rows.len(), 2);
+        assert_eq!(puzzle.cols.len(), 3);
+        assert!(puzzle.cols[1].is_empty());
+        assert_eq!(puzzle.rows[1].len(), 2);
+
+        let colored = "Row 1: 2 red, 1 blue\nRow 2: 1 red\n\nColumn 1: 2 red\nColumn 2: 1 blue\n";
+        let puzzle = clue_text_to_puzzle(colored).unwrap();
+        assert_eq!(puzzle.palette.len(), 3);
+        assert_eq!(crate::export::as_clue_text(&puzzle), colored);
+
+        // A single colored block has no comma:
+        let one_block = "Row 1: 1 red\n\nColumn 1: 1 red\n";
+        let puzzle = clue_text_to_puzzle(one_block).unwrap();
+        assert_eq!(puzzle.rows[0].len(), 1);
+        assert_eq!(puzzle.palette[&puzzle.rows[0][0].color].name, "red");
+
+        assert!(clue_text_to_puzzle("3\nx 1\n\n2\n").is_err());
+    }
+
+    #[test]
+    fn bad_files_are_errors() {
+        assert!(load("bad.txt", vec![0xff, 0xfe, b'#'], None).is_err());
+        assert!(load("bad.png", b"not a png".to_vec(), None).is_err());
+        assert!(load_path(&PathBuf::from("no/such/file.png"), None).is_err());
+
+        let doc = load("ok.txt", b"#.\n.#\n".to_vec(), None).unwrap();
+        assert_eq!(doc.file, "ok.txt");
+    }
+
+    #[test]
+    fn partial_char_grid() {
+        let palette = bw_palette();
+        let partial = char_grid_to_partial("#?\n.#\n", &palette, '?').unwrap();
+        assert_eq!(partial.dim(), (2, 2));
+        assert!(partial[[0, 0]].is_known_to_be(Color(1)));
+        assert!(!partial[[0, 1]].is_known());
+        assert!(partial[[1, 0]].is_known_to_be(BACKGROUND));
+
+        assert!(char_grid_to_partial("#?\n.\n", &palette, '?').is_err());
+        assert!(char_grid_to_partial("#x\n", &palette, '?').is_err());
+    }
+
+    #[test]
+    fn duplicates_are_dropped() {
+        let mut docs = vec![
+            load("a.txt", b"#.\n.#\n".to_vec(), None).unwrap(),
+            load("b.txt", b"##\n.#\n".to_vec(), None).unwrap(),
+            load("c.txt", b"#.\n.#\n".to_vec(), None).unwrap(),
+            // The same cells as `e.txt`, in column-major order, but a different shape:
+            load("d.txt", b"#.#.\n".to_vec(), None).unwrap(),
+            load("e.txt", b"##\n..\n".to_vec(), None).unwrap(),
+        ];
+        assert_eq!(dedup_documents(&mut docs), 1);
+        assert_eq!(
+            docs.iter().map(|doc| doc.file.as_str()).collect::<Vec<_>>(),
+            vec!["a.txt", "b.txt", "d.txt", "e.txt"]
+        );
+    }
+
+    #[test]
+    fn bad_files_in_a_zip_are_skipped() {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let mut writer = zip::ZipWriter::new(Cursor::new(vec![]));
+        let options =
+            SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+        for (name, contents) in [
+            ("puzzles/a.txt", "#.\n.#\n"),
+            ("puzzles/broken.xml", "<puzzleset>"),
+            ("puzzles/b.txt", "##\n.#\n"),
+        ] {
+            writer.start_file(name, options).unwrap();
+            writer.write_all(contents.as_bytes()).unwrap();
+        }
+        let bytes = writer.finish().unwrap().into_inner();
+
+        let documents = load_zip(bytes).unwrap();
+        assert_eq!(documents.len(), 3);
+        assert_eq!(documents[0].as_ref().unwrap().file, "a.txt");
+        let err = format!("{:#}", documents[1].as_ref().unwrap_err());
+        assert!(err.contains("broken.xml"), "{err}");
+        assert_eq!(documents[2].as_ref().unwrap().file, "b.txt");
+    }
+
+    #[test]
+    fn sniffing() {
+        use puzzle::sniff_format;
+        assert_eq!(sniff_format(b"\x89PNG\r\n\x1a\n"), NonogramFormat::Image);
+        assert_eq!(sniff_format(b"GIF89a"), NonogramFormat::Image);
+        assert_eq!(
+            sniff_format(b"<?xml version=\"1.0\"?>"),
+            NonogramFormat::Webpbn
+        );
+        assert_eq!(sniff_format(b"\n<puzzleset>"), NonogramFormat::Webpbn);
+        assert_eq!(sniff_format(b"#d\n1\n"), NonogramFormat::Olsak);
+        assert_eq!(sniff_format(b"WOVEN-abc-"), NonogramFormat::Woven);
+        assert_eq!(sniff_format(b"WOVEN1-abc-"), NonogramFormat::Woven);
+        assert_eq!(sniff_format(b"#.#\n.#.\n"), NonogramFormat::CharGrid);
+        assert_eq!(sniff_format(b"{\"title\": \"\"}"), NonogramFormat::Json);
+        assert_eq!(sniff_format(b"Rows:\n1\n"), NonogramFormat::ClueList);
+
+        // Pasting a clue list:
+        let clues = "Row 1: 1\nRow 2: 1\n\nColumn 1: 2\n";
+        let mut doc = load("", clues.as_bytes().to_vec(), None).unwrap();
+        assert_eq!((doc.puzzle().rows(), doc.puzzle().cols()), (2, 1));
+
+        use crate::puzzle::PuzzleDynOps;
+        let olsak =
+            "#d\n   a:#   #000000   black\n   w:.   #FFFFFF   white\n: rows\n1a\n: columns\n1a\n";
+        let mut doc = load("-", olsak.as_bytes().to_vec(), None).unwrap();
+        assert_eq!(doc.puzzle().rows(), 1);
+    }
+}
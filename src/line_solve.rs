@@ -8,22 +8,28 @@ use anyhow::{Context, bail};
 use colored::{ColoredString, Colorize};
 use ndarray::{ArrayView1, ArrayViewMut1};
 
-#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, clap::ValueEnum)]
 pub enum SolveMode {
     // Listed in order from quickest to most comprehensive:
     Skim,
     Scrub,
+    /// Tries every arrangement of the clues (`exhaust_line_by_enumeration`). This can't learn
+    /// anything scrubbing doesn't, but it's a much simpler check on it, so when asked for, it
+    /// takes scrubbing's place.
+    Enumerate,
 }
 
 impl SolveMode {
-    pub fn all() -> &'static [SolveMode] {
-        &[SolveMode::Skim, SolveMode::Scrub]
+    /// In declaration order, so `all()[mode as usize] == mode`.
+    pub fn all() -> &'static [SolveMode; NUM_SOLVE_MODES] {
+        &[SolveMode::Skim, SolveMode::Scrub, SolveMode::Enumerate]
     }
 
     pub fn name(self) -> &'static str {
         match self {
             SolveMode::Skim => "skim",
             SolveMode::Scrub => "scrub",
+            SolveMode::Enumerate => "enumerate",
         }
     }
 
@@ -31,6 +37,7 @@ impl SolveMode {
         match self {
             SolveMode::Skim => self.name().green(),
             SolveMode::Scrub => self.name().red(),
+            SolveMode::Enumerate => self.name().magenta(),
         }
     }
 
@@ -38,6 +45,7 @@ impl SolveMode {
         match self {
             SolveMode::Skim => '-',
             SolveMode::Scrub => '+',
+            SolveMode::Enumerate => '*',
         }
     }
 
@@ -45,13 +53,15 @@ impl SolveMode {
         match self {
             SolveMode::Skim => None,
             SolveMode::Scrub => Some(SolveMode::Skim),
+            SolveMode::Enumerate => Some(SolveMode::Scrub),
         }
     }
 
     pub fn next(self) -> Option<SolveMode> {
         match self {
             SolveMode::Skim => Some(SolveMode::Scrub),
-            SolveMode::Scrub => None,
+            SolveMode::Scrub => Some(SolveMode::Enumerate),
+            SolveMode::Enumerate => None,
         }
     }
 
@@ -60,33 +70,44 @@ impl SolveMode {
     }
 
     pub fn last() -> SolveMode {
-        SolveMode::Scrub
+        SolveMode::Enumerate
     }
 }
 
+/// How many variants `SolveMode` has.
+const NUM_SOLVE_MODES: usize = 3;
+
+/// One `T` per `SolveMode`.
 #[derive(Clone, Copy, PartialEq, Eq)]
-pub struct ModeMap<T> {
-    pub skim: T,
-    pub scrub: T,
-}
+pub struct ModeMap<T>([T; NUM_SOLVE_MODES]);
 
 impl<T: Clone> ModeMap<T> {
     pub fn new_uniform(value: T) -> ModeMap<T> {
-        ModeMap {
-            skim: value.clone(),
-            scrub: value,
-        }
+        ModeMap(std::array::from_fn(|_| value.clone()))
+    }
+}
+
+impl<T> ModeMap<T> {
+    pub fn from_fn(mut f: impl FnMut(SolveMode) -> T) -> ModeMap<T> {
+        ModeMap(std::array::from_fn(|i| f(SolveMode::all()[i])))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (SolveMode, &T)> {
+        SolveMode::all().iter().copied().zip(self.0.iter())
     }
 }
 
-impl<T: std::fmt::Display> std::fmt::Display for ModeMap<T> {
+impl std::fmt::Display for ModeMap<usize> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for mode in SolveMode::all() {
-            // In practice, we know this is a count so (HACK) pluralize:
-            write!(f, "{}s: {: >6}", mode.name(), self[*mode])?;
-            if *mode != SolveMode::last() {
+        // Enumeration only happens when it's asked for, so it's only mentioned then.
+        let modes = SolveMode::all()
+            .iter()
+            .filter(|mode| **mode != SolveMode::Enumerate || self[**mode] > 0);
+        for (i, mode) in modes.enumerate() {
+            if i > 0 {
                 write!(f, "  ")?;
             }
+            write!(f, "{}s: {: >6}", mode.name(), self[*mode])?;
         }
         Ok(())
     }
@@ -96,19 +117,13 @@ impl<T> std::ops::Index<SolveMode> for ModeMap<T> {
     type Output = T;
 
     fn index(&self, index: SolveMode) -> &Self::Output {
-        match index {
-            SolveMode::Skim => &self.skim,
-            SolveMode::Scrub => &self.scrub,
-        }
+        &self.0[index as usize]
     }
 }
 
 impl<T> std::ops::IndexMut<SolveMode> for ModeMap<T> {
     fn index_mut(&mut self, index: SolveMode) -> &mut Self::Output {
-        match index {
-            SolveMode::Skim => &mut self.skim,
-            SolveMode::Scrub => &mut self.scrub,
-        }
+        &mut self.0[index as usize]
     }
 }
 
@@ -174,6 +189,11 @@ impl Cell {
         self.possible_color_mask == 1 << color.0
     }
 
+    /// How many colors this cell could still be.
+    pub fn candidate_count(&self) -> u32 {
+        self.possible_color_mask.count_ones()
+    }
+
     pub fn can_be(&self, color: Color) -> bool {
         (self.possible_color_mask & 1 << color.0) != 0
     }
@@ -256,6 +276,28 @@ fn bg_squares<C: Clue>(cs: &[C], len: u16) -> u16 {
     remaining
 }
 
+/// When do adjacent blocks need background between them?
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, clap::ValueEnum)]
+pub enum SeparationRule {
+    /// The usual rule, as determined by `Clue::must_be_separated_from`.
+    #[default]
+    SameColor,
+    /// "No-split" puzzles: every pair of blocks is separated, regardless of color.
+    Always,
+    /// Blocks may touch, even if they're the same color.
+    Never,
+}
+
+impl SeparationRule {
+    pub fn separates<C: Clue>(self, prev: &C, next: &C) -> bool {
+        match self {
+            SeparationRule::SameColor => prev.must_be_separated_from(next),
+            SeparationRule::Always => true,
+            SeparationRule::Never => false,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ScrubReport {
     pub affected_cells: Vec<usize>,
@@ -299,11 +341,12 @@ fn learn_cell_not(
 
 struct ClueAdjIterator<'a, C: Clue> {
     clues: &'a [C],
+    rule: SeparationRule,
     i: usize,
 }
 impl<'a, C: Clue> ClueAdjIterator<'a, C> {
-    fn new(clues: &'a [C]) -> ClueAdjIterator<'a, C> {
-        ClueAdjIterator { clues, i: 0 }
+    fn new(clues: &'a [C], rule: SeparationRule) -> ClueAdjIterator<'a, C> {
+        ClueAdjIterator { clues, rule, i: 0 }
     }
 }
 
@@ -315,10 +358,15 @@ impl<'a, C: Clue> Iterator for ClueAdjIterator<'a, C> {
             return None;
         }
         let res = (
-            self.i > 0 && self.clues[self.i - 1].must_be_separated_from(&self.clues[self.i]),
+            self.i > 0
+                && self
+                    .rule
+                    .separates(&self.clues[self.i - 1], &self.clues[self.i]),
             &self.clues[self.i],
             self.i < self.clues.len() - 1
-                && self.clues[self.i].must_be_separated_from(&self.clues[self.i + 1]),
+                && self
+                    .rule
+                    .separates(&self.clues[self.i], &self.clues[self.i + 1]),
         );
         self.i += 1;
         Some(res)
@@ -331,6 +379,7 @@ fn packed_extents<C: Clue + Copy>(
     clues: &[C],
     lane: &ArrayViewMut1<Cell>,
     reversed: bool,
+    rule: SeparationRule,
 ) -> anyhow::Result<Vec<usize>> {
     if clues.is_empty() {
         return Ok(vec![]);
@@ -368,11 +417,11 @@ fn packed_extents<C: Clue + Copy>(
         let clue = clue_at(clue_idx);
         if let Some(last_clue) = last_clue {
             if !reversed {
-                if last_clue.must_be_separated_from(clue) {
+                if rule.separates(&last_clue, clue) {
                     pos += 1;
                 }
             } else {
-                if clue.must_be_separated_from(&last_clue) {
+                if rule.separates(clue, &last_clue) {
                     pos += 1;
                 }
             }
@@ -460,6 +509,7 @@ fn packed_extents<C: Clue + Copy>(
 pub fn skim_line<C: Clue + Copy>(
     clues: &[C],
     lane: &mut ArrayViewMut1<Cell>,
+    rule: SeparationRule,
 ) -> anyhow::Result<ScrubReport> {
     let mut affected = Vec::<usize>::new();
     if clues.is_empty() {
@@ -485,11 +535,11 @@ pub fn skim_line<C: Clue + Copy>(
     }
 
     // Now slam the clues back and forth!
-    let left_packed_right_extents = packed_extents(clues, &lane, false)?;
-    let right_packed_left_extents = packed_extents(clues, &lane, true)?;
+    let left_packed_right_extents = packed_extents(clues, &lane, false, rule)?;
+    let right_packed_left_extents = packed_extents(clues, &lane, true, rule)?;
 
-    for ((gap_before, clue, gap_after), (left_extent, right_extent)) in ClueAdjIterator::new(clues)
-        .zip(
+    for ((gap_before, clue, gap_after), (left_extent, right_extent)) in
+        ClueAdjIterator::new(clues, rule).zip(
             right_packed_left_extents
                 .iter()
                 .zip(left_packed_right_extents.iter()),
@@ -571,14 +621,42 @@ pub fn skim_line<C: Clue + Copy>(
     })
 }
 
+/// The range of cells (inclusive) that each clue could still cover, after skimming. Each clue's
+/// block lies somewhere in its range, and nothing outside of it can belong to that clue.
+pub fn clue_spans<C: Clue + Copy>(
+    clues: &[C],
+    lane: ArrayView1<Cell>,
+    rule: SeparationRule,
+) -> anyhow::Result<Vec<(usize, usize)>> {
+    let mut lane = lane.to_owned();
+    let mut lane = lane.view_mut();
+    skim_line(clues, &mut lane, rule)?;
+
+    let left_packed_right_extents = packed_extents(clues, &lane, false, rule)?;
+    let right_packed_left_extents = packed_extents(clues, &lane, true, rule)?;
+
+    Ok(clues
+        .iter()
+        .zip(
+            left_packed_right_extents
+                .iter()
+                .zip(right_packed_left_extents.iter()),
+        )
+        .map(|(clue, (right_extent, left_extent))| {
+            (right_extent + 1 - clue.len(), left_extent + clue.len() - 1)
+        })
+        .collect())
+}
+
 pub fn settle_line<C: Clue + Copy>(
     clues: &[C],
     lane: &mut ArrayViewMut1<Cell>,
+    rule: SeparationRule,
 ) -> anyhow::Result<ScrubReport> {
     let mut affected = Vec::<usize>::new();
 
-    let left_packed_right_extents = packed_extents(clues, &lane, false)?;
-    let right_packed_left_extents = packed_extents(clues, &lane, true)?;
+    let left_packed_right_extents = packed_extents(clues, &lane, false, rule)?;
+    let right_packed_left_extents = packed_extents(clues, &lane, true, rule)?;
 
     let mut prev_known_end = Some(0); // Left edge is known!
     for i in 0..clues.len() {
@@ -592,13 +670,13 @@ pub fn settle_line<C: Clue + Copy>(
         if is_known {
             // Separator background before
             if left_extent > 0 {
-                if i > 0 && clues[i - 1].must_be_separated_from(clue) {
+                if i > 0 && rule.separates(&clues[i - 1], clue) {
                     learn_cell(BACKGROUND, lane, left_extent - 1, &mut affected)?;
                 }
             }
             // Separator background after
             if right_extent < lane.len() - 1 {
-                if i < clues.len() - 1 && clue.must_be_separated_from(&clues[i + 1]) {
+                if i < clues.len() - 1 && rule.separates(clue, &clues[i + 1]) {
                     learn_cell(BACKGROUND, lane, right_extent + 1, &mut affected)?;
                 }
             }
@@ -667,6 +745,7 @@ pub fn skim_heuristic<C: Clue>(clues: &[C], lane: ArrayView1<Cell>) -> i32 {
 pub fn scrub_line<C: Clue + Clone + Copy>(
     cs: &[C],
     lane: &mut ArrayViewMut1<Cell>,
+    rule: SeparationRule,
 ) -> anyhow::Result<ScrubReport> {
     let mut res = ScrubReport {
         affected_cells: vec![],
@@ -682,7 +761,7 @@ pub fn scrub_line<C: Clue + Clone + Copy>(
 
             hypothetical_lane[i] = Cell::from_color(color);
 
-            match skim_line(cs, &mut hypothetical_lane.view_mut()) {
+            match skim_line(cs, &mut hypothetical_lane.view_mut(), rule) {
                 Ok(_) => { /* no luck: no contradiction */ }
                 Err(err) => {
                     // `color` is impossible here; we've learned something!
@@ -697,7 +776,7 @@ pub fn scrub_line<C: Clue + Clone + Copy>(
     Ok(res)
 }
 
-pub fn scrub_heuristic<C: Clue>(clues: &[C], lane: ArrayView1<Cell>) -> i32 {
+pub fn scrub_heuristic<C: Clue>(clues: &[C], lane: ArrayView1<Cell>, rule: SeparationRule) -> i32 {
     let mut foreground_cells: i32 = 0;
     // If `space_taken == lane.len()`, the line is immediately solvable with no other knowledge.
     let mut space_taken: i32 = 0;
@@ -707,7 +786,7 @@ pub fn scrub_heuristic<C: Clue>(clues: &[C], lane: ArrayView1<Cell>) -> i32 {
         foreground_cells += c.len() as i32;
         space_taken += c.len() as i32;
         if let Some(last_clue) = last_clue {
-            if last_clue.must_be_separated_from(c) {
+            if rule.separates(&last_clue, c) {
                 // We need to leave a space between these clues.
                 space_taken += 1;
             }
@@ -762,6 +841,7 @@ pub fn scrub_heuristic<C: Clue>(clues: &[C], lane: ArrayView1<Cell>) -> i32 {
 pub fn exhaust_line<C: Clue + Clone + Copy>(
     cs: &[C],
     lane: &mut ArrayViewMut1<Cell>,
+    rule: SeparationRule,
 ) -> anyhow::Result<ScrubReport> {
     if cs.is_empty() {
         let mut affected_cells = vec![];
@@ -810,7 +890,7 @@ pub fn exhaust_line<C: Clue + Clone + Copy>(
                         .can_be(clue.color_at(clue_cell_idx))
                 });
                 let consec_placeable = clue_idx == 0
-                    || !cs[clue_idx - 1].must_be_separated_from(&cs[clue_idx])
+                    || !rule.separates(&cs[clue_idx - 1], &cs[clue_idx])
                     || new_gap > pfx_gap;
                 if gap_placeable && color_placeable && consec_placeable {
                     reachable[clue_idx][new_gap as usize] = true;
@@ -850,7 +930,7 @@ pub fn exhaust_line<C: Clue + Clone + Copy>(
                 let gap_placeable = (new_gap..gap_sfx)
                     .all(|g_idx| lane[clue_len_so_far + g_idx].can_be(BACKGROUND));
                 let consec_placeable = clue_idx == cs.len() - 1
-                    || !cs[clue_idx].must_be_separated_from(&cs[clue_idx + 1])
+                    || !rule.separates(&cs[clue_idx], &cs[clue_idx + 1])
                     || new_gap < gap_sfx;
 
                 if gap_placeable && clue_placeable && consec_placeable {
@@ -897,6 +977,106 @@ pub fn exhaust_line<C: Clue + Clone + Copy>(
     Ok(ScrubReport { affected_cells })
 }
 
+/// Another way of getting `exhaust_line`'s results: tries every arrangement of the clues that
+/// fits `lane`, and keeps only the colors that appear in some arrangement. This is exponential,
+/// so it gives up (returning `Ok(None)` and leaving `lane` untouched) after `max_arrangements`.
+/// It's mainly useful as a simple, obviously-correct check on `exhaust_line`.
+pub fn exhaust_line_by_enumeration<C: Clue>(
+    cs: &[C],
+    lane: &mut ArrayViewMut1<Cell>,
+    rule: SeparationRule,
+    max_arrangements: usize,
+) -> anyhow::Result<Option<ScrubReport>> {
+    let mut superposition = vec![Cell::new_impossible(); lane.len()];
+    let mut budget = max_arrangements;
+    let mut arrangement = vec![];
+    if !enumerate_arrangements(
+        cs,
+        None,
+        lane,
+        rule,
+        &mut arrangement,
+        &mut superposition,
+        &mut budget,
+    ) {
+        return Ok(None);
+    }
+    if budget == max_arrangements {
+        bail!("no arrangement of the clues fits");
+    }
+
+    let mut affected_cells = vec![];
+    for i in 0..lane.len() {
+        learn_cell_intersect(superposition[i], lane, i, &mut affected_cells)?;
+    }
+    Ok(Some(ScrubReport { affected_cells }))
+}
+
+/// Places `cs` after `arrangement` in every possible way, recording each complete arrangement in
+/// `superposition`. Returns false if it ran out of `budget`.
+fn enumerate_arrangements<C: Clue>(
+    cs: &[C],
+    prev: Option<&C>,
+    lane: &ArrayViewMut1<Cell>,
+    rule: SeparationRule,
+    arrangement: &mut Vec<Color>,
+    superposition: &mut [Cell],
+    budget: &mut usize,
+) -> bool {
+    let pos = arrangement.len();
+    let Some((clue, rest)) = cs.split_first() else {
+        // The rest of the line is background.
+        if (pos..lane.len()).any(|i| !lane[i].can_be(BACKGROUND)) {
+            return true;
+        }
+        if *budget == 0 {
+            return false;
+        }
+        *budget -= 1;
+        for (i, cell) in superposition.iter_mut().enumerate() {
+            cell.actually_could_be(arrangement.get(i).copied().unwrap_or(BACKGROUND));
+        }
+        return true;
+    };
+
+    let min_gap = match prev {
+        Some(prev) if rule.separates(prev, clue) => 1,
+        _ => 0,
+    };
+    for gap in 0.. {
+        if pos + gap + clue.len() > lane.len() {
+            break;
+        }
+        if gap > 0 && !lane[pos + gap - 1].can_be(BACKGROUND) {
+            break; // Longer gaps would cover this cell, too.
+        }
+        if gap < min_gap {
+            continue;
+        }
+        let fits = (0..clue.len()).all(|i| lane[pos + gap + i].can_be(clue.color_at(i)));
+        if !fits {
+            continue;
+        }
+
+        arrangement.extend(std::iter::repeat_n(BACKGROUND, gap));
+        arrangement.extend((0..clue.len()).map(|i| clue.color_at(i)));
+        let keep_going = enumerate_arrangements(
+            rest,
+            Some(clue),
+            lane,
+            rule,
+            arrangement,
+            superposition,
+            budget,
+        );
+        arrangement.truncate(pos);
+        if !keep_going {
+            return false;
+        }
+    }
+    true
+}
+
 pub fn filter_report_by_color(
     report: &mut ScrubReport,
     orig_lane: &[Cell],
@@ -1014,6 +1194,7 @@ mod tests {
         exhaust_line(
             &clues,
             &mut working_line.rows_mut().into_iter().next().unwrap(),
+            SeparationRule::default(),
         )
         .unwrap();
         working_line
@@ -1024,6 +1205,7 @@ mod tests {
         scrub_line(
             &clues,
             &mut working_line.rows_mut().into_iter().next().unwrap(),
+            SeparationRule::default(),
         )
         .unwrap();
         working_line
@@ -1034,6 +1216,7 @@ mod tests {
         skim_line(
             &clues,
             &mut working_line.rows_mut().into_iter().next().unwrap(),
+            SeparationRule::default(),
         )
         .unwrap();
         working_line
@@ -1044,11 +1227,108 @@ mod tests {
         settle_line(
             &clues,
             &mut working_line.rows_mut().into_iter().next().unwrap(),
+            SeparationRule::default(),
         )
         .unwrap();
         working_line
     }
 
+    type LineSolver =
+        fn(&[Nono], &mut ArrayViewMut1<Cell>, SeparationRule) -> anyhow::Result<ScrubReport>;
+
+    /// `None` if `solver` finds a contradiction.
+    fn test_with_rule(
+        solver: LineSolver,
+        rule: SeparationRule,
+        clues: &str,
+        init: &str,
+    ) -> Option<ndarray::Array1<Cell>> {
+        let mut working_line = l(init);
+        solver(
+            &n(clues),
+            &mut working_line.rows_mut().into_iter().next().unwrap(),
+            rule,
+        )
+        .ok()?;
+        Some(working_line)
+    }
+
+    #[test]
+    fn separation_rules() {
+        use SeparationRule::*;
+        let solvers: [LineSolver; 2] = [skim_line, exhaust_line];
+        for solver in solvers {
+            // Same-color blocks only fit in three cells if they can touch:
+            for rule in [SameColor, Always] {
+                assert_eq!(test_with_rule(solver, rule, "⬛1 ⬛2", "🔳 🔳 🔳"), None);
+            }
+            assert_eq!(
+                test_with_rule(solver, Never, "⬛1 ⬛2", "🔳 🔳 🔳"),
+                Some(l("⬛ ⬛ ⬛"))
+            );
+
+            // With a cell to spare, the gap isn't guaranteed if they can touch:
+            for rule in [SameColor, Always] {
+                assert_eq!(
+                    test_with_rule(solver, rule, "⬛1 ⬛2", "🔳 🔳 🔳 🔳"),
+                    Some(l("⬛ ⬜ ⬛ ⬛"))
+                );
+            }
+            assert_eq!(
+                test_with_rule(solver, Never, "⬛1 ⬛2", "🔳 🔳 🔳 🔳"),
+                Some(l("🔳 🔳 ⬛ 🔳"))
+            );
+
+            // Different colors need a gap only under `Always`:
+            assert_eq!(
+                test_with_rule(solver, Always, "⬛1 🟥1", "⬜⬛🟥 ⬜⬛🟥 ⬜⬛🟥"),
+                Some(l("⬛ ⬜ 🟥"))
+            );
+            assert_eq!(
+                test_with_rule(solver, Always, "⬛1 🟥1", "⬜⬛🟥 ⬜⬛🟥"),
+                None
+            );
+        }
+
+        assert_eq!(
+            test_with_rule(exhaust_line, SameColor, "⬛1 🟥1", "⬜⬛🟥 ⬜⬛🟥 ⬜⬛🟥"),
+            Some(l("⬜⬛ ⬜⬛🟥 ⬜🟥"))
+        );
+        assert_eq!(
+            test_with_rule(exhaust_line, Never, "⬛1 🟥1", "⬜⬛🟥 ⬜⬛🟥"),
+            Some(l("⬛ 🟥"))
+        );
+    }
+
+    #[test]
+    fn candidate_count_test() {
+        assert_eq!(Cell::from_color(Color(1)).candidate_count(), 1);
+        assert_eq!(
+            Cell::from_colors(&[Color(0), Color(2), Color(5)]).candidate_count(),
+            3
+        );
+        assert_eq!(Cell::new_impossible().candidate_count(), 0);
+        assert_eq!(Cell::new_anything().candidate_count(), 32);
+    }
+
+    #[test]
+    fn clue_spans_test() {
+        let spans = |clues: Vec<Nono>, init: &str| {
+            clue_spans(&clues, l(init).view(), SeparationRule::default()).unwrap()
+        };
+
+        assert_eq!(
+            spans(n("⬛1 ⬛2 ⬛1"), "🔳 🔳 🔳 🔳 🔳 🔳 🔳 🔳"),
+            vec![(0, 2), (2, 5), (5, 7)]
+        );
+        // A known cell pins down the clue that must cover it:
+        assert_eq!(
+            spans(n("⬛1 ⬛2"), "🔳 🔳 🔳 🔳 🔳 ⬛"),
+            vec![(0, 2), (4, 5)]
+        );
+        assert!(clue_spans(&n("⬛3"), l("🔳 ⬜ 🔳").view(), SeparationRule::default()).is_err());
+    }
+
     #[test]
     fn scrub_test() {
         assert_eq!(test_scrub(n("⬛1"), "🔳 🔳 🔳 🔳"), l("🔳 🔳 🔳 🔳"));
@@ -1109,6 +1389,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn enumeration_matches_exhaust() {
+        use itertools::Itertools;
+
+        let colors = [Color(0), Color(1), Color(2)];
+        for truth in std::iter::repeat_n(colors, 5).multi_cartesian_product() {
+            let clues: Vec<Nono> = truth
+                .iter()
+                .dedup_with_count()
+                .filter(|(_, color)| **color != BACKGROUND)
+                .map(|(count, color)| Nono {
+                    color: *color,
+                    count: count as u16,
+                })
+                .collect();
+
+            for known_mask in 0..(1 << truth.len()) {
+                let mut palette_cell = Cell::new_impossible();
+                for color in colors {
+                    palette_cell.actually_could_be(color);
+                }
+                let init: ndarray::Array1<Cell> = truth
+                    .iter()
+                    .enumerate()
+                    .map(|(i, color)| {
+                        if known_mask & (1 << i) != 0 {
+                            Cell::from_color(*color)
+                        } else {
+                            palette_cell
+                        }
+                    })
+                    .collect();
+
+                let mut exhausted = init.clone();
+                exhaust_line(&clues, &mut exhausted.view_mut(), SeparationRule::default()).unwrap();
+                let mut enumerated = init.clone();
+                exhaust_line_by_enumeration(
+                    &clues,
+                    &mut enumerated.view_mut(),
+                    SeparationRule::default(),
+                    usize::MAX,
+                )
+                .unwrap()
+                .unwrap();
+                assert_eq!(
+                    exhausted, enumerated,
+                    "clues {clues:?}, starting from {init:?}"
+                );
+            }
+        }
+
+        // Running out of arrangements leaves the line alone:
+        let mut line = l("🔳 🔳 🔳 🔳 🔳 🔳");
+        let report = exhaust_line_by_enumeration(
+            &n("⬛1 ⬛1"),
+            &mut line.view_mut(),
+            SeparationRule::default(),
+            3,
+        )
+        .unwrap();
+        assert!(report.is_none());
+        assert_eq!(line, l("🔳 🔳 🔳 🔳 🔳 🔳"));
+
+        let mut line = l("⬜ ⬜");
+        assert!(
+            exhaust_line_by_enumeration(
+                &n("⬛1"),
+                &mut line.view_mut(),
+                SeparationRule::default(),
+                10
+            )
+            .is_err()
+        );
+    }
+
     #[test]
     fn skim_test() {
         assert_eq!(test_skim(n("⬛1"), "🔳 🔳 🔳 🔳"), l("🔳 🔳 🔳 🔳"));
@@ -1205,7 +1560,8 @@ mod tests {
             let initial = ndarray::arr1(&[ $($state),* ]);
             scrub_heuristic(
                 &vec![ $( crate::puzzle::Nono { color: $color.unwrap_color(), count: $count} ),* ],
-                initial.rows().into_iter().next().unwrap())
+                initial.rows().into_iter().next().unwrap(),
+                SeparationRule::SameColor)
         }
     };
 }
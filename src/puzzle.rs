@@ -1,11 +1,16 @@
+use anyhow::bail;
 use core::panic;
 use std::fmt::Debug;
 use std::hash::Hash;
-use std::{collections::HashMap, hash::Hasher};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hasher,
+};
 
 use crate::{
     grid_solve::{self, LineStatus, SolveOptions},
     import::{solution_to_puzzle, solution_to_triano_puzzle},
+    line_solve::SeparationRule,
 };
 use serde::{Deserialize, Serialize};
 pub trait Clue: Clone + Copy + Debug + PartialEq + Eq + Hash + Send {
@@ -105,9 +110,13 @@ impl Clue for Triano {
             _ => self.body_color,
         }
     }
+    // A back cap always ends a block, and a front cap always starts one, so either lets blocks
+    // touch. Otherwise, the blocks would read as one if they were the same color, or if either is
+    // a lone cap (which would become the other one's cap).
     fn must_be_separated_from(&self, next: &Self) -> bool {
-        // TODO: check the semantics with the book!
-        self.body_color == next.body_color && self.back_cap.is_none() && next.front_cap.is_none()
+        self.back_cap.is_none()
+            && next.front_cap.is_none()
+            && (self.body_len == 0 || next.body_len == 0 || self.body_color == next.body_color)
     }
 
     fn to_string(&self, puzzle: &Puzzle<Self>) -> String {
@@ -187,6 +196,62 @@ pub struct Corner {
     pub left: bool,
 }
 
+impl Corner {
+    /// Characters that the chargrid importer reads as this corner.
+    pub fn chars(self) -> [char; 3] {
+        match (self.upper, self.left) {
+            (false, false) => ['◢', '🮞', '◿'],
+            (false, true) => ['◣', '🮟', '◺'],
+            (true, true) => ['◤', '🮜', '◸'],
+            (true, false) => ['◥', '🮝', '◹'],
+        }
+    }
+
+    /// Whether this half of a square covers the point `(u, v)` (each from 0 to 1, from the
+    /// top-left).
+    pub fn covers(self, u: f32, v: f32) -> bool {
+        let from_left = if self.left { u } else { 1.0 - u };
+        let from_top = if self.upper { v } else { 1.0 - v };
+        from_left + from_top < 1.0
+    }
+}
+
+/// Which part of a picture stays in place when it's resized.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// How far across the picture (in halves) the anchor is, horizontally and vertically.
+    fn halves(self) -> (i64, i64) {
+        match self {
+            Anchor::TopLeft => (0, 0),
+            Anchor::Top => (1, 0),
+            Anchor::TopRight => (2, 0),
+            Anchor::Left => (0, 1),
+            Anchor::Center => (1, 1),
+            Anchor::Right => (2, 1),
+            Anchor::BottomLeft => (0, 2),
+            Anchor::Bottom => (1, 2),
+            Anchor::BottomRight => (2, 2),
+        }
+    }
+}
+
+/// Characters that the chargrid importer guesses are the background.
+pub const BACKGROUND_CHARS: [char; 9] = [' ', '.', '_', 'w', 'W', '·', '☐', '0', '⬜'];
+/// Characters that the chargrid importer guesses are black.
+pub const BLACK_CHARS: [char; 8] = ['#', 'B', 'b', '.', '■', '█', '1', '⬛'];
+
 // Note that `rgb` is not necessarily unique!
 // But `ch` and `name` ought to be, along with `rgb` + `corner`.
 #[derive(PartialEq, Eq, Clone, Debug, Hash, Serialize, Deserialize)]
@@ -231,13 +296,19 @@ pub type PartialSolution = ndarray::Array2<crate::line_solve::Cell>;
 
 impl Solution {
     pub fn to_partial(&self) -> PartialSolution {
+        self.to_partial_with_unknown(&[UNSOLVED])
+    }
+
+    /// Like `to_partial`, but any of the `unknown` colors can be anything. (For example, treating
+    /// `BACKGROUND` as unknown lets the solver work from a half-drawn picture.)
+    pub fn to_partial_with_unknown(&self, unknown: &[Color]) -> PartialSolution {
         let mut res = PartialSolution::from_elem(
             (self.y_size(), self.x_size()),
             crate::line_solve::Cell::new_impossible(),
         );
         for (x, col) in self.grid.iter().enumerate() {
             for (y, color) in col.iter().enumerate() {
-                if *color == UNSOLVED {
+                if unknown.contains(color) {
                     res[[y, x]] = crate::line_solve::Cell::new_anything();
                 } else {
                     res[[y, x]] = crate::line_solve::Cell::from_color(*color);
@@ -262,6 +333,73 @@ impl<C: Clue> Hash for Puzzle<C> {
     }
 }
 
+impl<C: Clue> Puzzle<C> {
+    /// For each row and column, how often the solver had to come back to it, and which technique
+    /// last made progress on it. The most-visited lines are the bottlenecks.
+    pub fn per_line_difficulty(
+        &self,
+    ) -> anyhow::Result<(Vec<grid_solve::LineStats>, Vec<grid_solve::LineStats>)> {
+        let report = grid_solve::solve(self, &mut None, &SolveOptions::default())?;
+        Ok((report.row_stats, report.col_stats))
+    }
+
+    /// How many cells of each color the row clues call for, and how many the column clues do.
+    pub fn clue_color_totals(&self) -> (HashMap<Color, usize>, HashMap<Color, usize>) {
+        let totals = |lanes: &[Vec<C>]| {
+            let mut totals = HashMap::new();
+            for clue in lanes.iter().flatten() {
+                for idx in 0..clue.len() {
+                    *totals.entry(clue.color_at(idx)).or_insert(0) += 1;
+                }
+            }
+            totals
+        };
+        (totals(&self.rows), totals(&self.cols))
+    }
+
+    /// Lines whose clues don't fit, even when packed as tightly as possible, as
+    /// `(is_row, index, cells_needed)`.
+    pub fn overlong_lines(&self, rule: SeparationRule) -> Vec<(bool, usize, usize)> {
+        let min_packed_len = |clues: &[C]| {
+            clues.iter().map(|c| c.len()).sum::<usize>()
+                + clues
+                    .windows(2)
+                    .filter(|pair| rule.separates(&pair[0], &pair[1]))
+                    .count()
+        };
+
+        let mut res = vec![];
+        for (is_row, lines, lane_len) in [
+            (true, &self.rows, self.cols.len()),
+            (false, &self.cols, self.rows.len()),
+        ] {
+            for (idx, clues) in lines.iter().enumerate() {
+                let needed = min_packed_len(clues);
+                if needed > lane_len {
+                    res.push((is_row, idx, needed));
+                }
+            }
+        }
+        res
+    }
+
+    /// Fails, describing the first one, if any line's clues don't fit.
+    pub fn check_lines_fit(&self, rule: SeparationRule) -> anyhow::Result<()> {
+        if let Some((is_row, idx, needed)) = self.overlong_lines(rule).first() {
+            let (name, available) = if *is_row {
+                ("row", self.cols.len())
+            } else {
+                ("column", self.rows.len())
+            };
+            bail!(
+                "clues for {name} {} need at least {needed} cells, but there are only {available}",
+                idx + 1
+            );
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum DynPuzzle {
     Nono(Puzzle<Nono>),
@@ -284,8 +422,32 @@ pub trait PuzzleDynOps {
     fn plain_solve(&self) -> anyhow::Result<crate::grid_solve::Report> {
         self.solve(&SolveOptions::default())
     }
-    fn analyze_lines(&self, partial: &PartialSolution) -> (Vec<LineStatus>, Vec<LineStatus>);
-    fn settle_solution(&self, partial: &mut PartialSolution) -> anyhow::Result<()>;
+    fn analyze_lines(
+        &self,
+        partial: &PartialSolution,
+        only_color: Option<Color>,
+        rule: SeparationRule,
+    ) -> (Vec<LineStatus>, Vec<LineStatus>);
+    fn settle_solution(
+        &self,
+        partial: &mut PartialSolution,
+        rule: SeparationRule,
+    ) -> anyhow::Result<()>;
+    fn forced_lines(&self, rule: SeparationRule) -> (Vec<bool>, Vec<bool>);
+    fn hint(
+        &self,
+        partial: &PartialSolution,
+        rule: SeparationRule,
+    ) -> Option<(usize, usize, Color)>;
+    fn candidates(&self, partial: &PartialSolution, rule: SeparationRule) -> PartialSolution;
+    fn clue_span(
+        &self,
+        partial: &PartialSolution,
+        row: bool,
+        lane: usize,
+        clue_idx: usize,
+        rule: SeparationRule,
+    ) -> Option<(usize, usize)>;
 }
 
 impl<C: Clue> PuzzleDynOps for Puzzle<C> {
@@ -318,12 +480,48 @@ impl<C: Clue> PuzzleDynOps for Puzzle<C> {
         grid_solve::solve_grid(self, &mut None, options, &mut partial)
     }
 
-    fn analyze_lines(&self, partial: &PartialSolution) -> (Vec<LineStatus>, Vec<LineStatus>) {
-        grid_solve::analyze_lines(self, partial)
+    fn analyze_lines(
+        &self,
+        partial: &PartialSolution,
+        only_color: Option<Color>,
+        rule: SeparationRule,
+    ) -> (Vec<LineStatus>, Vec<LineStatus>) {
+        grid_solve::analyze_lines(self, partial, only_color, rule)
     }
 
-    fn settle_solution(&self, partial: &mut PartialSolution) -> anyhow::Result<()> {
-        grid_solve::settle_solution(self, partial)
+    fn settle_solution(
+        &self,
+        partial: &mut PartialSolution,
+        rule: SeparationRule,
+    ) -> anyhow::Result<()> {
+        grid_solve::settle_solution(self, partial, rule)
+    }
+
+    fn forced_lines(&self, rule: SeparationRule) -> (Vec<bool>, Vec<bool>) {
+        grid_solve::forced_lines(self, rule)
+    }
+
+    fn hint(
+        &self,
+        partial: &PartialSolution,
+        rule: SeparationRule,
+    ) -> Option<(usize, usize, Color)> {
+        grid_solve::hint(self, partial, rule)
+    }
+
+    fn candidates(&self, partial: &PartialSolution, rule: SeparationRule) -> PartialSolution {
+        grid_solve::candidates(self, partial, rule)
+    }
+
+    fn clue_span(
+        &self,
+        partial: &PartialSolution,
+        row: bool,
+        lane: usize,
+        clue_idx: usize,
+        rule: SeparationRule,
+    ) -> Option<(usize, usize)> {
+        grid_solve::clue_span(self, partial, row, lane, clue_idx, rule)
     }
 }
 
@@ -371,17 +569,65 @@ impl PuzzleDynOps for DynPuzzle {
         }
     }
 
-    fn analyze_lines(&self, partial: &PartialSolution) -> (Vec<LineStatus>, Vec<LineStatus>) {
+    fn analyze_lines(
+        &self,
+        partial: &PartialSolution,
+        only_color: Option<Color>,
+        rule: SeparationRule,
+    ) -> (Vec<LineStatus>, Vec<LineStatus>) {
+        match self {
+            DynPuzzle::Nono(p) => p.analyze_lines(partial, only_color, rule),
+            DynPuzzle::Triano(p) => p.analyze_lines(partial, only_color, rule),
+        }
+    }
+
+    fn settle_solution(
+        &self,
+        partial: &mut PartialSolution,
+        rule: SeparationRule,
+    ) -> anyhow::Result<()> {
         match self {
-            DynPuzzle::Nono(p) => p.analyze_lines(partial),
-            DynPuzzle::Triano(p) => p.analyze_lines(partial),
+            DynPuzzle::Nono(p) => p.settle_solution(partial, rule),
+            DynPuzzle::Triano(p) => p.settle_solution(partial, rule),
         }
     }
 
-    fn settle_solution(&self, partial: &mut PartialSolution) -> anyhow::Result<()> {
+    fn forced_lines(&self, rule: SeparationRule) -> (Vec<bool>, Vec<bool>) {
         match self {
-            DynPuzzle::Nono(p) => p.settle_solution(partial),
-            DynPuzzle::Triano(p) => p.settle_solution(partial),
+            DynPuzzle::Nono(p) => p.forced_lines(rule),
+            DynPuzzle::Triano(p) => p.forced_lines(rule),
+        }
+    }
+
+    fn hint(
+        &self,
+        partial: &PartialSolution,
+        rule: SeparationRule,
+    ) -> Option<(usize, usize, Color)> {
+        match self {
+            DynPuzzle::Nono(p) => p.hint(partial, rule),
+            DynPuzzle::Triano(p) => p.hint(partial, rule),
+        }
+    }
+
+    fn candidates(&self, partial: &PartialSolution, rule: SeparationRule) -> PartialSolution {
+        match self {
+            DynPuzzle::Nono(p) => p.candidates(partial, rule),
+            DynPuzzle::Triano(p) => p.candidates(partial, rule),
+        }
+    }
+
+    fn clue_span(
+        &self,
+        partial: &PartialSolution,
+        row: bool,
+        lane: usize,
+        clue_idx: usize,
+        rule: SeparationRule,
+    ) -> Option<(usize, usize)> {
+        match self {
+            DynPuzzle::Nono(p) => p.clue_span(partial, row, lane, clue_idx, rule),
+            DynPuzzle::Triano(p) => p.clue_span(partial, row, lane, clue_idx, rule),
         }
     }
 }
@@ -427,19 +673,57 @@ impl DynSolveCache {
     }
 
     pub fn solve(&mut self, p: &DynPuzzle) -> anyhow::Result<crate::grid_solve::Report> {
-        let options = crate::grid_solve::SolveOptions::default();
+        self.solve_with(p, &crate::grid_solve::SolveOptions::default())
+    }
+
+    pub fn solve_with(
+        &mut self,
+        p: &DynPuzzle,
+        options: &crate::grid_solve::SolveOptions,
+    ) -> anyhow::Result<crate::grid_solve::Report> {
         p.specialize(
-            |p| crate::grid_solve::solve(p, &mut self.nono_cache, &options),
-            |p| crate::grid_solve::solve(p, &mut self.triano_cache, &options),
+            |p| crate::grid_solve::solve(p, &mut self.nono_cache, options),
+            |p| crate::grid_solve::solve(p, &mut self.triano_cache, options),
         )
     }
+
+    /// How many line results are remembered.
+    pub fn cached_lines(&self) -> usize {
+        self.nono_cache.as_ref().map_or(0, |c| c.len())
+            + self.triano_cache.as_ref().map_or(0, |c| c.len())
+    }
+}
+
+/// The Manhattan rgb distance below which `quality_check` warns that colors are hard to tell
+/// apart.
+pub const SIMILAR_COLOR_THRESHOLD: u16 = 30;
+
+/// Pairs of colors (in order) whose rgb values are within `threshold` of each other, by Manhattan
+/// distance. Triangles with different corners are told apart by their shape, so they never count.
+pub fn similar_colors(palette: &HashMap<Color, ColorInfo>, threshold: u16) -> Vec<(Color, Color)> {
+    let mut res = vec![];
+    for (color_key, color) in palette {
+        for (color_key2, color2) in palette {
+            if color_key >= color_key2 || color.corner != color2.corner {
+                continue;
+            }
+            let (r, g, b) = color.rgb;
+            let (r2, g2, b2) = color2.rgb;
+            let distance = r.abs_diff(r2) as u16 + g.abs_diff(g2) as u16 + b.abs_diff(b2) as u16;
+            if distance < threshold {
+                res.push((*color_key, *color_key2));
+            }
+        }
+    }
+    res.sort();
+    res
 }
 
 impl Solution {
     pub fn quality_check(&self) -> Vec<String> {
         let mut problems = vec![];
-        let width = self.grid.len();
-        let height = self.grid.first().unwrap().len();
+        let width = self.x_size();
+        let height = self.y_size();
 
         let bg_squares_found: usize = self
             .grid
@@ -473,32 +757,41 @@ impl Solution {
             ))
         }
 
-        // Find similar colors
-        for (color_key, color) in &self.palette {
-            for (color_key2, color2) in &self.palette {
-                if color_key >= color_key2 {
-                    continue;
-                }
-                if color.corner != color2.corner && color.rgb == color2.rgb {
-                    continue; // Corners may be the same color.
-                }
-                let (r, g, b) = color.rgb;
-                let (r2, g2, b2) = color2.rgb;
-                if (r2 as i16 - r as i16).abs()
-                    + (g2 as i16 - g as i16).abs()
-                    + (b2 as i16 - b as i16).abs()
-                    < 30
-                {
-                    problems.push(format!(
-                        "very similar colors found: {:?} (\"{}\") and {:?} (\"{}\")",
-                        color.rgb, color.name, color2.rgb, color2.name
-                    ));
-                }
+        // Each row (and each column) should account for every foreground cell exactly once; if
+        // not, clue generation has a bug (most likely with Triano caps).
+        let mut histogram = self.color_histogram();
+        histogram.remove(&BACKGROUND);
+        let (row_totals, col_totals) = self
+            .to_puzzle()
+            .specialize(|p| p.clue_color_totals(), |p| p.clue_color_totals());
+        for (lines, totals) in [("row", row_totals), ("column", col_totals)] {
+            if totals != histogram {
+                problems.push(format!(
+                    "internal error: the {lines} clues don't add up to the picture \
+                    ({totals:?} vs. {histogram:?})"
+                ));
             }
         }
+
+        for (color, color2) in similar_colors(&self.palette, SIMILAR_COLOR_THRESHOLD) {
+            let (color, color2) = (&self.palette[&color], &self.palette[&color2]);
+            problems.push(format!(
+                "very similar colors found: {:?} (\"{}\") and {:?} (\"{}\")",
+                color.rgb, color.name, color2.rgb, color2.name
+            ));
+        }
         problems
     }
 
+    /// How many cells there are of each color.
+    pub fn color_histogram(&self) -> HashMap<Color, usize> {
+        let mut histogram = HashMap::new();
+        for color in self.grid.iter().flatten() {
+            *histogram.entry(*color).or_insert(0) += 1;
+        }
+        histogram
+    }
+
     pub fn blank_bw(x_size: usize, y_size: usize) -> Solution {
         Solution {
             clue_style: ClueStyle::Nono,
@@ -522,7 +815,7 @@ impl Solution {
     }
 
     pub fn y_size(&self) -> usize {
-        self.grid.first().unwrap().len()
+        self.grid.first().map_or(0, |col| col.len())
     }
 
     pub fn count_contiguous(&self, x: usize, y: usize) -> (usize, usize, usize, usize) {
@@ -566,6 +859,235 @@ impl Solution {
 
         (up, down, left, right)
     }
+
+    /// Like `count_contiguous`, but along the diagonals: how many cells of the same color continue
+    /// up-left, up-right, down-left, and down-right from `(x, y)`.
+    pub fn count_contiguous_diagonal(&self, x: usize, y: usize) -> (usize, usize, usize, usize) {
+        let target_color = self.grid[x][y];
+
+        let run = |dx: isize, dy: isize| {
+            let mut count = 0;
+            let (mut xi, mut yi) = (x, y);
+            loop {
+                let (Some(next_x), Some(next_y)) =
+                    (xi.checked_add_signed(dx), yi.checked_add_signed(dy))
+                else {
+                    break;
+                };
+                if next_x >= self.x_size()
+                    || next_y >= self.y_size()
+                    || self.grid[next_x][next_y] != target_color
+                {
+                    break;
+                }
+                count += 1;
+                (xi, yi) = (next_x, next_y);
+            }
+            count
+        };
+
+        (run(-1, -1), run(1, -1), run(-1, 1), run(1, 1))
+    }
+
+    /// A copy with the given dimensions. The existing cells stay put relative to `anchor`; new
+    /// space is filled with `BACKGROUND`, and anything that no longer fits is cropped off.
+    pub fn resized(&self, new_x_size: usize, new_y_size: usize, anchor: Anchor) -> Solution {
+        let (x_frac, y_frac) = anchor.halves();
+        // Where the old (0, 0) lands in the new grid; negative when cropping.
+        let offset = |old: usize, new: usize, frac: i64| (new as i64 - old as i64) * frac / 2;
+        let dx = offset(self.x_size(), new_x_size, x_frac);
+        let dy = offset(self.y_size(), new_y_size, y_frac);
+
+        let mut grid = vec![vec![BACKGROUND; new_y_size]; new_x_size];
+        for (x, col) in grid.iter_mut().enumerate() {
+            let Some(old_col) = usize::try_from(x as i64 - dx)
+                .ok()
+                .and_then(|old_x| self.grid.get(old_x))
+            else {
+                continue;
+            };
+            for (y, cell) in col.iter_mut().enumerate() {
+                if let Some(old_cell) = usize::try_from(y as i64 - dy)
+                    .ok()
+                    .and_then(|old_y| old_col.get(old_y))
+                {
+                    *cell = *old_cell;
+                }
+            }
+        }
+
+        Solution {
+            clue_style: self.clue_style,
+            palette: self.palette.clone(),
+            grid,
+        }
+    }
+
+    /// Trims off background-only rows and columns around the edges, and any colors that no longer
+    /// appear. An all-background picture is left alone.
+    pub fn cropped_to_content(&self) -> Solution {
+        let content = |x: usize, y: usize| self.grid[x][y] != BACKGROUND;
+        let xs: Vec<usize> = (0..self.x_size())
+            .filter(|x| (0..self.y_size()).any(|y| content(*x, y)))
+            .collect();
+        let ys: Vec<usize> = (0..self.y_size())
+            .filter(|y| (0..self.x_size()).any(|x| content(x, *y)))
+            .collect();
+        let (Some(x_lo), Some(x_hi), Some(y_lo), Some(y_hi)) =
+            (xs.first(), xs.last(), ys.first(), ys.last())
+        else {
+            return self.clone();
+        };
+
+        let mut cropped = Solution {
+            clue_style: self.clue_style,
+            palette: self.palette.clone(),
+            grid: self.grid[*x_lo..=*x_hi]
+                .iter()
+                .map(|col| col[*y_lo..=*y_hi].to_vec())
+                .collect(),
+        };
+        cropped.prune_palette();
+        cropped
+    }
+
+    /// Repeats the whole picture `across` times horizontally and `down` times vertically.
+    pub fn tiled(&self, across: usize, down: usize) -> Solution {
+        let grid = (0..self.x_size() * across)
+            .map(|x| self.grid[x % self.x_size()].repeat(down))
+            .collect();
+        Solution {
+            clue_style: self.clue_style,
+            palette: self.palette.clone(),
+            grid,
+        }
+    }
+
+    /// Renumbers the colors to be contiguous (`BACKGROUND` stays 0, and the rest keep their
+    /// relative order), rewriting the grid to match. `UNSOLVED` is left alone.
+    pub fn normalize_palette(&mut self) {
+        let mut colors: Vec<Color> = self
+            .palette
+            .keys()
+            .copied()
+            .filter(|c| *c != BACKGROUND && *c != UNSOLVED)
+            .collect();
+        colors.sort();
+
+        // At most 254 colors remain, so the new indices can't reach `UNSOLVED`.
+        let renumbering: HashMap<Color, Color> = colors
+            .into_iter()
+            .enumerate()
+            .map(|(i, old)| (old, Color(i as u8 + 1)))
+            .collect();
+        let renumber = |c: Color| renumbering.get(&c).copied().unwrap_or(c);
+
+        self.palette = self
+            .palette
+            .drain()
+            .map(|(color, ci)| {
+                let color = renumber(color);
+                (color, ColorInfo { color, ..ci })
+            })
+            .collect();
+        for cell in self.grid.iter_mut().flatten() {
+            *cell = renumber(*cell);
+        }
+    }
+
+    /// Drops palette entries that no cell uses (but always keeps `BACKGROUND`, and the triangle
+    /// corners, which "New color" can't make again).
+    pub fn prune_palette(&mut self) {
+        let used: HashSet<Color> = self.grid.iter().flatten().copied().collect();
+        self.palette.retain(|color, ci| {
+            *color == BACKGROUND || ci.corner.is_some() || used.contains(color)
+        });
+    }
+
+    /// Repaints every `a` cell as `b` and vice versa; the palette is unchanged.
+    pub fn swap_colors(&mut self, a: Color, b: Color) {
+        for cell in self.grid.iter_mut().flatten() {
+            if *cell == a {
+                *cell = b;
+            } else if *cell == b {
+                *cell = a;
+            }
+        }
+    }
+
+    /// Whether the two pictures look the same, regardless of how their palettes are keyed (or
+    /// what unused colors they contain).
+    pub fn visually_eq(&self, other: &Solution) -> bool {
+        if self.x_size() != other.x_size() || self.y_size() != other.y_size() {
+            return false;
+        }
+        let look = |s: &Solution, c: &Color| s.palette.get(c).map(|ci| (ci.rgb, ci.corner));
+        self.grid
+            .iter()
+            .flatten()
+            .zip(other.grid.iter().flatten())
+            .all(|(lhs, rhs)| look(self, lhs) == look(other, rhs))
+    }
+}
+
+/// Makes every `ColorInfo.ch` distinct and safe to write into a chargrid (i.e., it'll be read
+/// back as the same kind of color). Characters that are already fine are left alone; the rest are
+/// replaced in `Color` order, so the result only depends on the palette.
+pub fn assign_palette_chars(solution: &mut Solution) {
+    let all_corner_chars: Vec<char> = [(false, false), (false, true), (true, true), (true, false)]
+        .into_iter()
+        .flat_map(|(upper, left)| Corner { upper, left }.chars())
+        .collect();
+    let mut fallbacks = ('a'..='z')
+        .chain('A'..='Z')
+        .chain('2'..='9')
+        .chain('\u{100}'..='\u{24F}');
+
+    let mut used = HashSet::<char>::new();
+    let mut colors: Vec<Color> = solution.palette.keys().copied().collect();
+    colors.sort();
+
+    for color in colors {
+        let ci = solution.palette.get_mut(&color).unwrap();
+        let (corner, rgb) = (ci.corner, ci.rgb);
+        let is_safe = |ch: char, used: &HashSet<char>| {
+            if used.contains(&ch) || (ch.is_whitespace() && color != BACKGROUND) || ch.is_control()
+            {
+                return false;
+            }
+            if color == BACKGROUND {
+                return BACKGROUND_CHARS.contains(&ch);
+            }
+            if let Some(corner) = corner {
+                return corner.chars().contains(&ch);
+            }
+            !BACKGROUND_CHARS.contains(&ch)
+                && !all_corner_chars.contains(&ch)
+                && (rgb == (0, 0, 0) || !BLACK_CHARS.contains(&ch))
+        };
+
+        if !is_safe(ci.ch, &used) {
+            let preferred: Vec<char> = if color == BACKGROUND {
+                BACKGROUND_CHARS.to_vec()
+            } else if let Some(corner) = corner {
+                corner.chars().to_vec()
+            } else {
+                vec![]
+            };
+            ci.ch = match preferred.into_iter().find(|&ch| is_safe(ch, &used)) {
+                Some(ch) => ch,
+                // A corner color can land here if there are several in the same orientation.
+                None => fallbacks
+                    .find(|&ch| {
+                        !used.contains(&ch)
+                            && !BACKGROUND_CHARS.contains(&ch)
+                            && !BLACK_CHARS.contains(&ch)
+                    })
+                    .expect("too many colors!"),
+            };
+        }
+        used.insert(ci.ch);
+    }
 }
 
 #[derive(Clone, Copy, Debug, clap::ValueEnum, Default, PartialEq, Eq)]
@@ -583,8 +1105,16 @@ pub enum NonogramFormat {
     CharGrid,
     /// Number Loom's format, mostly aimed at making copy-and-paste easier.
     Woven,
+    /// The same information as `woven`, but as human-readable (and diffable) JSON.
+    Json,
     /// (Export-only.) An HTML representation of a puzzle.
     Html,
+    /// The clues as plain text, one line per row or column. Handy for typing in a puzzle from a
+    /// magazine. (Import only supports nonograms.)
+    ClueList,
+    /// A simple interchange format used by several other solvers: dimensions, then row clues,
+    /// then column clues. Black-and-white only.
+    Nin,
 }
 
 #[derive(Clone, Copy, Debug, clap::ValueEnum, Default, PartialEq, Eq, Serialize, Deserialize)]
@@ -596,20 +1126,57 @@ pub enum ClueStyle {
 
 // `path` may be either a filename or a path
 pub fn infer_format(path: &str, format_arg: Option<NonogramFormat>) -> NonogramFormat {
-    if let Some(format) = format_arg {
-        return format;
-    }
+    format_arg
+        .or_else(|| format_from_extension(path))
+        .unwrap_or(NonogramFormat::CharGrid)
+}
 
+pub fn format_from_extension(path: &str) -> Option<NonogramFormat> {
     let ext = path.rsplit_once('.').map(|x| x.1);
 
     match ext {
-        Some("png") | Some("bmp") | Some("gif") => NonogramFormat::Image,
-        Some("xml") | Some("pbn") => NonogramFormat::Webpbn,
-        Some("g") => NonogramFormat::Olsak,
-        Some("html") => NonogramFormat::Html,
-        Some("txt") => NonogramFormat::CharGrid,
-        Some("woven") => NonogramFormat::Woven,
-        _ => NonogramFormat::CharGrid,
+        Some("png") | Some("bmp") | Some("gif") => Some(NonogramFormat::Image),
+        Some("xml") | Some("pbn") => Some(NonogramFormat::Webpbn),
+        Some("g") => Some(NonogramFormat::Olsak),
+        Some("html") => Some(NonogramFormat::Html),
+        Some("txt") => Some(NonogramFormat::CharGrid),
+        Some("woven") => Some(NonogramFormat::Woven),
+        Some("json") => Some(NonogramFormat::Json),
+        Some("clues") => Some(NonogramFormat::ClueList),
+        Some("nin") => Some(NonogramFormat::Nin),
+        _ => None,
+    }
+}
+
+/// Whether `text` starts like a list of clues: "Rows", "Row 1: 3 1", and so on.
+fn is_clue_text(text: &str) -> bool {
+    let first_line = text.lines().next().unwrap_or("").to_lowercase();
+    ["row", "column", "cols"]
+        .iter()
+        .any(|label| first_line.starts_with(label))
+}
+
+/// Guesses the format from the file contents, for when there's no extension to go on (e.g. stdin).
+pub fn sniff_format(bytes: &[u8]) -> NonogramFormat {
+    const MAGIC: [&[u8]; 4] = [b"\x89PNG", b"GIF87a", b"GIF89a", b"BM"];
+    if MAGIC.iter().any(|magic| bytes.starts_with(magic)) {
+        return NonogramFormat::Image;
+    }
+
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    if text.starts_with("<?xml") || text.starts_with("<puzzleset") {
+        NonogramFormat::Webpbn
+    } else if text.starts_with("#d") {
+        NonogramFormat::Olsak
+    } else if text.starts_with("WOVEN") {
+        NonogramFormat::Woven
+    } else if text.starts_with('{') {
+        NonogramFormat::Json
+    } else if is_clue_text(text) {
+        NonogramFormat::ClueList
+    } else {
+        NonogramFormat::CharGrid
     }
 }
 
@@ -624,11 +1191,40 @@ pub struct Document {
     pub author: String,
     pub id: String,
     pub license: String,
+    /// Problems found while loading that didn't stop the load (reported by `quality_check`).
+    pub load_warnings: Vec<String>,
+}
+
+fn clues_match<C: Clue>(stored: &Puzzle<C>, derived: &Puzzle<C>) -> anyhow::Result<()> {
+    for (name, stored_lanes, derived_lanes) in [
+        ("row", &stored.rows, &derived.rows),
+        ("column", &stored.cols, &derived.cols),
+    ] {
+        if stored_lanes.len() != derived_lanes.len() {
+            bail!(
+                "there are clues for {} {name}s, but the picture has {}",
+                stored_lanes.len(),
+                derived_lanes.len()
+            );
+        }
+        for (idx, (stored_clues, derived_clues)) in
+            stored_lanes.iter().zip(derived_lanes).enumerate()
+        {
+            if stored_clues != derived_clues {
+                bail!(
+                    "the clues for {name} {} are {stored_clues:?}, but the picture has \
+                    {derived_clues:?}",
+                    idx + 1
+                );
+            }
+        }
+    }
+    Ok(())
 }
 
 impl Document {
-    pub fn quality_check(&mut self) -> Vec<String> {
-        let mut problems = vec![];
+    pub fn quality_check(&mut self, rule: SeparationRule) -> Vec<String> {
+        let mut problems = self.load_warnings.clone();
         if self.author.is_empty() {
             problems.push("missing author".to_string());
         }
@@ -638,20 +1234,57 @@ impl Document {
         }
 
         let puzzle = self.puzzle();
-        match puzzle.plain_solve() {
+        let (width, height) = (puzzle.cols(), puzzle.rows());
+        let overlong = puzzle.specialize(|p| p.overlong_lines(rule), |p| p.overlong_lines(rule));
+        for (is_row, idx, needed) in overlong {
+            let (name, available) = if is_row {
+                ("row", width)
+            } else {
+                ("column", height)
+            };
+            problems.push(format!(
+                "clues for {name} {} need at least {needed} cells, but there are only {available}",
+                idx + 1
+            ));
+        }
+
+        let options = SolveOptions {
+            separation: rule,
+            ..SolveOptions::default()
+        };
+        match puzzle.solve(&options) {
             Ok(report) => {
-                if report.cells_left > 0 {
-                    problems.push(format!("puzzle is not solveable with line-logic"));
+                if let Some((x, y, _)) = report.ambiguous.first() {
+                    problems.push(format!(
+                        "puzzle is not solveable with line-logic ({} cells left, starting at ({x}, {y}))",
+                        report.ambiguous.len()
+                    ));
                 }
             }
-            Err(_) => {
-                problems.push("puzzle is self-contradictory".to_string());
-            }
+            Err(e) => match grid_solve::Contradiction::find(&e) {
+                Some(contradiction) => {
+                    problems.push(format!("puzzle is self-contradictory: {contradiction}"))
+                }
+                None => problems.push("puzzle is self-contradictory".to_string()),
+            },
         }
 
         problems
     }
 
+    /// If the document has both clues and a picture, checks that the picture produces exactly
+    /// those clues.
+    pub fn verify_consistent(&self) -> anyhow::Result<()> {
+        let (Some(puzzle), Some(solution)) = (&self.p, &self.s) else {
+            return Ok(());
+        };
+        match (puzzle, &solution.to_puzzle()) {
+            (DynPuzzle::Nono(stored), DynPuzzle::Nono(derived)) => clues_match(stored, derived),
+            (DynPuzzle::Triano(stored), DynPuzzle::Triano(derived)) => clues_match(stored, derived),
+            _ => bail!("the clues and the picture are different styles of puzzle"),
+        }
+    }
+
     pub fn new(
         puzzle: Option<DynPuzzle>,
         solution: Option<Solution>,
@@ -672,6 +1305,18 @@ impl Document {
             author: author.unwrap_or_default(),
             id: id.unwrap_or_default(),
             license: license.unwrap_or_default(),
+            load_warnings: vec![],
+        }
+    }
+
+    /// If the picture doesn't produce the clues, it's probably the picture that's wrong (the
+    /// clues are what the file is for), so this drops it and records a warning.
+    pub fn drop_inconsistent_solution(&mut self) {
+        if let Err(e) = self.verify_consistent() {
+            self.load_warnings.push(format!(
+                "ignored the goal, which doesn't match the clues: {e:#}"
+            ));
+            self.s = None;
         }
     }
 
@@ -685,9 +1330,26 @@ impl Document {
             return Ok(self.title.clone());
         }
 
+        let hash = self.content_hash().to_le_bytes();
+
+        Ok(mnemonic::to_string(&hash[0..4]))
+    }
+
+    /// Identifies the picture (or, failing that, the clues), ignoring the title and other metadata.
+    pub fn content_hash(&self) -> u64 {
         let mut hasher = std::hash::DefaultHasher::new();
 
         if let Some(solution) = self.try_solution() {
+            // The same cells, read at a different width or in different colors, are a different
+            // picture.
+            (solution.x_size(), solution.y_size()).hash(&mut hasher);
+            let mut palette: Vec<_> = solution
+                .palette
+                .values()
+                .map(|ci| (ci.color, ci.rgb, ci.corner))
+                .collect();
+            palette.sort_by_key(|(color, _, _)| *color);
+            palette.hash(&mut hasher);
             for row in &solution.grid {
                 for color in row {
                     color.hash(&mut hasher);
@@ -698,9 +1360,7 @@ impl Document {
             puzzle.hash(&mut hasher);
         }
 
-        let hash = hasher.finish().to_le_bytes();
-
-        Ok(mnemonic::to_string(&hash[0..4]))
+        hasher.finish()
     }
 
     #[allow(dead_code)]
@@ -776,6 +1436,7 @@ impl Document {
             author: "".to_string(),
             id: "".to_string(),
             license: "".to_string(),
+            load_warnings: vec![],
         }
     }
 
@@ -789,6 +1450,401 @@ impl Document {
             author: "".to_string(),
             id: "".to_string(),
             license: "".to_string(),
+            load_warnings: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // In rows, ◢ is a front cap and ◣ is a back cap.
+    const FRONT: Color = Color(6);
+    const BACK: Color = Color(5);
+
+    fn t(
+        front_cap: Option<Color>,
+        body_color: u8,
+        body_len: u16,
+        back_cap: Option<Color>,
+    ) -> Triano {
+        Triano {
+            front_cap,
+            body_color: if body_len == 0 {
+                BACKGROUND
+            } else {
+                Color(body_color)
+            },
+            body_len,
+            back_cap,
+        }
+    }
+
+    fn cells(clue: &Triano) -> Vec<Color> {
+        (0..clue.len()).map(|i| clue.color_at(i)).collect()
+    }
+
+    #[test]
+    fn triano_separation() {
+        let body = t(None, 1, 2, None);
+        let other_body = t(None, 2, 1, None);
+        let front_only = t(Some(FRONT), 1, 0, None);
+        let back_only = t(None, 1, 0, Some(BACK));
+        let front_body = t(Some(FRONT), 1, 2, None);
+        let body_back = t(None, 1, 2, Some(BACK));
+
+        // body-to-body
+        assert!(body.must_be_separated_from(&body));
+        assert!(!body.must_be_separated_from(&other_body));
+        // cap-to-body
+        assert!(!body_back.must_be_separated_from(&body));
+        assert!(front_only.must_be_separated_from(&other_body));
+        // body-to-cap
+        assert!(!body.must_be_separated_from(&front_body));
+        assert!(body.must_be_separated_from(&back_only));
+        // cap-to-cap
+        assert!(front_only.must_be_separated_from(&back_only));
+        assert!(!back_only.must_be_separated_from(&front_only));
+        assert!(!front_only.must_be_separated_from(&front_only));
+        assert!(!back_only.must_be_separated_from(&back_only));
+    }
+
+    /// Two blocks can touch exactly when clue generation would keep them apart.
+    #[test]
+    fn triano_separation_matches_clue_generation() {
+        let samples = [
+            t(None, 1, 2, None),
+            t(None, 2, 1, None),
+            t(Some(FRONT), 1, 0, None),
+            t(None, 1, 0, Some(BACK)),
+            t(Some(FRONT), 1, 2, None),
+            t(None, 1, 2, Some(BACK)),
+            t(Some(FRONT), 2, 1, Some(BACK)),
+        ];
+
+        let mut palette = crate::import::triano_palette();
+        palette.insert(
+            Color(2),
+            ColorInfo {
+                ch: 'r',
+                name: "red".to_string(),
+                rgb: (255, 0, 0),
+                color: Color(2),
+                corner: None,
+            },
+        );
+
+        for a in &samples {
+            for b in &samples {
+                let row = [cells(a), cells(b)].concat();
+                let solution = Solution {
+                    clue_style: ClueStyle::Triano,
+                    palette: palette.clone(),
+                    grid: row.iter().map(|c| vec![*c]).collect(),
+                };
+                let generated = solution_to_triano_puzzle(&solution).rows[0].clone();
+
+                assert_eq!(
+                    generated == vec![*a, *b],
+                    !a.must_be_separated_from(b),
+                    "{a:?} then {b:?} generated {generated:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn to_partial_with_unknown_colors() {
+        let mut solution = Solution::blank_bw(2, 1);
+        solution.grid[0][0] = Color(1);
+        solution.grid[1][0] = UNSOLVED;
+
+        let partial = solution.to_partial();
+        assert!(partial[[0, 0]].is_known_to_be(Color(1)));
+        assert!(!partial[[0, 1]].is_known());
+
+        let partial = solution.to_partial_with_unknown(&[BACKGROUND, UNSOLVED]);
+        assert!(partial[[0, 0]].is_known_to_be(Color(1)));
+        assert!(!partial[[0, 1]].is_known());
+
+        let partial = solution.to_partial_with_unknown(&[Color(1)]);
+        assert!(!partial[[0, 0]].is_known());
+        assert!(partial[[0, 1]].is_known_to_be(UNSOLVED));
+    }
+
+    #[test]
+    fn similar_colors_respect_corners() {
+        // Four corners that share black with each other and with the plain black square:
+        let mut palette = crate::import::triano_palette();
+        assert_eq!(similar_colors(&palette, SIMILAR_COLOR_THRESHOLD), vec![]);
+
+        palette.insert(
+            Color(2),
+            ColorInfo {
+                ch: 'k',
+                name: "near-black".to_string(),
+                rgb: (10, 10, 5),
+                color: Color(2),
+                corner: None,
+            },
+        );
+        assert_eq!(
+            similar_colors(&palette, SIMILAR_COLOR_THRESHOLD),
+            vec![(Color(1), Color(2))]
+        );
+        assert_eq!(similar_colors(&palette, 25), vec![]);
+    }
+
+    #[test]
+    fn visually_eq_ignores_color_keys() {
+        let red = |color| ColorInfo {
+            ch: 'r',
+            name: "red".to_string(),
+            rgb: (255, 0, 0),
+            color,
+            corner: None,
+        };
+        let lhs = Solution {
+            clue_style: ClueStyle::Nono,
+            palette: HashMap::from([
+                (BACKGROUND, ColorInfo::default_bg()),
+                (Color(1), red(Color(1))),
+            ]),
+            grid: vec![vec![BACKGROUND, Color(1)]],
+        };
+        let mut rhs = Solution {
+            clue_style: ClueStyle::Nono,
+            palette: HashMap::from([
+                (BACKGROUND, ColorInfo::default_bg()),
+                (Color(1), ColorInfo::default_fg(Color(1))), // unused
+                (Color(2), red(Color(2))),
+            ]),
+            grid: vec![vec![BACKGROUND, Color(2)]],
+        };
+
+        assert_ne!(lhs, rhs);
+        assert!(lhs.visually_eq(&rhs));
+
+        rhs.grid[0][1] = Color(1);
+        assert!(!lhs.visually_eq(&rhs));
+    }
+
+    #[test]
+    fn prune_palette_keeps_background() {
+        let mut solution = Solution::blank_bw(2, 2);
+        solution
+            .palette
+            .insert(Color(2), ColorInfo::default_fg(Color(2)));
+        solution.grid[1][1] = Color(2);
+        solution.palette.insert(
+            Color(3),
+            ColorInfo {
+                corner: Some(Corner {
+                    upper: true,
+                    left: true,
+                }),
+                ..ColorInfo::default_fg(Color(3))
+            },
+        );
+
+        solution.prune_palette();
+
+        // The unused corner stays:
+        let mut remaining: Vec<Color> = solution.palette.keys().copied().collect();
+        remaining.sort();
+        assert_eq!(remaining, vec![BACKGROUND, Color(2), Color(3)]);
+    }
+
+    #[test]
+    fn overlong_lines_are_reported() {
+        let black = |count| Nono {
+            color: Color(1),
+            count,
+        };
+        let puzzle = Puzzle {
+            palette: Solution::blank_bw(1, 1).palette,
+            rows: vec![vec![black(3), black(2)], vec![black(5)]],
+            cols: vec![vec![black(1)]; 5],
+        };
+        assert_eq!(
+            puzzle.overlong_lines(SeparationRule::SameColor),
+            vec![(true, 0, 6)]
+        );
+        assert_eq!(
+            puzzle.overlong_lines(SeparationRule::Always),
+            vec![(true, 0, 6)]
+        );
+        assert_eq!(puzzle.overlong_lines(SeparationRule::Never), vec![]);
+
+        let mut doc = Document::from_puzzle(DynPuzzle::Nono(puzzle), "long.clues".to_string());
+        assert!(
+            doc.quality_check(SeparationRule::SameColor).contains(
+                &"clues for row 1 need at least 6 cells, but there are only 5".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn normalize_palette_is_contiguous() {
+        let mut solution = Solution::blank_bw(3, 1);
+        solution.palette.remove(&Color(1));
+        for idx in [7, 3] {
+            solution
+                .palette
+                .insert(Color(idx), ColorInfo::default_fg(Color(idx)));
         }
+        solution.grid = vec![vec![Color(7)], vec![BACKGROUND], vec![Color(3)]];
+
+        solution.normalize_palette();
+
+        let mut colors: Vec<Color> = solution.palette.keys().copied().collect();
+        colors.sort();
+        assert_eq!(colors, vec![BACKGROUND, Color(1), Color(2)]);
+        assert_eq!(
+            solution.grid,
+            vec![vec![Color(2)], vec![BACKGROUND], vec![Color(1)]]
+        );
+        assert!(solution.palette.iter().all(|(c, ci)| *c == ci.color));
+    }
+
+    #[test]
+    fn resized_keeps_anchor() {
+        let mut solution = Solution::blank_bw(2, 2);
+        solution.grid[0][0] = Color(1);
+
+        let grown = solution.resized(4, 3, Anchor::BottomRight);
+        assert_eq!((grown.x_size(), grown.y_size()), (4, 3));
+        assert_eq!(grown.grid[2][1], Color(1));
+
+        let centered = solution.resized(4, 4, Anchor::Center);
+        assert_eq!(centered.grid[1][1], Color(1));
+        assert_eq!(
+            centered
+                .grid
+                .iter()
+                .flatten()
+                .filter(|c| **c == Color(1))
+                .count(),
+            1
+        );
+
+        let cropped = solution.resized(1, 2, Anchor::Right);
+        assert_eq!(cropped.grid, vec![vec![BACKGROUND, BACKGROUND]]);
+        let cropped = solution.resized(1, 1, Anchor::TopLeft);
+        assert_eq!(cropped.grid, vec![vec![Color(1)]]);
+
+        assert_eq!(solution.resized(2, 2, Anchor::Center).grid, solution.grid);
+    }
+
+    #[test]
+    fn swap_colors_inverts() {
+        let mut solution = Solution::blank_bw(3, 1);
+        solution
+            .palette
+            .insert(Color(2), ColorInfo::default_fg(Color(2)));
+        solution.grid = vec![vec![Color(1)], vec![BACKGROUND], vec![Color(2)]];
+
+        solution.swap_colors(BACKGROUND, Color(1));
+
+        assert_eq!(
+            solution.grid,
+            vec![vec![BACKGROUND], vec![Color(1)], vec![Color(2)]]
+        );
+    }
+
+    #[test]
+    fn tiled_repeats() {
+        let mut solution = Solution::blank_bw(2, 1);
+        solution.grid[0][0] = Color(1);
+
+        let tiled = solution.tiled(2, 3);
+        assert_eq!((tiled.x_size(), tiled.y_size()), (4, 3));
+        for (x, col) in tiled.grid.iter().enumerate() {
+            let expected = if x % 2 == 0 { Color(1) } else { BACKGROUND };
+            assert!(col.iter().all(|c| *c == expected));
+        }
+        assert_eq!(solution.tiled(1, 1).grid, solution.grid);
+    }
+
+    #[test]
+    fn triano_clue_totals_match_histogram() {
+        let solution = crate::import::char_grid_to_solution("◢#◣.\n##◤.\n◥..◢\n");
+        assert_eq!(solution.clue_style, ClueStyle::Triano);
+
+        let mut histogram = solution.color_histogram();
+        assert_eq!(histogram.values().sum::<usize>(), 12);
+        histogram.remove(&BACKGROUND);
+        let puzzle = solution.to_puzzle();
+        let puzzle = puzzle.assume_triano();
+        assert_eq!(puzzle.clue_color_totals(), (histogram.clone(), histogram));
+        assert!(
+            !solution
+                .quality_check()
+                .iter()
+                .any(|problem| problem.contains("don't add up"))
+        );
+    }
+
+    #[test]
+    fn cropped_to_content_trims_background() {
+        let mut solution = Solution::blank_bw(5, 4);
+        solution
+            .palette
+            .insert(Color(2), ColorInfo::default_fg(Color(2)));
+        solution.grid[1][1] = Color(1);
+        solution.grid[3][2] = Color(1);
+
+        let cropped = solution.cropped_to_content();
+        assert_eq!((cropped.x_size(), cropped.y_size()), (3, 2));
+        assert_eq!(cropped.grid[0][0], Color(1));
+        assert_eq!(cropped.grid[2][1], Color(1));
+        assert!(!cropped.palette.contains_key(&Color(2)));
+
+        let blank = Solution::blank_bw(2, 2);
+        assert_eq!(blank.cropped_to_content().grid, blank.grid);
+    }
+
+    #[test]
+    fn contiguous_diagonals() {
+        let solution = crate::import::char_grid_to_solution("#..#\n.##.\n.##.\n#..#\n");
+        assert_eq!(solution.count_contiguous_diagonal(1, 1), (1, 0, 0, 2));
+        assert_eq!(solution.count_contiguous_diagonal(2, 1), (0, 1, 2, 0));
+        assert_eq!(solution.count_contiguous_diagonal(0, 0), (0, 0, 0, 3));
+        assert_eq!(solution.count_contiguous_diagonal(1, 0), (0, 0, 1, 0));
+    }
+
+    #[test]
+    fn verify_consistent_catches_mismatches() {
+        let solution = crate::import::char_grid_to_solution("#.#\n.##\n");
+        let puzzle = solution.to_puzzle();
+        let doc = |puzzle, solution| {
+            Document::new(
+                Some(puzzle),
+                Some(solution),
+                "test".to_string(),
+                None,
+                None,
+                None,
+                None,
+                None,
+            )
+        };
+        assert!(
+            doc(puzzle.clone(), solution.clone())
+                .verify_consistent()
+                .is_ok()
+        );
+
+        let mut altered = solution.clone();
+        altered.grid[1][0] = Color(1);
+        let err = doc(puzzle.clone(), altered)
+            .verify_consistent()
+            .unwrap_err();
+        assert!(err.to_string().contains("row 1"));
+
+        let mut triano = solution.clone();
+        triano.clue_style = ClueStyle::Triano;
+        assert!(doc(puzzle, triano).verify_consistent().is_err());
     }
 }
@@ -4,7 +4,7 @@ mod tests {
 
     use ndarray::Array1;
     use number_loom::import::{solution_to_puzzle, solution_to_triano_puzzle};
-    use number_loom::line_solve::{Cell, exhaust_line, scrub_line, skim_line};
+    use number_loom::line_solve::{Cell, SeparationRule, exhaust_line, scrub_line, skim_line};
     use number_loom::puzzle::{
         BACKGROUND, Clue, ClueStyle, Color, ColorInfo, Corner, Puzzle, Solution,
     };
@@ -106,7 +106,11 @@ mod tests {
         let mut sc_partial_solution = partial.clone();
         let mut sk_partial_solution = partial.clone();
 
-        match skim_line(clues, &mut sk_partial_solution.view_mut()) {
+        match skim_line(
+            clues,
+            &mut sk_partial_solution.view_mut(),
+            SeparationRule::default(),
+        ) {
             Ok(_) => {
                 for j in 0..line.len() {
                     if !sk_partial_solution[j].can_be(line[j]) {
@@ -125,7 +129,11 @@ mod tests {
             }
         }
 
-        match scrub_line(clues, &mut sk_partial_solution.view_mut()) {
+        match scrub_line(
+            clues,
+            &mut sk_partial_solution.view_mut(),
+            SeparationRule::default(),
+        ) {
             Ok(_) => {
                 for j in 0..line.len() {
                     if !sk_partial_solution[j].can_be(line[j]) {
@@ -144,7 +152,11 @@ mod tests {
             }
         }
 
-        match exhaust_line(clues, &mut sc_partial_solution.view_mut()) {
+        match exhaust_line(
+            clues,
+            &mut sc_partial_solution.view_mut(),
+            SeparationRule::default(),
+        ) {
             Ok(_) => {
                 for j in 0..line.len() {
                     if !sc_partial_solution[j].can_be(line[j]) {
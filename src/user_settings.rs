@@ -14,7 +14,16 @@ pub mod consts {
     pub const SOLVER_ANALYZE_LINES: &str = "solver.analyze_lines";
     pub const SOLVER_DETECT_ERRORS: &str = "solver.detect_errors";
     pub const SOLVER_INFER_BACKGROUND: &str = "solver.infer_background";
+    pub const SOLVER_RENDER_STYLE: &str = "solver.render_style";
+    /// Prefix; the full key is `solver.progress.<document id>`.
+    pub const SOLVER_PROGRESS: &str = "solver.progress";
     pub const EDITOR_AUTHOR_NAME: &str = "editor.author_name";
+    pub const EDITOR_LICENSE: &str = "editor.license";
+    /// JSON list of `(name, source)` pairs, newest first.
+    pub const EDITOR_RECENT_FILES: &str = "editor.recent_files";
+    pub const EDITOR_NEW_X_SIZE: &str = "editor.new_x_size";
+    pub const EDITOR_NEW_Y_SIZE: &str = "editor.new_y_size";
+    pub const EDITOR_NEW_CLUE_STYLE: &str = "editor.new_clue_style";
 }
 
 